@@ -0,0 +1,257 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use log::debug;
+
+/// The symbols in a Breakpad text symbol file, with addresses relative to the module's base address.
+#[derive(Debug, Default)]
+pub struct BreakpadSymbols {
+    /// The names of the functions in the module, from `FUNC` and `PUBLIC` records.
+    /// Where a `FUNC` record and a `PUBLIC` record are at the same address,
+    /// the name of the `FUNC` record is used.
+    pub functions: BTreeMap<u64, String>,
+    /// The source file and line number at the start of each line record.
+    pub lines: Vec<(u64, String, u32)>,
+}
+
+/// The name of the Breakpad symbol file for a module's debug file,
+/// following the convention of Breakpad's `dump_syms` (e.g. `foo.sym` for `foo.pdb`, `libfoo.so.sym` for `libfoo.so`).
+fn symbol_file_name(debug_file: &str) -> String {
+    match debug_file
+        .len()
+        .checked_sub(4)
+        .filter(|&extension_start| debug_file[extension_start..].eq_ignore_ascii_case(".pdb"))
+    {
+        Some(extension_start) => format!("{}.sym", &debug_file[..extension_start]),
+        None => format!("{}.sym", debug_file),
+    }
+}
+
+/// Find the Breakpad symbol file for a module in a list of local symbol directories,
+/// laid out as `<debug file>/<debug identifier>/<debug file>.sym` (the layout used by Breakpad's `symupload`
+/// and by Mozilla's and Chromium's symbol servers).
+///
+/// Only a symbol file whose `MODULE` record has a matching debug identifier is returned.
+pub fn find_breakpad_symbols(
+    search_paths: &[PathBuf],
+    debug_file: &str,
+    debug_id: &str,
+) -> Option<PathBuf> {
+    let symbol_file_name = symbol_file_name(debug_file);
+    for search_path in search_paths {
+        let candidate = search_path
+            .join(debug_file)
+            .join(debug_id)
+            .join(&symbol_file_name);
+        if !candidate.is_file() {
+            continue;
+        }
+        match read_module_debug_id(&candidate) {
+            Ok(Some(candidate_debug_id)) if candidate_debug_id.eq_ignore_ascii_case(debug_id) => {
+                return Some(candidate);
+            }
+            Ok(candidate_debug_id) => debug!(
+                "Skipping Breakpad symbol file {} with debug identifier {:?}, which does not match {}",
+                candidate.display(),
+                candidate_debug_id,
+                debug_id,
+            ),
+            Err(error) => debug!(
+                "Could not read Breakpad symbol file {}: {}",
+                candidate.display(),
+                error
+            ),
+        }
+    }
+    None
+}
+
+/// Read the debug identifier from the `MODULE <os> <arch> <id> <name>` record
+/// on the first line of a Breakpad symbol file.
+fn read_module_debug_id(path: &Path) -> io::Result<Option<String>> {
+    let mut first_line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut first_line)?;
+    let mut fields = first_line.split_whitespace();
+    if fields.next() != Some("MODULE") {
+        return Ok(None);
+    }
+    Ok(fields.nth(2).map(String::from))
+}
+
+/// Read the `FILE`, `FUNC`, `PUBLIC`, and line records of a Breakpad symbol file.
+/// Records which don't affect symbolization (e.g. `STACK` and `INLINE`) are ignored.
+pub fn read_breakpad_symbols(path: &Path) -> io::Result<BreakpadSymbols> {
+    let mut symbols = BreakpadSymbols::default();
+    let mut files = HashMap::new();
+    let mut public_symbols = Vec::new();
+    let mut line_records = Vec::new();
+    let mut in_function = false;
+
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        let (record_type, rest) = line.split_once(' ').unwrap_or((&line, ""));
+        match record_type {
+            // FILE <index> <name>
+            "FILE" => {
+                in_function = false;
+                if let Some((index, name)) = rest.split_once(' ') {
+                    if let Ok(index) = index.parse::<u32>() {
+                        files.insert(index, name.to_string());
+                    }
+                }
+            }
+            // FUNC [m] <address> <size> <parameter size> <name>
+            "FUNC" => {
+                in_function = false;
+                let rest = rest.strip_prefix("m ").unwrap_or(rest);
+                let mut fields = rest.splitn(4, ' ');
+                if let (Some(address), Some(_size), Some(_parameter_size), Some(name)) =
+                    (fields.next(), fields.next(), fields.next(), fields.next())
+                {
+                    if let Ok(address) = u64::from_str_radix(address, 16) {
+                        symbols.functions.insert(address, name.to_string());
+                        in_function = true;
+                    }
+                }
+            }
+            // PUBLIC [m] <address> <parameter size> <name>
+            "PUBLIC" => {
+                in_function = false;
+                let rest = rest.strip_prefix("m ").unwrap_or(rest);
+                let mut fields = rest.splitn(3, ' ');
+                if let (Some(address), Some(_parameter_size), Some(name)) =
+                    (fields.next(), fields.next(), fields.next())
+                {
+                    if let Ok(address) = u64::from_str_radix(address, 16) {
+                        public_symbols.push((address, name.to_string()));
+                    }
+                }
+            }
+            // Line records have no record type, and follow the FUNC record they belong to:
+            // <address> <size> <line> <file index>
+            _ if in_function => {
+                let mut fields = line.split(' ');
+                if let (Some(address), Some(_size), Some(line_number), Some(file_index)) =
+                    (fields.next(), fields.next(), fields.next(), fields.next())
+                {
+                    if let (Ok(address), Ok(line_number), Ok(file_index)) = (
+                        u64::from_str_radix(address, 16),
+                        line_number.parse::<u32>(),
+                        file_index.parse::<u32>(),
+                    ) {
+                        line_records.push((address, file_index, line_number));
+                        continue;
+                    }
+                }
+                in_function = false;
+            }
+            _ => in_function = false,
+        }
+    }
+
+    for (address, name) in public_symbols {
+        symbols.functions.entry(address).or_insert(name);
+    }
+
+    // Line records refer to files by index, and FILE records may appear after them
+    // (e.g. in files written by older versions of dump_syms), so resolve the names last.
+    symbols.lines = line_records
+        .into_iter()
+        .map(|(address, file_index, line_number)| {
+            let file_name = files
+                .get(&file_index)
+                .cloned()
+                .unwrap_or_else(|| format!("<file {}>", file_index));
+            (address, file_name, line_number)
+        })
+        .collect();
+
+    Ok(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    const DEBUG_ID: &str = "3F2E5D1A9B7C4E0F8A6B5C4D3E2F1A0B1";
+
+    const SYMBOL_FILE: &str = "\
+MODULE windows x86_64 3F2E5D1A9B7C4E0F8A6B5C4D3E2F1A0B1 test.pdb
+INFO CODE_ID 5F3E2D1C4000 test.dll
+FILE 0 c:\\src\\main.c
+FILE 1 c:\\src\\util.c
+FUNC 1000 40 0 main
+1000 10 12 0
+1010 30 13 0
+FUNC m 1040 20 8 helper(int, int)
+1040 20 7 1
+PUBLIC 1040 8 ?helper@@YAHHH@Z
+PUBLIC m 2000 0 exported_function
+STACK CFI INIT 1000 40 .cfa: $rsp 8 + .ra: .cfa -8 + ^
+";
+
+    /// Write a symbol file into a new temporary directory laid out as a symbol store.
+    fn symbol_store(name: &str, contents: &str) -> PathBuf {
+        let store = std::env::temp_dir().join(format!(
+            "minidump_layout_breakpad_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let symbol_directory = store.join("test.pdb").join(DEBUG_ID);
+        fs::create_dir_all(&symbol_directory).unwrap();
+        fs::write(symbol_directory.join("test.sym"), contents).unwrap();
+        store
+    }
+
+    #[test]
+    fn names_symbol_files_after_the_debug_file() {
+        assert_eq!(symbol_file_name("test.pdb"), "test.sym");
+        assert_eq!(symbol_file_name("TEST.PDB"), "TEST.sym");
+        assert_eq!(symbol_file_name("libc.so.6"), "libc.so.6.sym");
+        assert_eq!(symbol_file_name("pdb"), "pdb.sym");
+    }
+
+    #[test]
+    fn reads_functions_and_lines() {
+        let store = symbol_store("read", SYMBOL_FILE);
+        let symbol_path =
+            find_breakpad_symbols(std::slice::from_ref(&store), "test.pdb", DEBUG_ID).unwrap();
+        let symbols = read_breakpad_symbols(&symbol_path).unwrap();
+        fs::remove_dir_all(store).unwrap();
+
+        // The FUNC record's name takes precedence over the PUBLIC record at the same address
+        assert_eq!(
+            symbols.functions.into_iter().collect::<Vec<_>>(),
+            vec![
+                (0x1000, "main".to_string()),
+                (0x1040, "helper(int, int)".to_string()),
+                (0x2000, "exported_function".to_string()),
+            ]
+        );
+        assert_eq!(
+            symbols.lines,
+            vec![
+                (0x1000, "c:\\src\\main.c".to_string(), 12),
+                (0x1010, "c:\\src\\main.c".to_string(), 13),
+                (0x1040, "c:\\src\\util.c".to_string(), 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_symbol_files_for_other_builds() {
+        let store = symbol_store("mismatch", SYMBOL_FILE);
+        let other_debug_id = "00000000000000000000000000000000A";
+        let other_directory = store.join("test.pdb").join(other_debug_id);
+        fs::create_dir_all(&other_directory).unwrap();
+        fs::write(other_directory.join("test.sym"), SYMBOL_FILE).unwrap();
+
+        let found = find_breakpad_symbols(std::slice::from_ref(&store), "test.pdb", other_debug_id);
+        fs::remove_dir_all(store).unwrap();
+        assert!(found.is_none());
+    }
+}
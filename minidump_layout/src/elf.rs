@@ -0,0 +1,381 @@
+use minidump::system_info::Cpu;
+use minidump::Endian;
+
+use crate::layout::{MD_CPU_ARCHITECTURE_RISCV, MD_CPU_ARCHITECTURE_RISCV64};
+use crate::memory::DumpMemory;
+
+pub const EM_SPARC: u16 = 2;
+pub const EM_386: u16 = 3;
+pub const EM_MIPS: u16 = 8;
+pub const EM_PPC: u16 = 20;
+pub const EM_PPC64: u16 = 21;
+pub const EM_ARM: u16 = 40;
+pub const EM_SPARCV9: u16 = 43;
+pub const EM_X86_64: u16 = 62;
+pub const EM_AARCH64: u16 = 183;
+pub const EM_RISCV: u16 = 243;
+
+const ET_EXEC: u16 = 2;
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+
+const DT_NULL: u64 = 0;
+const DT_INIT: u64 = 12;
+const DT_INIT_ARRAY: u64 = 25;
+const DT_INIT_ARRAYSZ: u64 = 27;
+const DT_PREINIT_ARRAY: u64 = 32;
+const DT_PREINIT_ARRAYSZ: u64 = 33;
+
+/// An upper bound on the number of entries read from tables in the dumped process's memory,
+/// in case the tables are damaged.
+const MAX_TABLE_ENTRIES: u64 = 0x1000;
+
+#[derive(Debug)]
+pub struct ProgramHeader {
+    pub p_type: u32,
+    pub p_vaddr: u64,
+    pub p_memsz: u64,
+}
+
+/// The ELF header of an ELF image which has been mapped into the memory of the dumped process.
+#[derive(Debug)]
+pub struct ElfHeader {
+    pub base_address: u64,
+    pub is_64bit: bool,
+    pub endian: Endian,
+    pub elf_type: u16,
+    pub machine: u16,
+    pub entry: u64,
+    pub phoff: u64,
+    pub phentsize: u16,
+    pub phnum: u16,
+}
+
+impl ElfHeader {
+    pub fn read(memory: &DumpMemory, base_address: u64) -> Option<Self> {
+        let header = memory.read(base_address, 0x40)?;
+        if &header[0..4] != b"\x7fELF" {
+            return None;
+        }
+        let is_64bit = match header[4] {
+            1 => false,
+            2 => true,
+            _ => return None,
+        };
+        let endian = match header[5] {
+            1 => Endian::Little,
+            2 => Endian::Big,
+            _ => return None,
+        };
+
+        let mut elf_header = ElfHeader {
+            base_address,
+            is_64bit,
+            endian,
+            elf_type: 0,
+            machine: 0,
+            entry: 0,
+            phoff: 0,
+            phentsize: 0,
+            phnum: 0,
+        };
+        elf_header.elf_type = elf_header.u16_at(&header, 0x10)?;
+        elf_header.machine = elf_header.u16_at(&header, 0x12)?;
+        if is_64bit {
+            elf_header.entry = elf_header.u64_at(&header, 0x18)?;
+            elf_header.phoff = elf_header.u64_at(&header, 0x20)?;
+            elf_header.phentsize = elf_header.u16_at(&header, 0x36)?;
+            elf_header.phnum = elf_header.u16_at(&header, 0x38)?;
+        } else {
+            elf_header.entry = elf_header.u32_at(&header, 0x18)? as u64;
+            elf_header.phoff = elf_header.u32_at(&header, 0x1c)? as u64;
+            elf_header.phentsize = elf_header.u16_at(&header, 0x2a)?;
+            elf_header.phnum = elf_header.u16_at(&header, 0x2c)?;
+        }
+
+        Some(elf_header)
+    }
+
+    fn u16_at(&self, data: &[u8], offset: usize) -> Option<u16> {
+        let bytes = data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(match self.endian {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32_at(&self, data: &[u8], offset: usize) -> Option<u32> {
+        let bytes = data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(match self.endian {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn u64_at(&self, data: &[u8], offset: usize) -> Option<u64> {
+        let bytes = data.get(offset..offset + 8)?.try_into().ok()?;
+        Some(match self.endian {
+            Endian::Little => u64::from_le_bytes(bytes),
+            Endian::Big => u64::from_be_bytes(bytes),
+        })
+    }
+
+    fn pointer_at(&self, data: &[u8], offset: usize) -> Option<u64> {
+        if self.is_64bit {
+            self.u64_at(data, offset)
+        } else {
+            self.u32_at(data, offset).map(u64::from)
+        }
+    }
+
+    fn pointer_size(&self) -> u64 {
+        if self.is_64bit {
+            8
+        } else {
+            4
+        }
+    }
+
+    fn parse_program_header(&self, data: &[u8]) -> Option<ProgramHeader> {
+        if self.is_64bit {
+            Some(ProgramHeader {
+                p_type: self.u32_at(data, 0)?,
+                p_vaddr: self.u64_at(data, 0x10)?,
+                p_memsz: self.u64_at(data, 0x28)?,
+            })
+        } else {
+            Some(ProgramHeader {
+                p_type: self.u32_at(data, 0)?,
+                p_vaddr: self.u32_at(data, 8)? as u64,
+                p_memsz: self.u32_at(data, 0x14)? as u64,
+            })
+        }
+    }
+
+    /// The program headers of the image, if they are present in the minidump's memory.
+    pub fn program_headers(&self, memory: &DumpMemory) -> Vec<ProgramHeader> {
+        let mut program_headers = Vec::new();
+        for index in 0..self.phnum as u64 {
+            let address = self
+                .base_address
+                .wrapping_add(self.phoff)
+                .wrapping_add(index * self.phentsize as u64);
+            let Some(data) = memory.read(address, self.phentsize as usize) else {
+                break;
+            };
+            match self.parse_program_header(&data) {
+                Some(program_header) => program_headers.push(program_header),
+                None => break,
+            }
+        }
+        program_headers
+    }
+
+    /// The difference between the addresses at which the image is mapped in the dumped process,
+    /// and the virtual addresses in its program headers.
+    pub fn load_bias(&self, memory: &DumpMemory) -> u64 {
+        let first_load_address = self
+            .program_headers(memory)
+            .iter()
+            .filter(|program_header| program_header.p_type == PT_LOAD)
+            .map(|program_header| program_header.p_vaddr & !0xfff)
+            .min();
+        match first_load_address {
+            Some(first_load_address) => self.base_address.wrapping_sub(first_load_address),
+            None if self.elf_type == ET_EXEC => 0,
+            None => self.base_address,
+        }
+    }
+
+    /// The virtual address of the image's entry point (`e_entry`, adjusted by the load bias).
+    pub fn entry_point(&self, memory: &DumpMemory) -> Option<u64> {
+        if self.entry == 0 {
+            return None;
+        }
+        Some(self.entry.wrapping_add(self.load_bias(memory)))
+    }
+
+    /// The virtual addresses of the image's initializers, which run before the entry point:
+    /// `DT_INIT`, and the functions in `DT_PREINIT_ARRAY` and `DT_INIT_ARRAY`.
+    pub fn initializers(&self, memory: &DumpMemory) -> Vec<u64> {
+        let mut initializers = Vec::new();
+        let load_bias = self.load_bias(memory);
+        let Some(dynamic) = self
+            .program_headers(memory)
+            .into_iter()
+            .find(|program_header| program_header.p_type == PT_DYNAMIC)
+        else {
+            return initializers;
+        };
+
+        let dynamic_address = dynamic.p_vaddr.wrapping_add(load_bias);
+        let entry_size = 2 * self.pointer_size();
+        let entry_count = (dynamic.p_memsz / entry_size).min(MAX_TABLE_ENTRIES);
+        let (mut init_arrays, mut init_array_size, mut preinit_array_size) =
+            (Vec::new(), None, None);
+        for index in 0..entry_count {
+            let Some(entry) = memory.read(
+                dynamic_address.wrapping_add(index * entry_size),
+                entry_size as usize,
+            ) else {
+                break;
+            };
+            let (Some(tag), Some(value)) = (
+                self.pointer_at(&entry, 0),
+                self.pointer_at(&entry, self.pointer_size() as usize),
+            ) else {
+                break;
+            };
+            // The dynamic linker doesn't relocate these entries in place,
+            // so they still need to be adjusted by the load bias.
+            match tag {
+                DT_NULL => break,
+                DT_INIT => initializers.push(value.wrapping_add(load_bias)),
+                DT_INIT_ARRAY | DT_PREINIT_ARRAY => {
+                    init_arrays.push((tag, value.wrapping_add(load_bias)))
+                }
+                DT_INIT_ARRAYSZ => init_array_size = Some(value),
+                DT_PREINIT_ARRAYSZ => preinit_array_size = Some(value),
+                _ => {}
+            }
+        }
+
+        for (tag, array_address) in init_arrays {
+            let array_size = match tag {
+                DT_PREINIT_ARRAY => preinit_array_size,
+                _ => init_array_size,
+            }
+            .unwrap_or(0);
+            let count = (array_size / self.pointer_size()).min(MAX_TABLE_ENTRIES);
+            let Some(array) = memory.read(array_address, (count * self.pointer_size()) as usize)
+            else {
+                continue;
+            };
+            // Unlike the dynamic section, the pointers in the arrays have already been relocated.
+            for index in 0..count as usize {
+                match self.pointer_at(&array, index * self.pointer_size() as usize) {
+                    // Entries of 0 and -1 are placeholders, and are skipped by the dynamic linker
+                    Some(initializer) if initializer != 0 && initializer != u64::MAX => {
+                        initializers.push(initializer)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        initializers
+    }
+
+    /// The CPU architecture of the image, in the same form as reported by a `MinidumpSystemInfo` stream.
+    pub fn minidump_cpu(&self) -> Option<Cpu> {
+        match (self.machine, self.is_64bit) {
+            (EM_386, _) => Some(Cpu::X86),
+            (EM_X86_64, _) => Some(Cpu::X86_64),
+            (EM_ARM, _) => Some(Cpu::Arm),
+            (EM_AARCH64, _) => Some(Cpu::Arm64),
+            (EM_PPC, _) => Some(Cpu::Ppc),
+            (EM_PPC64, _) => Some(Cpu::Ppc64),
+            (EM_MIPS, false) => Some(Cpu::Mips),
+            (EM_MIPS, true) => Some(Cpu::Mips64),
+            (EM_SPARC, _) | (EM_SPARCV9, _) => Some(Cpu::Sparc),
+            (EM_RISCV, false) => Some(Cpu::Unknown(MD_CPU_ARCHITECTURE_RISCV)),
+            (EM_RISCV, true) => Some(Cpu::Unknown(MD_CPU_ARCHITECTURE_RISCV64)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_ADDRESS: u64 = 0x5555_5555_4000;
+
+    /// A 64-bit little-endian position-independent executable with a `PT_LOAD` segment at
+    /// virtual address 0, and a dynamic section with `DT_INIT`, `DT_INIT_ARRAY`,
+    /// and `DT_INIT_ARRAYSZ` entries.
+    fn elf_image(phoff: u64) -> Vec<u8> {
+        let mut image = vec![0; 0x400];
+        image[0..4].copy_from_slice(b"\x7fELF");
+        image[4] = 2; // ELFCLASS64
+        image[5] = 1; // ELFDATA2LSB
+        image[0x10..0x12].copy_from_slice(&3u16.to_le_bytes()); // ET_DYN
+        image[0x12..0x14].copy_from_slice(&EM_X86_64.to_le_bytes());
+        image[0x18..0x20].copy_from_slice(&0x180u64.to_le_bytes()); // e_entry
+        image[0x20..0x28].copy_from_slice(&phoff.to_le_bytes());
+        image[0x36..0x38].copy_from_slice(&0x38u16.to_le_bytes()); // e_phentsize
+        image[0x38..0x3a].copy_from_slice(&2u16.to_le_bytes()); // e_phnum
+
+        let program_headers = [(PT_LOAD, 0u64, 0x400u64), (PT_DYNAMIC, 0x200, 0x40)];
+        for (index, (p_type, p_vaddr, p_memsz)) in program_headers.into_iter().enumerate() {
+            let offset = 0x40 + index * 0x38;
+            image[offset..offset + 4].copy_from_slice(&p_type.to_le_bytes());
+            image[offset + 0x10..offset + 0x18].copy_from_slice(&p_vaddr.to_le_bytes());
+            image[offset + 0x28..offset + 0x30].copy_from_slice(&p_memsz.to_le_bytes());
+        }
+
+        let dynamic = [
+            (DT_INIT, 0x100u64),
+            (DT_INIT_ARRAY, 0x300),
+            (DT_INIT_ARRAYSZ, 0x18),
+            (DT_NULL, 0),
+        ];
+        for (index, (tag, value)) in dynamic.into_iter().enumerate() {
+            let offset = 0x200 + index * 0x10;
+            image[offset..offset + 8].copy_from_slice(&tag.to_le_bytes());
+            image[offset + 8..offset + 0x10].copy_from_slice(&value.to_le_bytes());
+        }
+
+        // The init array has already been relocated, and has a -1 placeholder.
+        let init_array = [BASE_ADDRESS + 0x110, u64::MAX, BASE_ADDRESS + 0x120];
+        for (index, initializer) in init_array.into_iter().enumerate() {
+            let offset = 0x300 + index * 8;
+            image[offset..offset + 8].copy_from_slice(&initializer.to_le_bytes());
+        }
+        image
+    }
+
+    #[test]
+    fn finds_entry_point_and_initializers() {
+        let image = elf_image(0x40);
+        let memory = DumpMemory::from_regions(&[(BASE_ADDRESS, &image)]);
+        let elf_header = ElfHeader::read(&memory, BASE_ADDRESS).unwrap();
+        assert_eq!(elf_header.minidump_cpu(), Some(Cpu::X86_64));
+        assert_eq!(elf_header.program_headers(&memory).len(), 2);
+        assert_eq!(elf_header.load_bias(&memory), BASE_ADDRESS);
+        assert_eq!(elf_header.entry_point(&memory), Some(BASE_ADDRESS + 0x180));
+        assert_eq!(
+            elf_header.initializers(&memory),
+            vec![
+                BASE_ADDRESS + 0x100,
+                BASE_ADDRESS + 0x110,
+                BASE_ADDRESS + 0x120
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_program_headers_outside_of_the_address_space() {
+        let image = elf_image(u64::MAX - 0x10);
+        let memory = DumpMemory::from_regions(&[(BASE_ADDRESS, &image)]);
+        let elf_header = ElfHeader::read(&memory, BASE_ADDRESS).unwrap();
+        assert!(elf_header.program_headers(&memory).is_empty());
+        assert!(elf_header.initializers(&memory).is_empty());
+    }
+
+    #[test]
+    fn reads_big_endian_32bit_headers() {
+        let mut image = vec![0; 0x40];
+        image[0..4].copy_from_slice(b"\x7fELF");
+        image[4] = 1; // ELFCLASS32
+        image[5] = 2; // ELFDATA2MSB
+        image[0x10..0x12].copy_from_slice(&ET_EXEC.to_be_bytes());
+        image[0x12..0x14].copy_from_slice(&EM_MIPS.to_be_bytes());
+        image[0x18..0x1c].copy_from_slice(&0x0040_0120u32.to_be_bytes());
+        let memory = DumpMemory::from_regions(&[(0x0040_0000, &image)]);
+        let elf_header = ElfHeader::read(&memory, 0x0040_0000).unwrap();
+        assert_eq!(elf_header.minidump_cpu(), Some(Cpu::Mips));
+        // Executables without program headers aren't relocated.
+        assert_eq!(elf_header.entry_point(&memory), Some(0x0040_0120));
+    }
+}
@@ -0,0 +1,444 @@
+use std::fmt::Write;
+use std::ops::Deref;
+
+use minidump::format::{MINIDUMP_LOCATION_DESCRIPTOR, MINIDUMP_STREAM_TYPE};
+use minidump::{Minidump, MinidumpContext, MinidumpRawContext};
+
+/// The size of an x64 `CONTEXT`, which is followed by a `CONTEXT_EX` when the context has extended state.
+const CONTEXT_AMD64_SIZE: usize = 0x4d0;
+/// The offset of `ContextFlags` in an x64 `CONTEXT`.
+const CONTEXT_AMD64_FLAGS_OFFSET: usize = 0x30;
+/// The offset of `Xmm0` in an x64 `CONTEXT`, inside its `FltSave` area.
+const CONTEXT_AMD64_XMM_OFFSET: usize = 0x1a0;
+const CONTEXT_AMD64_XSTATE: u32 = 0x0010_0040;
+
+/// The size of the legacy region of an XSAVE area (the `FXSAVE` format),
+/// which feature offsets are measured from the start of.
+const XSAVE_LEGACY_REGION_SIZE: usize = 512;
+const XSAVE_HEADER_SIZE: usize = 64;
+/// The bit of `XSAVE_AREA_HEADER.CompactionMask` which is set for XSAVE areas in the compacted format.
+const XSTATE_COMPACTION_ENABLE: u64 = 1 << 63;
+
+// XSAVE state components
+const XSTATE_AVX: usize = 2;
+const XSTATE_AVX512_KMASK: usize = 5;
+const XSTATE_AVX512_ZMM_H: usize = 6;
+const XSTATE_AVX512_ZMM: usize = 7;
+
+/// The offset of the `XSTATE_CONFIG_FEATURE_MSC_INFO` in a `MINIDUMP_MISC_INFO_5`.
+const MISC_INFO_5_XSTATE_OFFSET: usize = 832;
+const XSTATE_FEATURE_COUNT: usize = 64;
+
+/// The layout of the XSAVE area in the thread contexts of a minidump, which depends on the
+/// processor features enabled on the machine the minidump was written on.
+/// Windows records this in the `MINIDUMP_MISC_INFO_5` version of the `MiscInfo` stream.
+#[derive(Debug, Clone)]
+pub struct XStateConfig {
+    pub enabled_features: u64,
+    /// The `(offset, size)` of each state component, with offsets measured from the start
+    /// of a standard-format XSAVE area.
+    pub features: Vec<(u32, u32)>,
+}
+
+impl XStateConfig {
+    pub fn read<T>(minidump_obj: &Minidump<T>) -> Option<Self>
+    where
+        T: Deref<Target = [u8]>,
+    {
+        let data = minidump_obj
+            .get_raw_stream(MINIDUMP_STREAM_TYPE::MiscInfoStream as u32)
+            .ok()?;
+        let read_u32 = |offset: usize| -> Option<u32> {
+            Some(u32::from_le_bytes(
+                data.get(offset..offset + 4)?.try_into().ok()?,
+            ))
+        };
+        // Older versions of the stream end before the XSTATE configuration
+        let size_of_xstate_info = read_u32(MISC_INFO_5_XSTATE_OFFSET)?;
+        if size_of_xstate_info == 0 {
+            return None;
+        }
+        let enabled_features = u64::from_le_bytes(
+            data.get(MISC_INFO_5_XSTATE_OFFSET + 8..MISC_INFO_5_XSTATE_OFFSET + 16)?
+                .try_into()
+                .ok()?,
+        );
+        let features = (0..XSTATE_FEATURE_COUNT)
+            .map(|index| {
+                let feature_offset = MISC_INFO_5_XSTATE_OFFSET + 16 + index * 8;
+                Some((read_u32(feature_offset)?, read_u32(feature_offset + 4)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(XStateConfig {
+            enabled_features,
+            features,
+        })
+    }
+
+    /// The standard-format offsets and sizes of the AVX and AVX-512 state components,
+    /// for minidumps which don't record their XSAVE layout.
+    fn standard() -> Self {
+        let mut features = vec![(0, 0); XSTATE_FEATURE_COUNT];
+        features[XSTATE_AVX] = (576, 256);
+        features[XSTATE_AVX512_KMASK] = (1088, 64);
+        features[XSTATE_AVX512_ZMM_H] = (1152, 512);
+        features[XSTATE_AVX512_ZMM] = (1664, 1024);
+        XStateConfig {
+            enabled_features: u64::MAX,
+            features,
+        }
+    }
+}
+
+/// A vector, floating-point, or mask register which isn't part of the minidump crate's
+/// decoded thread context (e.g. `ymm3`, `zmm17`, `k1`, or `v0`).
+#[derive(Debug, Clone)]
+pub struct ExtendedRegister {
+    pub name: String,
+    /// The value of the register, in little-endian byte order.
+    pub value: Vec<u8>,
+}
+
+impl ExtendedRegister {
+    /// The value of the register as a hexadecimal number (e.g. `0x0000000000000000000000003f800000`).
+    pub fn value_hex(&self) -> String {
+        let mut value_hex = String::from("0x");
+        for byte in self.value.iter().rev() {
+            let _ = write!(value_hex, "{:02x}", byte);
+        }
+        value_hex
+    }
+}
+
+/// Read the raw bytes of a thread context from the minidump file, which are needed to find the
+/// extended state following the context itself.
+pub fn context_bytes<'a>(dump_data: &'a [u8], location: &MINIDUMP_LOCATION_DESCRIPTOR) -> &'a [u8] {
+    let start = location.rva as usize;
+    let end = start.saturating_add(location.data_size as usize);
+    dump_data.get(start..end).unwrap_or_default()
+}
+
+/// Read the extended register state of a thread: the widest available form of each x64 vector
+/// register (`xmm`, `ymm` from AVX, or `zmm` from AVX-512) and the AVX-512 mask registers,
+/// or the NEON / floating-point registers of an AArch64 thread.
+///
+/// `context_bytes` are the raw bytes of the thread's context, as returned by `context_bytes`.
+/// Returns an empty list for other architectures.
+pub fn read_extended_registers(
+    context: &MinidumpContext,
+    context_bytes: &[u8],
+    xstate_config: Option<&XStateConfig>,
+) -> Vec<ExtendedRegister> {
+    match &context.raw {
+        MinidumpRawContext::Amd64(_) => read_amd64_extended_registers(context_bytes, xstate_config),
+        MinidumpRawContext::Arm64(raw_context) => read_arm64_extended_registers(
+            &raw_context.float_regs,
+            raw_context.fpcr,
+            raw_context.fpsr,
+        ),
+        MinidumpRawContext::OldArm64(raw_context) => read_arm64_extended_registers(
+            &raw_context.float_regs,
+            raw_context.fpcr,
+            raw_context.fpsr,
+        ),
+        _ => Vec::new(),
+    }
+}
+
+fn read_arm64_extended_registers(
+    float_regs: &[u128; 32],
+    fpcr: u32,
+    fpsr: u32,
+) -> Vec<ExtendedRegister> {
+    let mut registers: Vec<ExtendedRegister> = float_regs
+        .iter()
+        .enumerate()
+        .map(|(index, value)| ExtendedRegister {
+            name: format!("v{}", index),
+            value: value.to_le_bytes().to_vec(),
+        })
+        .collect();
+    registers.push(ExtendedRegister {
+        name: String::from("fpcr"),
+        value: fpcr.to_le_bytes().to_vec(),
+    });
+    registers.push(ExtendedRegister {
+        name: String::from("fpsr"),
+        value: fpsr.to_le_bytes().to_vec(),
+    });
+    registers
+}
+
+fn read_amd64_extended_registers(
+    context_bytes: &[u8],
+    xstate_config: Option<&XStateConfig>,
+) -> Vec<ExtendedRegister> {
+    // The legacy XMM registers are always part of the context itself
+    let mut vector_registers: Vec<Vec<u8>> = (0..16)
+        .filter_map(|index| {
+            let offset = CONTEXT_AMD64_XMM_OFFSET + index * 16;
+            context_bytes.get(offset..offset + 16).map(<[u8]>::to_vec)
+        })
+        .collect();
+    if vector_registers.len() != 16 {
+        return Vec::new();
+    }
+
+    let mut mask_registers = Vec::new();
+    let mut upper_vector_registers = Vec::new();
+    if let Some(xstate) = locate_xstate(context_bytes) {
+        let standard_config = XStateConfig::standard();
+        let xstate_config = xstate_config.unwrap_or(&standard_config);
+        let feature = |feature: usize, register_count: usize, register_size: usize| {
+            if xstate.mask & (1 << feature) == 0
+                || xstate_config.enabled_features & (1 << feature) == 0
+            {
+                return None;
+            }
+            let (offset, size) = xstate.feature_location(xstate_config, feature)?;
+            if size < register_count * register_size {
+                return None;
+            }
+            let start = (xstate.header_offset + offset).checked_sub(XSAVE_LEGACY_REGION_SIZE)?;
+            let data = context_bytes.get(start..start + register_count * register_size)?;
+            Some(
+                data.chunks_exact(register_size)
+                    .map(<[u8]>::to_vec)
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        if let Some(ymm_upper_halves) = feature(XSTATE_AVX, 16, 16) {
+            for (register, upper_half) in vector_registers.iter_mut().zip(ymm_upper_halves) {
+                register.extend(upper_half);
+            }
+            if let Some(zmm_upper_halves) = feature(XSTATE_AVX512_ZMM_H, 16, 32) {
+                for (register, upper_half) in vector_registers.iter_mut().zip(zmm_upper_halves) {
+                    register.extend(upper_half);
+                }
+                upper_vector_registers = feature(XSTATE_AVX512_ZMM, 16, 64).unwrap_or_default();
+            }
+        }
+        mask_registers = feature(XSTATE_AVX512_KMASK, 8, 8).unwrap_or_default();
+    }
+
+    let vector_register_prefix = match vector_registers[0].len() {
+        64 => "zmm",
+        32 => "ymm",
+        _ => "xmm",
+    };
+    let mut registers: Vec<ExtendedRegister> = vector_registers
+        .into_iter()
+        .chain(upper_vector_registers)
+        .enumerate()
+        .map(|(index, value)| ExtendedRegister {
+            name: format!("{}{}", vector_register_prefix, index),
+            value,
+        })
+        .collect();
+    registers.extend(
+        mask_registers
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| ExtendedRegister {
+                name: format!("k{}", index),
+                value,
+            }),
+    );
+    registers
+}
+
+struct XStateLocation {
+    /// The offset of the `XSAVE_AREA_HEADER` from the start of the context.
+    header_offset: usize,
+    /// The state components which were saved (`XSAVE_AREA_HEADER.Mask`).
+    mask: u64,
+    /// The state components in the XSAVE area, if it is in the compacted format
+    /// (`XSAVE_AREA_HEADER.CompactionMask`).
+    compaction_mask: Option<u64>,
+}
+
+impl XStateLocation {
+    /// The offset (from the start of the XSAVE area) and size of a state component.
+    /// In the compacted format, the components which are present are packed together in order,
+    /// straight after the XSAVE header.
+    fn feature_location(
+        &self,
+        xstate_config: &XStateConfig,
+        feature: usize,
+    ) -> Option<(usize, usize)> {
+        let (offset, size) = *xstate_config.features.get(feature)?;
+        let Some(compaction_mask) = self.compaction_mask else {
+            return Some((offset as usize, size as usize));
+        };
+        if compaction_mask & (1 << feature) == 0 {
+            return None;
+        }
+        let offset = (XSTATE_AVX..feature)
+            .filter(|&preceding_feature| compaction_mask & (1 << preceding_feature) != 0)
+            .filter_map(|preceding_feature| xstate_config.features.get(preceding_feature))
+            .map(|&(_, preceding_size)| preceding_size as usize)
+            .sum::<usize>()
+            + XSAVE_LEGACY_REGION_SIZE
+            + XSAVE_HEADER_SIZE;
+        Some((offset, size as usize))
+    }
+}
+
+/// Find the XSAVE area of an x64 context with `CONTEXT_XSTATE`, through the `CONTEXT_EX`
+/// which follows the `CONTEXT`.
+fn locate_xstate(context_bytes: &[u8]) -> Option<XStateLocation> {
+    let read_u32 = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            context_bytes.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    let context_flags = read_u32(CONTEXT_AMD64_FLAGS_OFFSET)?;
+    if context_flags & CONTEXT_AMD64_XSTATE != CONTEXT_AMD64_XSTATE {
+        return None;
+    }
+    // CONTEXT_EX is three CONTEXT_CHUNKs (All, Legacy, XState), each a signed offset
+    // from the start of the CONTEXT_EX and a length
+    let xstate_chunk_offset = read_u32(CONTEXT_AMD64_SIZE + 16)? as i32;
+    let xstate_chunk_length = read_u32(CONTEXT_AMD64_SIZE + 20)?;
+    if (xstate_chunk_length as usize) < XSAVE_HEADER_SIZE {
+        return None;
+    }
+    let header_offset = (CONTEXT_AMD64_SIZE as i64 + xstate_chunk_offset as i64)
+        .try_into()
+        .ok()?;
+    let read_u64 = |offset: usize| -> Option<u64> {
+        Some(u64::from_le_bytes(
+            context_bytes.get(offset..offset + 8)?.try_into().ok()?,
+        ))
+    };
+    let mask = read_u64(header_offset)?;
+    let compaction_mask = read_u64(header_offset + 8)?;
+    Some(XStateLocation {
+        header_offset,
+        mask,
+        compaction_mask: (compaction_mask & XSTATE_COMPACTION_ENABLE != 0)
+            .then_some(compaction_mask),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The offset of the XSAVE header from the start of the `CONTEXT_EX`.
+    const XSTATE_CHUNK_OFFSET: usize = 0x20;
+    const XSAVE_HEADER: usize = CONTEXT_AMD64_SIZE + XSTATE_CHUNK_OFFSET;
+
+    /// An x64 context with `xmm<n>` filled with `n`, followed by a `CONTEXT_EX` and an XSAVE header
+    /// with the given masks, and room for the AVX and AVX-512 state after the header.
+    fn amd64_context(mask: u64, compaction_mask: u64) -> Vec<u8> {
+        let mut context = vec![0; XSAVE_HEADER + XSAVE_HEADER_SIZE + 0x800];
+        context[CONTEXT_AMD64_FLAGS_OFFSET..CONTEXT_AMD64_FLAGS_OFFSET + 4]
+            .copy_from_slice(&CONTEXT_AMD64_XSTATE.to_le_bytes());
+        for index in 0..16 {
+            let offset = CONTEXT_AMD64_XMM_OFFSET + index * 16;
+            context[offset..offset + 16].fill(index as u8);
+        }
+        let xstate_chunk = CONTEXT_AMD64_SIZE + 16;
+        context[xstate_chunk..xstate_chunk + 4]
+            .copy_from_slice(&(XSTATE_CHUNK_OFFSET as u32).to_le_bytes());
+        context[xstate_chunk + 4..xstate_chunk + 8].copy_from_slice(&0x840u32.to_le_bytes());
+        context[XSAVE_HEADER..XSAVE_HEADER + 8].copy_from_slice(&mask.to_le_bytes());
+        context[XSAVE_HEADER + 8..XSAVE_HEADER + 16]
+            .copy_from_slice(&compaction_mask.to_le_bytes());
+        context
+    }
+
+    fn names(registers: &[ExtendedRegister]) -> Vec<&str> {
+        registers
+            .iter()
+            .map(|register| register.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn reads_ymm_registers_from_a_standard_xsave_area() {
+        let mut context = amd64_context(1 << XSTATE_AVX, 0);
+        // The AVX state is at offset 576 of the XSAVE area, right after the header
+        let avx_state = XSAVE_HEADER + XSAVE_HEADER_SIZE;
+        for index in 0..16 {
+            context[avx_state + index * 16..avx_state + index * 16 + 16].fill(0x80 + index as u8);
+        }
+
+        let registers = read_amd64_extended_registers(&context, None);
+        assert_eq!(registers.len(), 16);
+        assert_eq!(registers[3].name, "ymm3");
+        assert_eq!(registers[3].value[..16], [3; 16]);
+        assert_eq!(registers[3].value[16..], [0x83; 16]);
+    }
+
+    #[test]
+    fn reads_mask_registers_from_a_compacted_xsave_area() {
+        let features = (1 << XSTATE_AVX) | (1 << XSTATE_AVX512_KMASK);
+        let mut context = amd64_context(features, XSTATE_COMPACTION_ENABLE | features);
+        // In the compacted format, the mask registers follow the AVX state
+        let kmask_state = XSAVE_HEADER + XSAVE_HEADER_SIZE + 256;
+        context[kmask_state + 8..kmask_state + 16].copy_from_slice(&0xffu64.to_le_bytes());
+
+        let registers = read_amd64_extended_registers(&context, Some(&XStateConfig::standard()));
+        assert_eq!(registers.len(), 24);
+        assert_eq!(registers[0].name, "ymm0");
+        assert_eq!(
+            names(&registers[16..]),
+            ["k0", "k1", "k2", "k3", "k4", "k5", "k6", "k7"]
+        );
+        assert_eq!(registers[17].value_hex(), "0x00000000000000ff");
+    }
+
+    #[test]
+    fn skips_features_which_are_not_enabled() {
+        let context = amd64_context(1 << XSTATE_AVX, 0);
+        let xstate_config = XStateConfig {
+            enabled_features: 0,
+            ..XStateConfig::standard()
+        };
+        let registers = read_amd64_extended_registers(&context, Some(&xstate_config));
+        assert_eq!(registers.len(), 16);
+        assert_eq!(registers[0].name, "xmm0");
+        assert_eq!(registers[0].value.len(), 16);
+    }
+
+    #[test]
+    fn reads_xmm_registers_without_extended_state() {
+        let mut context = amd64_context(1 << XSTATE_AVX, 0);
+        context[CONTEXT_AMD64_FLAGS_OFFSET..CONTEXT_AMD64_FLAGS_OFFSET + 4]
+            .copy_from_slice(&0x0010_001fu32.to_le_bytes());
+        let registers = read_amd64_extended_registers(&context, None);
+        assert_eq!(registers.len(), 16);
+        assert_eq!(registers[15].name, "xmm15");
+        assert_eq!(registers[15].value, [15; 16]);
+
+        // A truncated context has no registers at all
+        assert!(read_amd64_extended_registers(&context[..0x200], None).is_empty());
+    }
+
+    #[test]
+    fn rejects_xstate_chunks_outside_the_context() {
+        let mut context = amd64_context(1 << XSTATE_AVX, 0);
+        let xstate_chunk = CONTEXT_AMD64_SIZE + 16;
+        context[xstate_chunk..xstate_chunk + 4].copy_from_slice(&(-0x1000i32).to_le_bytes());
+        assert!(locate_xstate(&context).is_none());
+        context[xstate_chunk..xstate_chunk + 4].copy_from_slice(&0x7fff_fff0i32.to_le_bytes());
+        assert!(locate_xstate(&context).is_none());
+    }
+
+    #[test]
+    fn reads_arm64_float_registers() {
+        let mut float_regs = [0u128; 32];
+        float_regs[1] = 0x3f80_0000;
+        let registers = read_arm64_extended_registers(&float_regs, 0x0300_0000, 0x10);
+        assert_eq!(registers.len(), 34);
+        assert_eq!(registers[1].name, "v1");
+        assert_eq!(
+            registers[1].value_hex(),
+            "0x0000000000000000000000003f800000"
+        );
+        assert_eq!(names(&registers[32..]), ["fpcr", "fpsr"]);
+    }
+}
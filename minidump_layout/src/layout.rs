@@ -0,0 +1,862 @@
+//! The layout of the process captured in a minidump, independent of Binary Ninja: where each
+//! memory segment is mapped and which part of the minidump file backs it, the protections of the
+//! segments, and the modules, threads, and exception of the process.
+//!
+//! The _Minidump_ view builds its segments and sections from a [`DumpLayout`], and other Rust code
+//! can use it to work with a minidump in the same way, without the Binary Ninja core.
+
+use std::ops::{Deref, Range};
+
+use log::{debug, error, warn};
+use minidump::format::MemoryProtection;
+use minidump::system_info::Os;
+use minidump::{
+    Minidump, MinidumpException, MinidumpLinuxMaps, MinidumpMemory64List, MinidumpMemoryInfoList,
+    MinidumpMemoryList, MinidumpMiscInfo, MinidumpModuleList, MinidumpStream, MinidumpSystemInfo,
+    MinidumpThreadList, MinidumpThreadNames, Module,
+};
+
+/// Whether a segment of the process's memory can be read, written, and executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentProtection {
+    pub readable: bool,
+    pub writable: bool,
+    pub executable: bool,
+}
+
+impl SegmentProtection {
+    /// The protection of a region in the `MinidumpMemoryInfoList` stream, from its `PAGE_*` flags.
+    pub fn from_memory_protection(memory_protection: MemoryProtection) -> Self {
+        let (readable, writable, executable) = match memory_protection {
+            MemoryProtection::PAGE_NOACCESS => (false, false, false),
+            MemoryProtection::PAGE_READONLY => (true, false, false),
+            MemoryProtection::PAGE_READWRITE => (true, true, false),
+            MemoryProtection::PAGE_WRITECOPY => (true, true, false),
+            MemoryProtection::PAGE_EXECUTE => (false, false, true),
+            MemoryProtection::PAGE_EXECUTE_READ => (true, false, true),
+            MemoryProtection::PAGE_EXECUTE_READWRITE => (true, true, true),
+            MemoryProtection::PAGE_EXECUTE_WRITECOPY => (true, true, true),
+            MemoryProtection::ACCESS_MASK => (false, false, false),
+            MemoryProtection::PAGE_GUARD => (false, false, false),
+            MemoryProtection::PAGE_NOCACHE => (false, false, false),
+            MemoryProtection::PAGE_WRITECOMBINE => (false, false, false),
+            _ => (false, false, false),
+        };
+        SegmentProtection {
+            readable,
+            writable,
+            executable,
+        }
+    }
+}
+
+/// A segment of the process's memory which was captured in the minidump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutSegment {
+    /// The range of virtual addresses at which the segment was mapped in the process.
+    pub address_range: Range<u64>,
+    /// The range of offsets in the minidump file which hold the segment's data.
+    pub backing_range: Range<u64>,
+    /// The protection of the memory region containing the start of the segment, or `None` if
+    /// the minidump has no protection information for it.
+    pub protection: Option<SegmentProtection>,
+}
+
+/// A module which was loaded in the process, from the `MinidumpModuleList` stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutModule {
+    /// The full path of the module (e.g. `C:\Windows\System32\kernel32.dll`).
+    pub name: String,
+    pub address_range: Range<u64>,
+}
+
+/// A thread of the process, from the `MinidumpThreadList` stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutThread {
+    pub thread_id: u32,
+    /// The name of the thread, if the minidump has a `ThreadNamesStream`.
+    pub name: Option<String>,
+    /// The range of the thread's stack which was captured in the minidump.
+    pub stack_range: Option<Range<u64>>,
+    /// The address of the thread environment block, only in Windows minidumps. Minidumps from
+    /// other platforms store something else in its place (e.g. the `pthread_t` on Linux), or nothing.
+    pub teb: Option<u64>,
+    /// The registers of the thread's `CONTEXT` which are needed to find where it was,
+    /// if the CPU of the minidump is known.
+    pub instruction_pointer: Option<u64>,
+    pub stack_pointer: Option<u64>,
+}
+
+/// The exception which caused the minidump to be written, from the `MinidumpException` stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutException {
+    pub thread_id: u32,
+    pub code: u32,
+    /// The address at which the exception happened (the crash site).
+    pub address: u64,
+    /// The decoded exception, from [`describe_exception`].
+    pub description: String,
+}
+
+/// The layout of the process captured in a minidump.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DumpLayout {
+    pub segments: Vec<LayoutSegment>,
+    /// The loaded modules, by address.
+    pub modules: Vec<LayoutModule>,
+    pub threads: Vec<LayoutThread>,
+    pub exception: Option<LayoutException>,
+}
+
+impl DumpLayout {
+    /// Read the layout of the process captured in a minidump. Streams which the minidump
+    /// doesn't have, or which can't be parsed, leave their part of the layout empty.
+    pub fn read<'a, T>(minidump_obj: &'a Minidump<'a, T>) -> Self
+    where
+        T: Deref<Target = [u8]> + 'a,
+    {
+        let mut memory_protections = read_memory_protections(minidump_obj);
+        memory_protections.sort_by_key(|(range, _)| range.start);
+        let segments = read_segment_ranges(minidump_obj)
+            .into_iter()
+            .map(|(backing_range, address_range)| {
+                // Memory segments in partial dumps (e.g. thread stacks) don't always line up
+                // exactly with the regions described by the memory protection information,
+                // so find the region which contains the start of the segment.
+                let protection = protection_containing(&memory_protections, address_range.start);
+                LayoutSegment {
+                    address_range,
+                    backing_range,
+                    protection,
+                }
+            })
+            .collect();
+
+        DumpLayout {
+            segments,
+            modules: read_modules(minidump_obj),
+            threads: read_threads(minidump_obj),
+            exception: read_exception(minidump_obj),
+        }
+    }
+}
+
+// RISC-V minidumps (from Breakpad / Crashpad) use processor architecture values
+// which the minidump crate doesn't have named variants for.
+pub const MD_CPU_ARCHITECTURE_RISCV: u16 = 0x8005;
+pub const MD_CPU_ARCHITECTURE_RISCV64: u16 = 0x8006;
+
+/// The file name of a module, without the rest of its path (e.g. `kernel32.dll`).
+pub fn module_file_name(module_name: &str) -> &str {
+    module_name
+        .rsplit(['\\', '/'])
+        .next()
+        .unwrap_or(module_name)
+}
+
+/// Decode the exception in a `MinidumpException` stream (e.g. `EXCEPTION_ACCESS_VIOLATION_WRITE accessing
+/// address 0x10`, or `SIGSEGV`). The exception code can only be decoded if the OS and CPU are known.
+pub fn describe_exception(
+    minidump_exception: &MinidumpException,
+    minidump_system_info: Option<&MinidumpSystemInfo>,
+) -> String {
+    let exception_record = &minidump_exception.raw.exception_record;
+    let Some(minidump_system_info) = minidump_system_info else {
+        return format!("exception code {:#x}", exception_record.exception_code);
+    };
+    let crash_reason =
+        minidump_exception.get_crash_reason(minidump_system_info.os, minidump_system_info.cpu);
+    // For access violations and memory faults, this is the address being accessed,
+    // rather than the address of the faulting instruction.
+    let crash_address =
+        minidump_exception.get_crash_address(minidump_system_info.os, minidump_system_info.cpu);
+    if crash_address != exception_record.exception_address {
+        format!("{} accessing address {:#x}", crash_reason, crash_address)
+    } else {
+        crash_reason.to_string()
+    }
+}
+
+/// The range of the minidump file backing each memory segment, and the range of addresses at
+/// which the segment was mapped.
+fn read_segment_ranges<'a, T>(minidump_obj: &'a Minidump<'a, T>) -> Vec<(Range<u64>, Range<u64>)>
+where
+    T: Deref<Target = [u8]> + 'a,
+{
+    let mut segment_ranges = Vec::new();
+
+    // Memory segments in a full memory dump (MinidumpMemory64List)
+    // Grab the shared base RVA for all entries in the MinidumpMemory64List,
+    // since the minidump crate doesn't expose this to us
+    if let Ok(raw_stream) = minidump_obj.get_raw_stream(MinidumpMemory64List::STREAM_TYPE) {
+        let Some(base_rva_array) = raw_stream
+            .get(8..16)
+            .and_then(|base_rva_bytes| base_rva_bytes.try_into().ok())
+        else {
+            error!("Could not parse BaseRVA value shared by all entries in the MinidumpMemory64List stream");
+            return segment_ranges;
+        };
+        let base_rva = u64::from_le_bytes(base_rva_array);
+        debug!("Found BaseRVA value {:#x}", base_rva);
+
+        if let Ok(minidump_memory_list) = minidump_obj.get_stream::<MinidumpMemory64List>() {
+            let mut current_rva = base_rva;
+            for memory_segment in minidump_memory_list.iter() {
+                debug!(
+                    "Found memory segment at RVA {:#x} with virtual address {:#x} and size {:#x}",
+                    current_rva, memory_segment.base_address, memory_segment.size,
+                );
+                segment_ranges.push((
+                    current_rva..current_rva + memory_segment.size,
+                    memory_segment.base_address..memory_segment.base_address + memory_segment.size,
+                ));
+                current_rva += memory_segment.size;
+            }
+        }
+    } else {
+        warn!("Could not read memory from minidump: could not find a valid MinidumpMemory64List stream. This minidump may not be a full memory dump. Trying to find partial dump memory from a MinidumpMemoryList now...");
+        // Memory segments in a regular memory dump (MinidumpMemoryList),
+        // i.e. one that does not include the full process memory data.
+        if let Ok(minidump_memory_list) = minidump_obj.get_stream::<MinidumpMemoryList>() {
+            for memory_segment in minidump_memory_list.by_addr() {
+                let rva = memory_segment.desc.memory.rva as u64;
+                debug!(
+                    "Found memory segment at RVA {:#x} with virtual address {:#x} and size {:#x}",
+                    rva, memory_segment.base_address, memory_segment.size
+                );
+                segment_ranges.push((
+                    rva..rva + memory_segment.size,
+                    memory_segment.base_address..memory_segment.base_address + memory_segment.size,
+                ));
+            }
+        } else {
+            error!("Could not read any memory from minidump: could not find a valid MinidumpMemory64List stream or a valid MinidumpMemoryList stream.");
+        }
+    }
+    segment_ranges
+}
+
+/// Find the protection of the region containing an address, in protection information
+/// sorted by the start address of each region.
+fn protection_containing(
+    memory_protections: &[(Range<u64>, SegmentProtection)],
+    address: u64,
+) -> Option<SegmentProtection> {
+    let index = memory_protections
+        .partition_point(|(range, _)| range.start <= address)
+        .checked_sub(1)?;
+    let (range, protection) = &memory_protections[index];
+    if range.contains(&address) {
+        Some(*protection)
+    } else {
+        None
+    }
+}
+
+/// The protection of every region of the process's address space.
+///
+/// Windows minidumps describe memory protections in the MinidumpMemoryInfoList stream;
+/// Breakpad / Crashpad minidumps from Linux and Android instead carry a copy of
+/// /proc/self/maps in the LinuxMaps stream.
+fn read_memory_protections<'a, T>(
+    minidump_obj: &'a Minidump<'a, T>,
+) -> Vec<(Range<u64>, SegmentProtection)>
+where
+    T: Deref<Target = [u8]> + 'a,
+{
+    let mut memory_protections = Vec::new();
+    if let Ok(minidump_memory_info_list) = minidump_obj.get_stream::<MinidumpMemoryInfoList>() {
+        for memory_info in minidump_memory_info_list.iter() {
+            if let Some(memory_range) = memory_info.memory_range() {
+                debug!(
+                    "Found memory protection info for memory segment ranging from virtual address {:#x} to {:#x}: {:#?}",
+                    memory_range.start,
+                    memory_range.end,
+                    memory_info.protection
+                );
+                memory_protections.push((
+                    // The range returned to us by MinidumpMemoryInfoList is an
+                    // end-inclusive range_map::Range; we need to add 1 to
+                    // the end index to make it into an end-exclusive std::ops::Range.
+                    Range {
+                        start: memory_range.start,
+                        end: memory_range.end.saturating_add(1),
+                    },
+                    SegmentProtection::from_memory_protection(memory_info.protection),
+                ));
+            }
+        }
+    } else if let Ok(minidump_linux_maps) = minidump_obj.get_stream::<MinidumpLinuxMaps>() {
+        for map_info in minidump_linux_maps.iter() {
+            if let Some(memory_range) = map_info.memory_range() {
+                debug!(
+                    "Found Linux memory mapping ranging from virtual address {:#x} to {:#x}",
+                    memory_range.start, memory_range.end,
+                );
+                memory_protections.push((
+                    // Same as above; the range is end-inclusive.
+                    Range {
+                        start: memory_range.start,
+                        end: memory_range.end.saturating_add(1),
+                    },
+                    SegmentProtection {
+                        readable: map_info.is_read,
+                        writable: map_info.is_write,
+                        executable: map_info.is_exec,
+                    },
+                ));
+            }
+        }
+    }
+    memory_protections
+}
+
+fn read_modules<'a, T>(minidump_obj: &'a Minidump<'a, T>) -> Vec<LayoutModule>
+where
+    T: Deref<Target = [u8]> + 'a,
+{
+    let Ok(minidump_module_list) = minidump_obj.get_stream::<MinidumpModuleList>() else {
+        warn!("Could not find valid module information in minidump: could not find a valid MinidumpModuleList stream");
+        return Vec::new();
+    };
+    minidump_module_list
+        .by_addr()
+        .map(|module_info| LayoutModule {
+            name: module_info.name.clone(),
+            address_range: Range {
+                start: module_info.base_address(),
+                end: module_info
+                    .base_address()
+                    .saturating_add(module_info.size()),
+            },
+        })
+        .collect()
+}
+
+fn read_threads<'a, T>(minidump_obj: &'a Minidump<'a, T>) -> Vec<LayoutThread>
+where
+    T: Deref<Target = [u8]> + 'a,
+{
+    let Ok(minidump_thread_list) = minidump_obj.get_stream::<MinidumpThreadList>() else {
+        warn!("Could not find valid thread information in minidump: could not find a valid MinidumpThreadList stream");
+        return Vec::new();
+    };
+    // Thread contexts can only be decoded once the CPU of the minidump is known
+    let minidump_system_info = minidump_obj.get_stream::<MinidumpSystemInfo>().ok();
+    let minidump_misc_info = minidump_obj.get_stream::<MinidumpMiscInfo>().ok();
+    let minidump_thread_names = minidump_obj.get_stream::<MinidumpThreadNames>().ok();
+    let is_windows = minidump_system_info
+        .as_ref()
+        .is_some_and(|minidump_system_info| minidump_system_info.os == Os::Windows);
+
+    minidump_thread_list
+        .threads
+        .iter()
+        .map(|thread| {
+            let thread_id = thread.raw.thread_id;
+            let stack_start = thread.raw.stack.start_of_memory_range;
+            let stack_size = thread.raw.stack.memory.data_size as u64;
+            let context = minidump_system_info
+                .as_ref()
+                .and_then(|minidump_system_info| {
+                    thread.context(minidump_system_info, minidump_misc_info.as_ref())
+                });
+            if context.is_none() {
+                debug!("Could not find a valid CONTEXT for thread {:#x}", thread_id);
+            }
+            LayoutThread {
+                thread_id,
+                name: minidump_thread_names
+                    .as_ref()
+                    .and_then(|thread_names| thread_names.get_name(thread_id))
+                    .map(|thread_name| thread_name.into_owned()),
+                stack_range: (stack_size != 0)
+                    .then(|| stack_start..stack_start.saturating_add(stack_size)),
+                teb: (is_windows && thread.raw.teb != 0).then_some(thread.raw.teb),
+                instruction_pointer: context
+                    .as_ref()
+                    .map(|context| context.get_instruction_pointer()),
+                stack_pointer: context.as_ref().map(|context| context.get_stack_pointer()),
+            }
+        })
+        .collect()
+}
+
+fn read_exception<'a, T>(minidump_obj: &'a Minidump<'a, T>) -> Option<LayoutException>
+where
+    T: Deref<Target = [u8]> + 'a,
+{
+    let minidump_exception = minidump_obj.get_stream::<MinidumpException>().ok()?;
+    let exception_record = &minidump_exception.raw.exception_record;
+    Some(LayoutException {
+        thread_id: minidump_exception.raw.thread_id,
+        code: exception_record.exception_code,
+        address: exception_record.exception_address,
+        description: describe_exception(
+            &minidump_exception,
+            minidump_obj
+                .get_stream::<MinidumpSystemInfo>()
+                .ok()
+                .as_ref(),
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minidump::format::MINIDUMP_SIGNATURE;
+
+    const PAGE_READONLY: u32 = 0x02;
+    const PAGE_READWRITE: u32 = 0x04;
+    const PAGE_EXECUTE_READ: u32 = 0x20;
+    const PROCESSOR_ARCHITECTURE_AMD64: u16 = 9;
+    const VER_PLATFORM_WIN32_NT: u32 = 2;
+    const PLATFORM_LINUX: u32 = 0x8201;
+    const CONTEXT_AMD64: u32 = 0x0010_0000;
+    const EXCEPTION_ACCESS_VIOLATION: u32 = 0xc000_0005;
+
+    /// A thread of a [`SynthMinidump`], with its captured stack and x64 `rip` and `rsp`.
+    struct SynthThread<'a> {
+        thread_id: u32,
+        teb: u64,
+        stack_start: u64,
+        stack: &'a [u8],
+        rip: u64,
+        rsp: u64,
+    }
+
+    /// A little-endian minidump built in memory, with the data of each stream appended in turn
+    /// after the header, and the stream directory at the end.
+    struct SynthMinidump {
+        data: Vec<u8>,
+        directory: Vec<(u32, u32, u32)>,
+    }
+
+    impl SynthMinidump {
+        fn new() -> Self {
+            SynthMinidump {
+                data: vec![0; 32],
+                directory: Vec::new(),
+            }
+        }
+
+        /// Append data which streams refer to, and return its RVA.
+        fn append(&mut self, bytes: &[u8]) -> u32 {
+            let rva = self.data.len() as u32;
+            self.data.extend_from_slice(bytes);
+            rva
+        }
+
+        fn add_stream(&mut self, stream_type: u32, bytes: &[u8]) {
+            let rva = self.append(bytes);
+            self.directory.push((stream_type, bytes.len() as u32, rva));
+        }
+
+        /// Append a length-prefixed UTF-16 string, as used for module names.
+        fn append_string(&mut self, string: &str) -> u32 {
+            let utf16: Vec<u8> = string.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            let mut bytes = (utf16.len() as u32).to_le_bytes().to_vec();
+            bytes.extend(utf16);
+            self.append(&bytes)
+        }
+
+        /// Append an x64 `CONTEXT`, and return its location.
+        fn append_x64_context(&mut self, rip: u64, rsp: u64) -> [u32; 2] {
+            let mut context = vec![0; 0x4d0];
+            context[0x30..0x34].copy_from_slice(&CONTEXT_AMD64.to_le_bytes());
+            context[0x98..0xa0].copy_from_slice(&rsp.to_le_bytes());
+            context[0xf8..0x100].copy_from_slice(&rip.to_le_bytes());
+            [context.len() as u32, self.append(&context)]
+        }
+
+        fn add_system_info(&mut self, platform_id: u32) {
+            let mut stream = Vec::new();
+            stream.extend(PROCESSOR_ARCHITECTURE_AMD64.to_le_bytes());
+            stream.extend([0; 6]);
+            stream.extend([10, 0, 0, 0, 0, 0, 0, 0, 0x65, 0x4a, 0, 0]);
+            stream.extend(platform_id.to_le_bytes());
+            stream.extend([0; 32]);
+            self.add_stream(MinidumpSystemInfo::STREAM_TYPE, &stream);
+        }
+
+        /// Add a `Memory64ListStream`, whose regions are stored one after the other.
+        fn add_memory64_list(&mut self, regions: &[(u64, &[u8])]) {
+            let base_rva = self.data.len() as u64;
+            let mut stream = (regions.len() as u64).to_le_bytes().to_vec();
+            stream.extend(base_rva.to_le_bytes());
+            for (address, contents) in regions {
+                self.append(contents);
+                stream.extend(address.to_le_bytes());
+                stream.extend((contents.len() as u64).to_le_bytes());
+            }
+            self.add_stream(MinidumpMemory64List::STREAM_TYPE, &stream);
+        }
+
+        fn add_memory_list(&mut self, regions: &[(u64, &[u8])]) {
+            let mut stream = (regions.len() as u32).to_le_bytes().to_vec();
+            for (address, contents) in regions {
+                let rva = self.append(contents);
+                stream.extend(address.to_le_bytes());
+                stream.extend((contents.len() as u32).to_le_bytes());
+                stream.extend(rva.to_le_bytes());
+            }
+            self.add_stream(MinidumpMemoryList::STREAM_TYPE, &stream);
+        }
+
+        /// Add a `MemoryInfoListStream` with the base address, size, and `PAGE_*` protection of each region.
+        fn add_memory_info_list(&mut self, regions: &[(u64, u64, u32)]) {
+            let mut stream = Vec::new();
+            stream.extend(16u32.to_le_bytes());
+            stream.extend(48u32.to_le_bytes());
+            stream.extend((regions.len() as u64).to_le_bytes());
+            for (address, size, protection) in regions {
+                stream.extend(address.to_le_bytes());
+                stream.extend(address.to_le_bytes());
+                stream.extend(protection.to_le_bytes());
+                stream.extend([0; 4]);
+                stream.extend(size.to_le_bytes());
+                // MEM_COMMIT, MEM_PRIVATE
+                stream.extend(0x1000u32.to_le_bytes());
+                stream.extend(protection.to_le_bytes());
+                stream.extend(0x20000u32.to_le_bytes());
+                stream.extend([0; 4]);
+            }
+            self.add_stream(MinidumpMemoryInfoList::STREAM_TYPE, &stream);
+        }
+
+        fn add_module_list(&mut self, modules: &[(&str, u64, u32)]) {
+            let mut stream = (modules.len() as u32).to_le_bytes().to_vec();
+            for (name, base_address, size) in modules {
+                let name_rva = self.append_string(name);
+                stream.extend(base_address.to_le_bytes());
+                stream.extend(size.to_le_bytes());
+                stream.extend([0; 8]);
+                stream.extend(name_rva.to_le_bytes());
+                // VS_FIXEDFILEINFO, the CodeView and misc records, and the reserved fields
+                stream.extend([0; 52 + 16 + 16]);
+            }
+            self.add_stream(MinidumpModuleList::STREAM_TYPE, &stream);
+        }
+
+        fn add_thread_list(&mut self, threads: &[SynthThread]) {
+            let mut stream = (threads.len() as u32).to_le_bytes().to_vec();
+            for thread in threads {
+                let stack_rva = self.append(thread.stack);
+                let [context_size, context_rva] = self.append_x64_context(thread.rip, thread.rsp);
+                stream.extend(thread.thread_id.to_le_bytes());
+                stream.extend([0; 12]);
+                stream.extend(thread.teb.to_le_bytes());
+                stream.extend(thread.stack_start.to_le_bytes());
+                stream.extend((thread.stack.len() as u32).to_le_bytes());
+                stream.extend(stack_rva.to_le_bytes());
+                stream.extend(context_size.to_le_bytes());
+                stream.extend(context_rva.to_le_bytes());
+            }
+            self.add_stream(MinidumpThreadList::STREAM_TYPE, &stream);
+        }
+
+        fn add_exception(&mut self, thread_id: u32, code: u32, address: u64, parameters: &[u64]) {
+            let [context_size, context_rva] = self.append_x64_context(address, 0);
+            let mut stream = Vec::new();
+            stream.extend(thread_id.to_le_bytes());
+            stream.extend([0; 4]);
+            stream.extend(code.to_le_bytes());
+            stream.extend([0; 12]);
+            stream.extend(address.to_le_bytes());
+            stream.extend((parameters.len() as u32).to_le_bytes());
+            stream.extend([0; 4]);
+            for index in 0..15 {
+                stream.extend(parameters.get(index).copied().unwrap_or(0).to_le_bytes());
+            }
+            stream.extend(context_size.to_le_bytes());
+            stream.extend(context_rva.to_le_bytes());
+            self.add_stream(MinidumpException::STREAM_TYPE, &stream);
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            let directory_rva = self.data.len() as u32;
+            for (stream_type, size, rva) in &self.directory {
+                self.data.extend(stream_type.to_le_bytes());
+                self.data.extend(size.to_le_bytes());
+                self.data.extend(rva.to_le_bytes());
+            }
+            let mut header = MINIDUMP_SIGNATURE.to_le_bytes().to_vec();
+            header.extend(0xa793u32.to_le_bytes());
+            header.extend((self.directory.len() as u32).to_le_bytes());
+            header.extend(directory_rva.to_le_bytes());
+            self.data[..header.len()].copy_from_slice(&header);
+            self.data
+        }
+    }
+
+    fn read_layout(dump_data: &[u8]) -> DumpLayout {
+        let minidump_obj = Minidump::read(dump_data).expect("synthesized minidump should parse");
+        DumpLayout::read(&minidump_obj)
+    }
+
+    const READ_ONLY: SegmentProtection = SegmentProtection {
+        readable: true,
+        writable: false,
+        executable: false,
+    };
+    const READ_EXECUTE: SegmentProtection = SegmentProtection {
+        readable: true,
+        writable: false,
+        executable: true,
+    };
+
+    #[test]
+    fn finds_protection_of_containing_region() {
+        let memory_protections = vec![(0x1000..0x3000, READ_ONLY), (0x5000..0x6000, READ_EXECUTE)];
+        assert_eq!(protection_containing(&memory_protections, 0xfff), None);
+        assert_eq!(
+            protection_containing(&memory_protections, 0x1000),
+            Some(READ_ONLY)
+        );
+        assert_eq!(
+            protection_containing(&memory_protections, 0x2fff),
+            Some(READ_ONLY)
+        );
+        assert_eq!(protection_containing(&memory_protections, 0x3000), None);
+        assert_eq!(
+            protection_containing(&memory_protections, 0x5800),
+            Some(READ_EXECUTE)
+        );
+        assert_eq!(protection_containing(&memory_protections, u64::MAX), None);
+        assert_eq!(protection_containing(&[], 0x1000), None);
+    }
+
+    const READ_WRITE: SegmentProtection = SegmentProtection {
+        readable: true,
+        writable: true,
+        executable: false,
+    };
+
+    #[test]
+    fn reads_full_dump_segments_with_protections() {
+        let mut synth = SynthMinidump::new();
+        synth.add_memory64_list(&[
+            (0x10000, &[0xaa; 0x20]),
+            (0x20000, &[0xbb; 0x10]),
+            (0x30000, &[0xcc; 0x8]),
+        ]);
+        synth.add_memory_info_list(&[
+            (0x10000, 0x1000, PAGE_EXECUTE_READ),
+            (0x1f000, 0x2000, PAGE_READWRITE),
+        ]);
+        let dump_data = synth.finish();
+        let layout = read_layout(&dump_data);
+
+        let address_ranges: Vec<_> = layout
+            .segments
+            .iter()
+            .map(|segment| segment.address_range.clone())
+            .collect();
+        assert_eq!(
+            address_ranges,
+            [0x10000..0x10020, 0x20000..0x20010, 0x30000..0x30008]
+        );
+        // The regions of a full dump are stored one after the other, from the shared base RVA
+        let backing_ranges: Vec<_> = layout
+            .segments
+            .iter()
+            .map(|segment| segment.backing_range.clone())
+            .collect();
+        assert_eq!(backing_ranges, [32..64, 64..80, 80..88]);
+        assert_eq!(&dump_data[64..80], &[0xbb; 0x10]);
+        let protections: Vec<_> = layout
+            .segments
+            .iter()
+            .map(|segment| segment.protection)
+            .collect();
+        assert_eq!(protections, [Some(READ_EXECUTE), Some(READ_WRITE), None]);
+    }
+
+    #[test]
+    fn reads_partial_dump_segments_by_address() {
+        let mut synth = SynthMinidump::new();
+        synth.add_memory_list(&[(0x20000, &[0xbb; 0x10]), (0x10000, &[0xaa; 0x20])]);
+        synth.add_memory_info_list(&[(0x10000, 0x1000, PAGE_READONLY)]);
+        let dump_data = synth.finish();
+        let layout = read_layout(&dump_data);
+
+        assert_eq!(layout.segments.len(), 2);
+        let first = &layout.segments[0];
+        assert_eq!(first.address_range, 0x10000..0x10020);
+        assert_eq!(
+            &dump_data[first.backing_range.start as usize..first.backing_range.end as usize],
+            &[0xaa; 0x20]
+        );
+        assert_eq!(first.protection, Some(READ_ONLY));
+        let second = &layout.segments[1];
+        assert_eq!(second.address_range, 0x20000..0x20010);
+        assert_eq!(
+            &dump_data[second.backing_range.start as usize..second.backing_range.end as usize],
+            &[0xbb; 0x10]
+        );
+        assert_eq!(second.protection, None);
+    }
+
+    #[test]
+    fn reads_modules_by_address() {
+        let mut synth = SynthMinidump::new();
+        synth.add_module_list(&[
+            (
+                r"C:\Windows\System32\kernel32.dll",
+                0x7ff8_0000_0000,
+                0x10_0000,
+            ),
+            (r"C:\test\crash.exe", 0x1_4000_0000, 0x5000),
+        ]);
+        let layout = read_layout(&synth.finish());
+
+        assert_eq!(
+            layout.modules,
+            [
+                LayoutModule {
+                    name: r"C:\test\crash.exe".to_string(),
+                    address_range: 0x1_4000_0000..0x1_4000_5000,
+                },
+                LayoutModule {
+                    name: r"C:\Windows\System32\kernel32.dll".to_string(),
+                    address_range: 0x7ff8_0000_0000..0x7ff8_0010_0000,
+                },
+            ]
+        );
+        assert_eq!(module_file_name(&layout.modules[0].name), "crash.exe");
+        assert_eq!(module_file_name("/usr/lib/libc.so.6"), "libc.so.6");
+        assert_eq!(module_file_name("crash.exe"), "crash.exe");
+    }
+
+    #[test]
+    fn reads_threads_and_exception() {
+        let mut synth = SynthMinidump::new();
+        synth.add_system_info(VER_PLATFORM_WIN32_NT);
+        synth.add_thread_list(&[
+            SynthThread {
+                thread_id: 0x1a2c,
+                teb: 0xa0_0000_0000,
+                stack_start: 0xa0_0000_f000,
+                stack: &[0; 0x100],
+                rip: 0x1_4000_1234,
+                rsp: 0xa0_0000_f010,
+            },
+            // A thread without a TEB or a captured stack
+            SynthThread {
+                thread_id: 0x2b3c,
+                teb: 0,
+                stack_start: 0,
+                stack: &[],
+                rip: 0x7ff8_0000_5678,
+                rsp: 0xb0_0000_f000,
+            },
+        ]);
+        synth.add_exception(
+            0x1a2c,
+            EXCEPTION_ACCESS_VIOLATION,
+            0x1_4000_1234,
+            &[0, 0x10],
+        );
+        let layout = read_layout(&synth.finish());
+
+        assert_eq!(
+            layout.threads,
+            [
+                LayoutThread {
+                    thread_id: 0x1a2c,
+                    name: None,
+                    stack_range: Some(0xa0_0000_f000..0xa0_0000_f100),
+                    teb: Some(0xa0_0000_0000),
+                    instruction_pointer: Some(0x1_4000_1234),
+                    stack_pointer: Some(0xa0_0000_f010),
+                },
+                LayoutThread {
+                    thread_id: 0x2b3c,
+                    name: None,
+                    stack_range: None,
+                    teb: None,
+                    instruction_pointer: Some(0x7ff8_0000_5678),
+                    stack_pointer: Some(0xb0_0000_f000),
+                },
+            ]
+        );
+        assert_eq!(
+            layout.exception,
+            Some(LayoutException {
+                thread_id: 0x1a2c,
+                code: EXCEPTION_ACCESS_VIOLATION,
+                address: 0x1_4000_1234,
+                description: "EXCEPTION_ACCESS_VIOLATION_READ accessing address 0x10".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_tebs_outside_of_windows() {
+        let mut synth = SynthMinidump::new();
+        synth.add_system_info(PLATFORM_LINUX);
+        synth.add_thread_list(&[SynthThread {
+            thread_id: 0x1a2c,
+            teb: 0x7f00_0000_0000,
+            stack_start: 0,
+            stack: &[],
+            rip: 0x5555_0000_1234,
+            rsp: 0x7ffc_0000_f010,
+        }]);
+        let layout = read_layout(&synth.finish());
+
+        assert_eq!(layout.threads.len(), 1);
+        assert_eq!(layout.threads[0].teb, None);
+        assert_eq!(
+            layout.threads[0].instruction_pointer,
+            Some(0x5555_0000_1234)
+        );
+    }
+
+    #[test]
+    fn reads_threads_without_system_info() {
+        let mut synth = SynthMinidump::new();
+        synth.add_thread_list(&[SynthThread {
+            thread_id: 0x1a2c,
+            teb: 0,
+            stack_start: 0,
+            stack: &[],
+            rip: 0x1_4000_1234,
+            rsp: 0xa0_0000_f010,
+        }]);
+        synth.add_exception(
+            0x1a2c,
+            EXCEPTION_ACCESS_VIOLATION,
+            0x1_4000_1234,
+            &[0, 0x10],
+        );
+        let layout = read_layout(&synth.finish());
+
+        // Thread contexts and exception codes can't be decoded without knowing the CPU and OS
+        assert_eq!(layout.threads[0].instruction_pointer, None);
+        assert_eq!(layout.threads[0].stack_pointer, None);
+        assert_eq!(
+            layout.exception.map(|exception| exception.description),
+            Some("exception code 0xc0000005".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_empty_layout_without_streams() {
+        let layout = read_layout(&SynthMinidump::new().finish());
+        assert_eq!(layout, DumpLayout::default());
+    }
+
+    #[test]
+    fn translates_page_protections() {
+        assert_eq!(
+            SegmentProtection::from_memory_protection(MemoryProtection::PAGE_EXECUTE_READ),
+            READ_EXECUTE
+        );
+        assert_eq!(
+            SegmentProtection::from_memory_protection(MemoryProtection::PAGE_READONLY),
+            READ_ONLY
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! The Binary Ninja-independent part of the minidump loader: the layout of the process captured
+//! in a minidump ([`layout`]), and parsers for the executable headers, symbol files, unwind
+//! information, and extended register state which the loader reads from it.
+//!
+//! This crate doesn't link against the Binary Ninja core, so it can be used by other Rust code,
+//! and tested, without a Binary Ninja installation.
+
+pub mod breakpad_symbols;
+pub mod elf;
+pub mod extended_registers;
+pub mod layout;
+pub mod linux;
+pub mod macho;
+pub mod memory;
+pub mod pdb_symbols;
+pub mod pe;
+pub mod streams;
+pub mod unwind;
@@ -0,0 +1,348 @@
+use log::{debug, warn};
+use minidump::format::MINIDUMP_STREAM_TYPE;
+use minidump::{Endian, Minidump};
+
+/// Auxiliary vector entry type holding the entry point of the main executable.
+pub const AT_ENTRY: u64 = 9;
+
+/// A loaded shared object, as recorded in the `r_debug` link map of the dumped process
+/// and written out by Breakpad / Crashpad in the `LinuxDsoDebug` stream.
+#[derive(Debug, Clone)]
+pub struct LinkMapEntry {
+    /// The load bias of the shared object (`l_addr`).
+    pub load_bias: u64,
+    /// The address of the shared object's dynamic section (`l_ld`).
+    pub dynamic: u64,
+    pub name: String,
+}
+
+/// The Linux-specific process information which Breakpad and Crashpad write into minidumps,
+/// in addition to the streams also found in Windows minidumps.
+///
+/// The `minidump` crate only parses some of these streams (e.g. `LinuxMaps`, `LinuxProcStatus`);
+/// the rest are read here from their raw stream data.
+#[derive(Debug, Default)]
+pub struct LinuxProcessInfo {
+    pub command_line: Vec<String>,
+    pub environment: Vec<String>,
+    pub auxv: Vec<(u64, u64)>,
+    pub link_map: Vec<LinkMapEntry>,
+    pub proc_status: Option<String>,
+    pub lsb_release: Option<String>,
+    pub cpu_info: Option<String>,
+}
+
+impl LinuxProcessInfo {
+    /// Read all of the Linux-specific streams present in a minidump.
+    ///
+    /// `dump_data` is the full contents of the minidump file, which is needed to resolve
+    /// the RVAs of the strings referenced by the `LinuxDsoDebug` stream.
+    /// Returns `None` if the minidump contains none of these streams.
+    pub fn read<T>(minidump_obj: &Minidump<T>, dump_data: &[u8], is_64bit: bool) -> Option<Self>
+    where
+        T: std::ops::Deref<Target = [u8]>,
+    {
+        let endian = minidump_obj.endian;
+        let raw_stream = |stream_type: MINIDUMP_STREAM_TYPE| {
+            minidump_obj.get_raw_stream(stream_type as u32).ok()
+        };
+
+        let mut found_stream = false;
+        let mut linux_info = LinuxProcessInfo::default();
+
+        if let Some(data) = raw_stream(MINIDUMP_STREAM_TYPE::LinuxCmdLine) {
+            found_stream = true;
+            linux_info.command_line = split_nul_separated(data);
+        }
+        if let Some(data) = raw_stream(MINIDUMP_STREAM_TYPE::LinuxEnviron) {
+            found_stream = true;
+            linux_info.environment = split_nul_separated(data);
+        }
+        if let Some(data) = raw_stream(MINIDUMP_STREAM_TYPE::LinuxAuxv) {
+            found_stream = true;
+            linux_info.auxv = parse_auxv(data, endian, is_64bit);
+        }
+        if let Some(data) = raw_stream(MINIDUMP_STREAM_TYPE::LinuxDsoDebug) {
+            found_stream = true;
+            match parse_dso_debug(data, dump_data, endian, is_64bit) {
+                Some(link_map) => linux_info.link_map = link_map,
+                None => warn!("Could not parse the LinuxDsoDebug stream in the minidump"),
+            }
+        }
+        if let Some(data) = raw_stream(MINIDUMP_STREAM_TYPE::LinuxProcStatus) {
+            found_stream = true;
+            linux_info.proc_status = Some(String::from_utf8_lossy(data).into_owned());
+        }
+        if let Some(data) = raw_stream(MINIDUMP_STREAM_TYPE::LinuxLsbRelease) {
+            found_stream = true;
+            linux_info.lsb_release = Some(String::from_utf8_lossy(data).into_owned());
+        }
+        if let Some(data) = raw_stream(MINIDUMP_STREAM_TYPE::LinuxCpuInfo) {
+            found_stream = true;
+            linux_info.cpu_info = Some(String::from_utf8_lossy(data).into_owned());
+        }
+
+        if found_stream {
+            Some(linux_info)
+        } else {
+            None
+        }
+    }
+
+    /// Look up the value of an entry in the auxiliary vector of the dumped process.
+    pub fn auxv_value(&self, auxv_type: u64) -> Option<u64> {
+        self.auxv
+            .iter()
+            .find(|(entry_type, _)| *entry_type == auxv_type)
+            .map(|(_, value)| *value)
+    }
+}
+
+fn split_nul_separated(data: &[u8]) -> Vec<String> {
+    data.split(|byte| *byte == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| String::from_utf8_lossy(entry).into_owned())
+        .collect()
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize, endian: Endian) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+    Some(match endian {
+        Endian::Little => u32::from_le_bytes(bytes),
+        Endian::Big => u32::from_be_bytes(bytes),
+    })
+}
+
+pub(crate) fn read_u64(data: &[u8], offset: usize, endian: Endian) -> Option<u64> {
+    let bytes = data.get(offset..offset.checked_add(8)?)?.try_into().ok()?;
+    Some(match endian {
+        Endian::Little => u64::from_le_bytes(bytes),
+        Endian::Big => u64::from_be_bytes(bytes),
+    })
+}
+
+/// Read a pointer-sized value, for a process with either 32-bit or 64-bit pointers.
+pub(crate) fn read_pointer(
+    data: &[u8],
+    offset: usize,
+    endian: Endian,
+    is_64bit: bool,
+) -> Option<u64> {
+    if is_64bit {
+        read_u64(data, offset, endian)
+    } else {
+        read_u32(data, offset, endian).map(u64::from)
+    }
+}
+
+fn parse_auxv(data: &[u8], endian: Endian, is_64bit: bool) -> Vec<(u64, u64)> {
+    let pointer_size = if is_64bit { 8 } else { 4 };
+    let mut auxv = Vec::new();
+    let mut offset = 0;
+    while let (Some(entry_type), Some(value)) = (
+        read_pointer(data, offset, endian, is_64bit),
+        read_pointer(data, offset + pointer_size, endian, is_64bit),
+    ) {
+        // The auxiliary vector is terminated by an AT_NULL entry
+        if entry_type == 0 {
+            break;
+        }
+        auxv.push((entry_type, value));
+        offset += 2 * pointer_size;
+    }
+    auxv
+}
+
+/// Read a `MINIDUMP_STRING` (a 32-bit byte length, followed by UTF-16 data) at an RVA in the minidump.
+pub(crate) fn read_minidump_string(dump_data: &[u8], rva: u32, endian: Endian) -> Option<String> {
+    let rva = rva as usize;
+    let length = read_u32(dump_data, rva, endian)? as usize;
+    let string_start = rva.checked_add(4)?;
+    let string_data = dump_data.get(string_start..string_start.checked_add(length)?)?;
+    let utf16: Vec<u16> = string_data
+        .chunks_exact(2)
+        .map(|pair| match endian {
+            Endian::Little => u16::from_le_bytes([pair[0], pair[1]]),
+            Endian::Big => u16::from_be_bytes([pair[0], pair[1]]),
+        })
+        .collect();
+    Some(String::from_utf16_lossy(&utf16))
+}
+
+/// Parse the `MDRawDebug` structure in a `LinuxDsoDebug` stream,
+/// along with the array of `MDRawLinkMap` structures which it points to.
+fn parse_dso_debug(
+    data: &[u8],
+    dump_data: &[u8],
+    endian: Endian,
+    is_64bit: bool,
+) -> Option<Vec<LinkMapEntry>> {
+    // struct MDRawDebug {
+    //     uint32_t version;
+    //     MDRVA map;
+    //     uint32_t dso_count;
+    //     void* brk;
+    //     void* ldbase;
+    //     void* dynamic;
+    // }
+    let version = read_u32(data, 0, endian)?;
+    let map_rva = read_u32(data, 4, endian)? as usize;
+    let dso_count = read_u32(data, 8, endian)? as usize;
+    debug!(
+        "Found LinuxDsoDebug stream with version {}, {} shared objects",
+        version, dso_count
+    );
+
+    // struct MDRawLinkMap {
+    //     void* addr;
+    //     MDRVA name;
+    //     void* ld;
+    // }
+    let (entry_size, name_offset, ld_offset) = if is_64bit { (24, 8, 16) } else { (12, 4, 8) };
+
+    // The count comes from the minidump, so don't trust it beyond the number of entries
+    // which could fit in the rest of the file
+    let dso_count = dso_count.min(dump_data.len().saturating_sub(map_rva) / entry_size);
+    let mut link_map = Vec::new();
+    for index in 0..dso_count {
+        let entry_offset = map_rva + index * entry_size;
+        // Keep the entries which were read before a truncated one
+        let (Some(load_bias), Some(name_rva), Some(dynamic)) = (
+            read_pointer(dump_data, entry_offset, endian, is_64bit),
+            read_u32(dump_data, entry_offset + name_offset, endian),
+            read_pointer(dump_data, entry_offset + ld_offset, endian, is_64bit),
+        ) else {
+            break;
+        };
+        let name = read_minidump_string(dump_data, name_rva, endian).unwrap_or_default();
+        link_map.push(LinkMapEntry {
+            load_bias,
+            dynamic,
+            name,
+        });
+    }
+    Some(link_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_u32(data: &mut Vec<u8>, value: u32) {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn push_u64(data: &mut Vec<u8>, value: u64) {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn splits_nul_separated_strings() {
+        assert_eq!(
+            split_nul_separated(b"/bin/app\0--flag\0\0"),
+            vec!["/bin/app".to_string(), "--flag".to_string()]
+        );
+    }
+
+    #[test]
+    fn parses_auxv_until_at_null() {
+        let mut data = Vec::new();
+        for value in [AT_ENTRY, 0x40_1000, 0, 0, 6, 0x1000] {
+            push_u64(&mut data, value);
+        }
+        let auxv = parse_auxv(&data, Endian::Little, true);
+        assert_eq!(auxv, vec![(AT_ENTRY, 0x40_1000)]);
+    }
+
+    #[test]
+    fn parses_32bit_auxv() {
+        let mut data = Vec::new();
+        for value in [AT_ENTRY, 0x0804_8000, 6, 0x1000] {
+            push_u32(&mut data, value as u32);
+        }
+        let linux_info = LinuxProcessInfo {
+            auxv: parse_auxv(&data, Endian::Little, false),
+            ..Default::default()
+        };
+        assert_eq!(linux_info.auxv_value(AT_ENTRY), Some(0x0804_8000));
+        assert_eq!(linux_info.auxv_value(6), Some(0x1000));
+        assert_eq!(linux_info.auxv_value(3), None);
+    }
+
+    #[test]
+    fn reads_minidump_string() {
+        let mut dump_data = vec![0; 4];
+        push_u32(&mut dump_data, 6);
+        dump_data.extend("abc".encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        assert_eq!(
+            read_minidump_string(&dump_data, 4, Endian::Little),
+            Some("abc".to_string())
+        );
+        // A length which runs past the end of the file
+        dump_data[4] = 0xff;
+        assert_eq!(read_minidump_string(&dump_data, 4, Endian::Little), None);
+        assert_eq!(
+            read_minidump_string(&dump_data, u32::MAX, Endian::Little),
+            None
+        );
+    }
+
+    /// A 64-bit minidump with a link map of two shared objects at RVA 0x10, and their names after it.
+    fn dso_debug_dump_data() -> Vec<u8> {
+        let mut dump_data = vec![0; 0x10];
+        let names_rva = 0x10 + 2 * 24;
+        for (index, (load_bias, dynamic)) in
+            [(0x7f00_0000, 0x7f00_2000), (0x7f10_0000, 0x7f10_3000)]
+                .into_iter()
+                .enumerate()
+        {
+            push_u64(&mut dump_data, load_bias);
+            push_u32(&mut dump_data, names_rva + index as u32 * 12);
+            push_u32(&mut dump_data, 0);
+            push_u64(&mut dump_data, dynamic);
+        }
+        for name in ["liba", "libb"] {
+            push_u32(&mut dump_data, 8);
+            dump_data.extend(name.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        }
+        dump_data
+    }
+
+    fn dso_debug_stream(dso_count: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        push_u32(&mut data, 1);
+        push_u32(&mut data, 0x10);
+        push_u32(&mut data, dso_count);
+        data
+    }
+
+    #[test]
+    fn parses_dso_debug_link_map() {
+        let dump_data = dso_debug_dump_data();
+        let link_map =
+            parse_dso_debug(&dso_debug_stream(2), &dump_data, Endian::Little, true).unwrap();
+        assert_eq!(link_map.len(), 2);
+        assert_eq!(link_map[0].load_bias, 0x7f00_0000);
+        assert_eq!(link_map[0].dynamic, 0x7f00_2000);
+        assert_eq!(link_map[0].name, "liba");
+        assert_eq!(link_map[1].load_bias, 0x7f10_0000);
+        assert_eq!(link_map[1].name, "libb");
+    }
+
+    #[test]
+    fn clamps_dso_count_to_file_size() {
+        let dump_data = dso_debug_dump_data();
+        // Only three entries fit in the rest of the file: the two real entries, and the names after
+        // them read as a third. The hundreds of millions of others aren't read or allocated.
+        let link_map = parse_dso_debug(
+            &dso_debug_stream(u32::MAX),
+            &dump_data,
+            Endian::Little,
+            true,
+        )
+        .unwrap();
+        assert_eq!(link_map.len(), 3);
+        assert_eq!(link_map[0].name, "liba");
+        assert_eq!(link_map[1].name, "libb");
+    }
+}
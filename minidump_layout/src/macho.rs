@@ -0,0 +1,373 @@
+use minidump::system_info::Cpu;
+
+use crate::memory::DumpMemory;
+
+pub const MH_MAGIC: u32 = 0xfeedface;
+pub const MH_MAGIC_64: u32 = 0xfeedfacf;
+
+pub const CPU_ARCH_ABI64: u32 = 0x0100_0000;
+pub const CPU_TYPE_X86: u32 = 7;
+pub const CPU_TYPE_X86_64: u32 = CPU_TYPE_X86 | CPU_ARCH_ABI64;
+pub const CPU_TYPE_ARM: u32 = 12;
+pub const CPU_TYPE_ARM64: u32 = CPU_TYPE_ARM | CPU_ARCH_ABI64;
+pub const CPU_TYPE_POWERPC: u32 = 18;
+pub const CPU_TYPE_POWERPC64: u32 = CPU_TYPE_POWERPC | CPU_ARCH_ABI64;
+
+const LC_SEGMENT: u32 = 0x1;
+const LC_SEGMENT_64: u32 = 0x19;
+const LC_MAIN: u32 = 0x8000_0028;
+
+const SECTION_TYPE: u32 = 0xff;
+const S_MOD_INIT_FUNC_POINTERS: u32 = 0x9;
+const S_INIT_FUNC_OFFSETS: u32 = 0x16;
+
+/// An upper bound on the number of entries read from tables in the dumped process's memory,
+/// in case the tables are damaged.
+const MAX_TABLE_ENTRIES: u64 = 0x1000;
+
+/// An upper bound on the size of the load commands read from the dumped process's memory, in case
+/// the header is damaged. Real images have at most a few dozen kilobytes of load commands.
+const MAX_LOAD_COMMANDS_SIZE: u32 = 0x10_0000;
+
+#[derive(Debug)]
+pub struct MachSegment {
+    pub name: String,
+    pub vmaddr: u64,
+    pub vmsize: u64,
+    pub fileoff: u64,
+    pub sections: Vec<MachSection>,
+}
+
+#[derive(Debug)]
+pub struct MachSection {
+    pub addr: u64,
+    pub size: u64,
+    pub flags: u32,
+}
+
+/// The `mach_header` and load commands of a Mach-O image which has been mapped into the memory
+/// of the dumped process.
+///
+/// Only little-endian Mach-O images are supported, since every platform which
+/// writes minidumps for Mach-O processes (macOS and iOS) is little-endian.
+#[derive(Debug)]
+pub struct MachHeader {
+    pub base_address: u64,
+    pub is_64bit: bool,
+    pub cpu_type: u32,
+    pub segments: Vec<MachSegment>,
+    /// The file offset of the entry point, from the `LC_MAIN` load command.
+    pub entry_offset: Option<u64>,
+}
+
+fn fixed_string(data: &[u8]) -> String {
+    let end = data
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+impl MachHeader {
+    pub fn read(memory: &DumpMemory, base_address: u64) -> Option<Self> {
+        let header = memory.read(base_address, 0x20)?;
+        let magic = u32_at(&header, 0)?;
+        let is_64bit = match magic {
+            MH_MAGIC => false,
+            MH_MAGIC_64 => true,
+            _ => return None,
+        };
+        let cpu_type = u32_at(&header, 4)?;
+        let ncmds = u32_at(&header, 0x10)?;
+        let sizeofcmds = u32_at(&header, 0x14)?.min(MAX_LOAD_COMMANDS_SIZE);
+
+        let mut mach_header = MachHeader {
+            base_address,
+            is_64bit,
+            cpu_type,
+            segments: Vec::new(),
+            entry_offset: None,
+        };
+
+        // The load commands are optional for our purposes;
+        // the header alone is enough to determine the architecture.
+        let header_size = if is_64bit { 0x20 } else { 0x1c };
+        if let Some(load_commands) =
+            base_address
+                .checked_add(header_size)
+                .and_then(|load_commands_address| {
+                    memory.read(load_commands_address, sizeofcmds as usize)
+                })
+        {
+            mach_header.parse_load_commands(&load_commands, ncmds);
+        }
+
+        Some(mach_header)
+    }
+
+    fn parse_load_commands(&mut self, load_commands: &[u8], ncmds: u32) {
+        let mut offset = 0;
+        for _ in 0..ncmds {
+            let (Some(cmd), Some(cmdsize)) = (
+                u32_at(load_commands, offset),
+                u32_at(load_commands, offset + 4),
+            ) else {
+                break;
+            };
+            let Some(command) = load_commands
+                .get(offset..)
+                .and_then(|commands| commands.get(..cmdsize as usize))
+            else {
+                break;
+            };
+            match cmd {
+                LC_SEGMENT | LC_SEGMENT_64 => {
+                    if let Some(segment) = MachHeader::parse_segment(command, cmd == LC_SEGMENT_64)
+                    {
+                        self.segments.push(segment);
+                    }
+                }
+                LC_MAIN => self.entry_offset = u64_at(command, 8),
+                _ => {}
+            }
+            if cmdsize == 0 {
+                break;
+            }
+            offset += cmdsize as usize;
+        }
+    }
+
+    fn parse_segment(command: &[u8], is_64bit: bool) -> Option<MachSegment> {
+        let name = fixed_string(command.get(8..0x18)?);
+        let (vmaddr, vmsize, fileoff, nsects, sections_offset, section_size) = if is_64bit {
+            (
+                u64_at(command, 0x18)?,
+                u64_at(command, 0x20)?,
+                u64_at(command, 0x28)?,
+                u32_at(command, 0x40)?,
+                0x48,
+                0x50,
+            )
+        } else {
+            (
+                u32_at(command, 0x18)? as u64,
+                u32_at(command, 0x1c)? as u64,
+                u32_at(command, 0x20)? as u64,
+                u32_at(command, 0x30)?,
+                0x38,
+                0x44,
+            )
+        };
+
+        let mut sections = Vec::new();
+        for index in 0..nsects as usize {
+            let Some(section) = command.get(sections_offset + index * section_size..) else {
+                break;
+            };
+            let parsed_section = if is_64bit {
+                MachSection {
+                    addr: u64_at(section, 0x20)?,
+                    size: u64_at(section, 0x28)?,
+                    flags: u32_at(section, 0x40)?,
+                }
+            } else {
+                MachSection {
+                    addr: u32_at(section, 0x20)? as u64,
+                    size: u32_at(section, 0x24)? as u64,
+                    flags: u32_at(section, 0x38)?,
+                }
+            };
+            sections.push(parsed_section);
+        }
+
+        Some(MachSegment {
+            name,
+            vmaddr,
+            vmsize,
+            fileoff,
+            sections,
+        })
+    }
+
+    /// The difference between the address at which the image is mapped in the dumped process
+    /// and the virtual address of its `__TEXT` segment.
+    pub fn slide(&self) -> u64 {
+        self.segments
+            .iter()
+            .find(|segment| segment.name == "__TEXT")
+            .map_or(0, |text_segment| {
+                self.base_address.wrapping_sub(text_segment.vmaddr)
+            })
+    }
+
+    /// The virtual address of the image's entry point, from the `LC_MAIN` load command.
+    pub fn entry_point(&self) -> Option<u64> {
+        let entry_offset = self.entry_offset?;
+        let segment = self.segments.iter().find(|segment| {
+            segment.fileoff <= entry_offset && entry_offset - segment.fileoff < segment.vmsize
+        })?;
+        Some(
+            segment
+                .vmaddr
+                .wrapping_add(entry_offset - segment.fileoff)
+                .wrapping_add(self.slide()),
+        )
+    }
+
+    /// The virtual addresses of the image's initializers, from its
+    /// `__mod_init_func` (`S_MOD_INIT_FUNC_POINTERS`) and `__init_offsets` (`S_INIT_FUNC_OFFSETS`) sections.
+    pub fn initializers(&self, memory: &DumpMemory) -> Vec<u64> {
+        let mut initializers = Vec::new();
+        let slide = self.slide();
+        let pointer_size = if self.is_64bit { 8 } else { 4 };
+
+        for section in self
+            .segments
+            .iter()
+            .flat_map(|segment| segment.sections.iter())
+        {
+            let section_address = section.addr.wrapping_add(slide);
+            match section.flags & SECTION_TYPE {
+                // Pointers to the initializers, which have already been rebased by dyld
+                S_MOD_INIT_FUNC_POINTERS => {
+                    for index in 0..(section.size / pointer_size).min(MAX_TABLE_ENTRIES) {
+                        if let Some(initializer) = memory.read_pointer(
+                            section_address.wrapping_add(index * pointer_size),
+                            self.is_64bit,
+                        ) {
+                            initializers.push(initializer);
+                        }
+                    }
+                }
+                // 32-bit offsets of the initializers from the start of the image
+                S_INIT_FUNC_OFFSETS => {
+                    for index in 0..(section.size / 4).min(MAX_TABLE_ENTRIES) {
+                        if let Some(initializer_offset) =
+                            memory.read_u32(section_address.wrapping_add(index * 4))
+                        {
+                            initializers
+                                .push(self.base_address.wrapping_add(initializer_offset as u64));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        initializers
+    }
+
+    /// The CPU architecture of the image, in the same form as reported by a `MinidumpSystemInfo` stream.
+    pub fn minidump_cpu(&self) -> Option<Cpu> {
+        match self.cpu_type {
+            CPU_TYPE_X86 => Some(Cpu::X86),
+            CPU_TYPE_X86_64 => Some(Cpu::X86_64),
+            CPU_TYPE_ARM => Some(Cpu::Arm),
+            CPU_TYPE_ARM64 => Some(Cpu::Arm64),
+            CPU_TYPE_POWERPC => Some(Cpu::Ppc),
+            CPU_TYPE_POWERPC64 => Some(Cpu::Ppc64),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_ADDRESS: u64 = 0x1_0000_0000;
+
+    /// A 64-bit Mach-O image with a `__TEXT` segment containing a `__mod_init_func` section,
+    /// and an `LC_MAIN` command.
+    fn mach_o_image(sizeofcmds: Option<u32>) -> Vec<u8> {
+        let mut segment = Vec::new();
+        segment.extend_from_slice(&LC_SEGMENT_64.to_le_bytes());
+        segment.extend_from_slice(&(0x48u32 + 0x50).to_le_bytes());
+        segment.extend_from_slice(b"__TEXT\0\0\0\0\0\0\0\0\0\0");
+        segment.extend_from_slice(&BASE_ADDRESS.to_le_bytes()); // vmaddr
+        segment.extend_from_slice(&0x1000u64.to_le_bytes()); // vmsize
+        segment.extend_from_slice(&0u64.to_le_bytes()); // fileoff
+        segment.extend_from_slice(&0x1000u64.to_le_bytes()); // filesize
+        segment.extend_from_slice(&[0; 8]); // maxprot, initprot
+        segment.extend_from_slice(&1u32.to_le_bytes()); // nsects
+        segment.extend_from_slice(&0u32.to_le_bytes()); // flags
+        let mut section = vec![0; 0x50];
+        section[0..15].copy_from_slice(b"__mod_init_func");
+        section[0x10..0x16].copy_from_slice(b"__TEXT");
+        section[0x20..0x28].copy_from_slice(&(BASE_ADDRESS + 0x200).to_le_bytes());
+        section[0x28..0x30].copy_from_slice(&0x10u64.to_le_bytes());
+        section[0x40..0x44].copy_from_slice(&S_MOD_INIT_FUNC_POINTERS.to_le_bytes());
+        segment.extend_from_slice(&section);
+
+        let mut main = Vec::new();
+        main.extend_from_slice(&LC_MAIN.to_le_bytes());
+        main.extend_from_slice(&0x18u32.to_le_bytes());
+        main.extend_from_slice(&0x180u64.to_le_bytes()); // entryoff
+        main.extend_from_slice(&0u64.to_le_bytes()); // stacksize
+
+        let load_commands_size = (segment.len() + main.len()) as u32;
+        let mut image = Vec::new();
+        image.extend_from_slice(&MH_MAGIC_64.to_le_bytes());
+        image.extend_from_slice(&CPU_TYPE_ARM64.to_le_bytes());
+        image.extend_from_slice(&0u32.to_le_bytes()); // cpusubtype
+        image.extend_from_slice(&2u32.to_le_bytes()); // filetype
+        image.extend_from_slice(&2u32.to_le_bytes()); // ncmds
+        image.extend_from_slice(&sizeofcmds.unwrap_or(load_commands_size).to_le_bytes());
+        image.extend_from_slice(&0u32.to_le_bytes()); // flags
+        image.extend_from_slice(&0u32.to_le_bytes()); // reserved
+        image.extend_from_slice(&segment);
+        image.extend_from_slice(&main);
+        image.resize(0x200, 0);
+        image.extend_from_slice(&(BASE_ADDRESS + 0x180).to_le_bytes());
+        image.extend_from_slice(&(BASE_ADDRESS + 0x190).to_le_bytes());
+        image
+    }
+
+    #[test]
+    fn parses_segments_entry_point_and_initializers() {
+        let image = mach_o_image(None);
+        let memory = DumpMemory::from_regions(&[(BASE_ADDRESS, &image)]);
+        let mach_header = MachHeader::read(&memory, BASE_ADDRESS).unwrap();
+        assert_eq!(mach_header.minidump_cpu(), Some(Cpu::Arm64));
+        assert_eq!(mach_header.segments.len(), 1);
+        assert_eq!(mach_header.segments[0].name, "__TEXT");
+        assert_eq!(mach_header.slide(), 0);
+        assert_eq!(mach_header.entry_point(), Some(BASE_ADDRESS + 0x180));
+        assert_eq!(
+            mach_header.initializers(&memory),
+            vec![BASE_ADDRESS + 0x180, BASE_ADDRESS + 0x190]
+        );
+    }
+
+    #[test]
+    fn caps_the_size_of_the_load_commands() {
+        // The captured memory stops long before the claimed size of the load commands,
+        // so only the header is parsed.
+        let image = mach_o_image(Some(u32::MAX));
+        let memory = DumpMemory::from_regions(&[(BASE_ADDRESS, &image)]);
+        let mach_header = MachHeader::read(&memory, BASE_ADDRESS).unwrap();
+        assert_eq!(mach_header.cpu_type, CPU_TYPE_ARM64);
+        assert!(mach_header.segments.is_empty());
+        assert_eq!(mach_header.entry_point(), None);
+    }
+
+    #[test]
+    fn rejects_images_without_a_mach_o_magic() {
+        let memory = DumpMemory::from_regions(&[(
+            BASE_ADDRESS,
+            b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        )]);
+        assert!(MachHeader::read(&memory, BASE_ADDRESS).is_none());
+    }
+}
@@ -0,0 +1,225 @@
+use std::ops::{Deref, Range};
+
+use minidump::{Minidump, MinidumpMemory64List, MinidumpMemoryList};
+
+#[derive(Debug)]
+struct MemoryRegion<'a> {
+    base_address: u64,
+    bytes: &'a [u8],
+}
+
+impl MemoryRegion<'_> {
+    fn end_address(&self) -> u64 {
+        self.base_address.saturating_add(self.bytes.len() as u64)
+    }
+}
+
+/// The process memory captured in a minidump, addressable by virtual address.
+///
+/// This reads directly from the memory regions in the `MinidumpMemory64List` or `MinidumpMemoryList`
+/// stream, so that structures in the dumped process (e.g. the headers of loaded modules) can be parsed
+/// while the binary view is still being initialized, before its segments are usable.
+/// Reads can span multiple regions, as long as the regions are contiguous.
+#[derive(Debug)]
+pub struct DumpMemory<'a> {
+    regions: Vec<MemoryRegion<'a>>,
+}
+
+impl<'a> DumpMemory<'a> {
+    pub fn from_minidump<T>(minidump_obj: &'a Minidump<'a, T>) -> Self
+    where
+        T: Deref<Target = [u8]> + 'a,
+    {
+        let mut regions = Vec::new();
+        if let Ok(memory_list) = minidump_obj.get_stream::<MinidumpMemory64List>() {
+            for memory in memory_list.iter() {
+                regions.push(MemoryRegion {
+                    base_address: memory.base_address,
+                    bytes: memory.bytes,
+                });
+            }
+        } else if let Ok(memory_list) = minidump_obj.get_stream::<MinidumpMemoryList>() {
+            for memory in memory_list.iter() {
+                regions.push(MemoryRegion {
+                    base_address: memory.base_address,
+                    bytes: memory.bytes,
+                });
+            }
+        }
+        regions.sort_by_key(|region| region.base_address);
+        DumpMemory { regions }
+    }
+
+    /// Build the memory of a dump from `(base_address, bytes)` pairs.
+    #[cfg(test)]
+    pub(crate) fn from_regions(regions: &[(u64, &'a [u8])]) -> Self {
+        let mut regions: Vec<MemoryRegion<'a>> = regions
+            .iter()
+            .map(|&(base_address, bytes)| MemoryRegion {
+                base_address,
+                bytes,
+            })
+            .collect();
+        regions.sort_by_key(|region| region.base_address);
+        DumpMemory { regions }
+    }
+
+    /// The number of memory regions captured in the minidump.
+    pub fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// The total number of bytes of memory captured in the minidump.
+    pub fn total_size(&self) -> u64 {
+        self.regions
+            .iter()
+            .map(|region| region.bytes.len() as u64)
+            .sum()
+    }
+
+    fn region_containing(&self, address: u64) -> Option<&MemoryRegion<'a>> {
+        let index = self
+            .regions
+            .partition_point(|region| region.base_address <= address)
+            .checked_sub(1)?;
+        let region = &self.regions[index];
+        if address < region.end_address() {
+            Some(region)
+        } else {
+            None
+        }
+    }
+
+    /// Check whether the byte at a virtual address was captured in the minidump.
+    pub fn contains(&self, address: u64) -> bool {
+        self.region_containing(address).is_some()
+    }
+
+    /// Find the first address in a range of virtual addresses which was captured in the minidump,
+    /// and the total number of bytes in the range which were captured.
+    pub fn captured_in(&self, range: &Range<u64>) -> Option<(u64, u64)> {
+        let first_index = self
+            .regions
+            .partition_point(|region| region.end_address() <= range.start);
+        let mut first_address = None;
+        let mut captured_len = 0;
+        for region in self.regions[first_index..]
+            .iter()
+            .take_while(|region| region.base_address < range.end)
+        {
+            let start = region.base_address.max(range.start);
+            let end = region.end_address().min(range.end);
+            first_address.get_or_insert(start);
+            captured_len += end - start;
+        }
+        first_address.map(|first_address| (first_address, captured_len))
+    }
+
+    /// Read `len` bytes starting at a virtual address.
+    /// Returns `None` if any part of the requested range is missing from the minidump.
+    pub fn read(&self, address: u64, len: usize) -> Option<Vec<u8>> {
+        // The length often comes from the dumped process's memory, so the buffer only grows
+        // by what has actually been read, rather than being allocated up front.
+        let mut data = Vec::new();
+        let mut current_address = address;
+        while data.len() < len {
+            let region = self.region_containing(current_address)?;
+            let region_offset = (current_address - region.base_address) as usize;
+            let available = &region.bytes[region_offset..];
+            let needed = (len - data.len()).min(available.len());
+            data.extend_from_slice(&available[..needed]);
+            current_address = current_address.checked_add(needed as u64)?;
+        }
+        Some(data)
+    }
+
+    pub fn read_u32(&self, address: u64) -> Option<u32> {
+        let bytes = self.read(address, 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    pub fn read_u64(&self, address: u64) -> Option<u64> {
+        let bytes = self.read(address, 8)?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    /// Read a pointer-sized little-endian value.
+    pub fn read_pointer(&self, address: u64, is_64bit: bool) -> Option<u64> {
+        if is_64bit {
+            self.read_u64(address)
+        } else {
+            self.read_u32(address).map(u64::from)
+        }
+    }
+
+    /// Read a NUL-terminated string of at most `max_len` bytes.
+    pub fn read_c_string(&self, address: u64, max_len: usize) -> Option<String> {
+        let mut string_bytes = Vec::new();
+        let mut current_address = address;
+        while string_bytes.len() < max_len {
+            let region = self.region_containing(current_address)?;
+            let region_offset = (current_address - region.base_address) as usize;
+            let available = &region.bytes[region_offset..];
+            let available = &available[..available.len().min(max_len - string_bytes.len())];
+            if let Some(nul_index) = available.iter().position(|byte| *byte == 0) {
+                string_bytes.extend_from_slice(&available[..nul_index]);
+                return Some(String::from_utf8_lossy(&string_bytes).into_owned());
+            }
+            string_bytes.extend_from_slice(available);
+            current_address = current_address.checked_add(available.len() as u64)?;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_across_contiguous_regions() {
+        let memory = DumpMemory::from_regions(&[(0x1004, &[5, 6, 7, 8]), (0x1000, &[1, 2, 3, 4])]);
+        assert_eq!(memory.read(0x1002, 4), Some(vec![3, 4, 5, 6]));
+        assert_eq!(memory.read_u32(0x1000), Some(0x0403_0201));
+        assert_eq!(memory.read_pointer(0x1004, false), Some(0x0807_0605));
+        assert_eq!(memory.read_u64(0x1000), Some(0x0807_0605_0403_0201));
+    }
+
+    #[test]
+    fn fails_reads_past_the_captured_memory() {
+        let memory = DumpMemory::from_regions(&[(0x1000, &[1, 2, 3, 4]), (0x2000, &[5, 6, 7, 8])]);
+        assert_eq!(memory.read(0x1002, 4), None);
+        assert_eq!(memory.read(0x0fff, 1), None);
+        // A huge length fails once the captured memory runs out, without allocating the length.
+        assert_eq!(memory.read(0x1000, usize::MAX), None);
+    }
+
+    #[test]
+    fn handles_regions_at_the_top_of_the_address_space() {
+        // The end of the region saturates, rather than overflowing.
+        let memory = DumpMemory::from_regions(&[(u64::MAX - 3, &[1, 2, 3, 4])]);
+        assert!(memory.contains(u64::MAX - 1));
+        assert_eq!(memory.read(u64::MAX - 3, 2), Some(vec![1, 2]));
+        assert_eq!(memory.read(u64::MAX - 3, 8), None);
+        assert_eq!(memory.read_c_string(u64::MAX - 3, 16), None);
+    }
+
+    #[test]
+    fn finds_captured_parts_of_a_range() {
+        let memory = DumpMemory::from_regions(&[(0x1000, &[0; 0x10]), (0x1020, &[0; 0x10])]);
+        assert_eq!(memory.captured_in(&(0x1008..0x1028)), Some((0x1008, 0x10)));
+        assert_eq!(memory.captured_in(&(0x1010..0x1020)), None);
+        assert_eq!(memory.total_size(), 0x20);
+        assert_eq!(memory.region_count(), 2);
+    }
+
+    #[test]
+    fn reads_c_strings() {
+        let memory = DumpMemory::from_regions(&[(0x1000, b"lib"), (0x1003, b"c.so\0junk")]);
+        assert_eq!(
+            memory.read_c_string(0x1000, 32),
+            Some("libc.so".to_string())
+        );
+        assert_eq!(memory.read_c_string(0x1000, 4), None);
+    }
+}
@@ -0,0 +1,834 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use log::{debug, warn};
+use pdb::{
+    ClassKind, FallibleIterator, Indirection, ItemFinder, PrimitiveKind, SymbolData, TypeData,
+    TypeIndex, TypeInformation, Variant, PDB,
+};
+
+/// An upper bound on how deeply types are followed when resolving the type of a function,
+/// in case a damaged PDB has a cycle of types.
+const MAX_TYPE_DEPTH: usize = 16;
+
+/// An upper bound on how many field lists are followed for the members of a single type,
+/// in case a damaged PDB has a cycle of field list continuations.
+const MAX_FIELD_LISTS: usize = 1024;
+
+/// Find the PDB file for a module in a list of local search paths.
+///
+/// Each search path can either be a plain directory containing PDB files, or a local symbol store
+/// (e.g. a symbol cache populated by WinDbg or `symchk`) laid out as `<name>.pdb/<GUID><age>/<name>.pdb`.
+/// `debug_id` is the module's debug identifier in Breakpad format, i.e. the GUID of the PDB
+/// followed by its age, in uppercase hexadecimal. Only a PDB whose GUID and age match is returned.
+pub fn find_pdb(search_paths: &[PathBuf], pdb_name: &str, debug_id: &str) -> Option<PathBuf> {
+    for search_path in search_paths {
+        let candidates = [
+            search_path.join(pdb_name).join(debug_id).join(pdb_name),
+            search_path.join(pdb_name),
+        ];
+        for candidate in candidates.iter() {
+            if !candidate.is_file() {
+                continue;
+            }
+            match read_pdb_debug_id(candidate) {
+                Ok(candidate_debug_id) if candidate_debug_id.eq_ignore_ascii_case(debug_id) => {
+                    return Some(candidate.clone());
+                }
+                Ok(candidate_debug_id) => debug!(
+                    "Skipping PDB {} with debug identifier {}, which does not match {}",
+                    candidate.display(),
+                    candidate_debug_id,
+                    debug_id,
+                ),
+                Err(error) => warn!("Could not read PDB {}: {}", candidate.display(), error),
+            }
+        }
+    }
+    None
+}
+
+/// Read the GUID and age of a PDB, formatted the same way as a Breakpad debug identifier.
+fn read_pdb_debug_id(path: &Path) -> pdb::Result<String> {
+    let mut pdb = PDB::open(File::open(path)?)?;
+    let pdb_information = pdb.pdb_information()?;
+    // The age in the DBI stream is the one which is updated on every link,
+    // and is the one which is written into the image's CodeView record.
+    let age = pdb
+        .debug_information()?
+        .age()
+        .unwrap_or(pdb_information.age);
+    Ok(format!(
+        "{}{:X}",
+        pdb_information
+            .guid
+            .simple()
+            .to_string()
+            .to_ascii_uppercase(),
+        age
+    ))
+}
+
+/// A type from a PDB, reduced to what is needed to describe the signature of a function
+/// or the members of a type.
+///
+/// Classes, structs, unions, and enums are only referred to by name. Their members are
+/// read separately, as [`PdbTypeDefinition`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PdbType {
+    Void,
+    Bool(usize),
+    Char,
+    WideChar(usize),
+    Int {
+        width: usize,
+        signed: bool,
+    },
+    Float(usize),
+    Pointer {
+        target: Box<PdbType>,
+        size: usize,
+    },
+    Array {
+        element: Box<PdbType>,
+        count: u64,
+    },
+    /// A class, struct, or union.
+    Struct {
+        name: String,
+    },
+    Enum {
+        name: String,
+        underlying: Box<PdbType>,
+    },
+    /// A pointer to a function.
+    Function(Box<PdbFunctionType>),
+    /// A type which can't be represented, such as a pointer to member,
+    /// or a type missing from the PDB.
+    Unknown,
+}
+
+/// The signature of a function in a PDB. The `this` pointer of member functions is
+/// the first of their parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdbFunctionType {
+    pub return_type: PdbType,
+    pub parameters: Vec<PdbType>,
+    pub variadic: bool,
+}
+
+/// A function in a PDB.
+#[derive(Debug, Clone)]
+pub struct PdbFunction {
+    pub name: String,
+    /// The function's signature, which is only known for functions with a procedure symbol.
+    pub function_type: Option<PdbFunctionType>,
+}
+
+/// Whether a [`PdbTypeDefinition::Struct`] is a class, struct, or union.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdbStructKind {
+    Class,
+    Struct,
+    Union,
+}
+
+/// A data member of a class, struct, or union in a PDB. Base classes are members named after
+/// the base class, and bitfields are members of their underlying type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdbMember {
+    pub name: String,
+    pub member_type: PdbType,
+    pub offset: u64,
+}
+
+/// The definition of a named class, struct, union, or enum in a PDB.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PdbTypeDefinition {
+    Struct {
+        name: String,
+        kind: PdbStructKind,
+        size: u64,
+        members: Vec<PdbMember>,
+    },
+    Enum {
+        name: String,
+        underlying: PdbType,
+        /// The name and value of each enumerator, with signed values sign extended.
+        enumerators: Vec<(String, u64)>,
+    },
+}
+
+impl PdbTypeDefinition {
+    pub fn name(&self) -> &str {
+        match self {
+            PdbTypeDefinition::Struct { name, .. } | PdbTypeDefinition::Enum { name, .. } => name,
+        }
+    }
+}
+
+/// The type of a primitive type in a PDB (a type with an index below 0x1000), which is a pointer
+/// to the primitive if it has an indirection.
+fn primitive_type(kind: PrimitiveKind, indirection: Option<Indirection>) -> PdbType {
+    let primitive = match kind {
+        PrimitiveKind::Void => PdbType::Void,
+        PrimitiveKind::Char | PrimitiveKind::RChar => PdbType::Char,
+        PrimitiveKind::WChar | PrimitiveKind::RChar16 => PdbType::WideChar(2),
+        PrimitiveKind::RChar32 => PdbType::WideChar(4),
+        PrimitiveKind::UChar | PrimitiveKind::U8 => PdbType::Int {
+            width: 1,
+            signed: false,
+        },
+        PrimitiveKind::I8 => PdbType::Int {
+            width: 1,
+            signed: true,
+        },
+        PrimitiveKind::Short | PrimitiveKind::I16 => PdbType::Int {
+            width: 2,
+            signed: true,
+        },
+        PrimitiveKind::UShort | PrimitiveKind::U16 => PdbType::Int {
+            width: 2,
+            signed: false,
+        },
+        PrimitiveKind::Long | PrimitiveKind::I32 | PrimitiveKind::HRESULT => PdbType::Int {
+            width: 4,
+            signed: true,
+        },
+        PrimitiveKind::ULong | PrimitiveKind::U32 => PdbType::Int {
+            width: 4,
+            signed: false,
+        },
+        PrimitiveKind::Quad | PrimitiveKind::I64 => PdbType::Int {
+            width: 8,
+            signed: true,
+        },
+        PrimitiveKind::UQuad | PrimitiveKind::U64 => PdbType::Int {
+            width: 8,
+            signed: false,
+        },
+        PrimitiveKind::Octa | PrimitiveKind::I128 => PdbType::Int {
+            width: 16,
+            signed: true,
+        },
+        PrimitiveKind::UOcta | PrimitiveKind::U128 => PdbType::Int {
+            width: 16,
+            signed: false,
+        },
+        PrimitiveKind::F16 => PdbType::Float(2),
+        PrimitiveKind::F32 | PrimitiveKind::F32PP => PdbType::Float(4),
+        PrimitiveKind::F48 => PdbType::Float(6),
+        PrimitiveKind::F64 => PdbType::Float(8),
+        PrimitiveKind::F80 => PdbType::Float(10),
+        PrimitiveKind::F128 => PdbType::Float(16),
+        PrimitiveKind::Bool8 => PdbType::Bool(1),
+        PrimitiveKind::Bool16 => PdbType::Bool(2),
+        PrimitiveKind::Bool32 => PdbType::Bool(4),
+        PrimitiveKind::Bool64 => PdbType::Bool(8),
+        _ => PdbType::Unknown,
+    };
+    let pointer_size = match indirection {
+        None => return primitive,
+        Some(Indirection::Near16 | Indirection::Far16 | Indirection::Huge16) => 2,
+        Some(Indirection::Near32 | Indirection::Far32) => 4,
+        Some(Indirection::Near64) => 8,
+        Some(Indirection::Near128) => 16,
+    };
+    PdbType::Pointer {
+        target: Box::new(primitive),
+        size: pointer_size,
+    }
+}
+
+/// Resolves type indices in a PDB's type information into [`PdbType`]s.
+struct TypeResolver<'a, 't> {
+    type_finder: &'a ItemFinder<'t, TypeIndex>,
+}
+
+impl<'t> TypeResolver<'_, 't> {
+    fn parse(&self, type_index: TypeIndex) -> Option<TypeData<'t>> {
+        self.type_finder
+            .find(type_index)
+            .and_then(|type_item| type_item.parse())
+            .ok()
+    }
+
+    fn resolve(&self, type_index: TypeIndex, depth: usize) -> PdbType {
+        if depth >= MAX_TYPE_DEPTH {
+            return PdbType::Unknown;
+        }
+        let Some(type_data) = self.parse(type_index) else {
+            return PdbType::Unknown;
+        };
+        match type_data {
+            TypeData::Primitive(primitive) => primitive_type(primitive.kind, primitive.indirection),
+            TypeData::Modifier(modifier) => self.resolve(modifier.underlying_type, depth + 1),
+            TypeData::Bitfield(bitfield) => self.resolve(bitfield.underlying_type, depth + 1),
+            TypeData::Pointer(pointer) if !pointer.attributes.pointer_to_member() => {
+                PdbType::Pointer {
+                    target: Box::new(self.resolve(pointer.underlying_type, depth + 1)),
+                    size: pointer.attributes.size() as usize,
+                }
+            }
+            TypeData::Array(array) => {
+                let element = self.resolve(array.element_type, depth + 1);
+                let array_size = array.dimensions.last().copied().unwrap_or(0) as u64;
+                match array.stride.map(u64::from).or(pdb_type_size(&element)) {
+                    Some(element_size) if element_size != 0 => PdbType::Array {
+                        element: Box::new(element),
+                        count: array_size / element_size,
+                    },
+                    _ => PdbType::Unknown,
+                }
+            }
+            TypeData::Class(class) => PdbType::Struct {
+                name: class.name.to_string().into_owned(),
+            },
+            TypeData::Union(union) => PdbType::Struct {
+                name: union.name.to_string().into_owned(),
+            },
+            TypeData::Enumeration(enumeration) => PdbType::Enum {
+                name: enumeration.name.to_string().into_owned(),
+                underlying: Box::new(self.resolve(enumeration.underlying_type, depth + 1)),
+            },
+            TypeData::Procedure(_) | TypeData::MemberFunction(_) => {
+                match self.resolve_function(type_index, depth + 1) {
+                    Some(function_type) => PdbType::Function(Box::new(function_type)),
+                    None => PdbType::Unknown,
+                }
+            }
+            _ => PdbType::Unknown,
+        }
+    }
+
+    /// Resolve the signature of a procedure or member function type.
+    fn resolve_function(&self, type_index: TypeIndex, depth: usize) -> Option<PdbFunctionType> {
+        let (return_type, this_type, argument_list) = match self.parse(type_index)? {
+            TypeData::Procedure(procedure) => {
+                (procedure.return_type, None, procedure.argument_list)
+            }
+            TypeData::MemberFunction(member_function) => (
+                Some(member_function.return_type),
+                member_function.this_pointer_type,
+                member_function.argument_list,
+            ),
+            _ => return None,
+        };
+        let TypeData::ArgumentList(argument_list) = self.parse(argument_list)? else {
+            return None;
+        };
+
+        let mut parameters: Vec<PdbType> = this_type
+            .map(|this_type| self.resolve(this_type, depth + 1))
+            .into_iter()
+            .collect();
+        let mut variadic = false;
+        for argument in argument_list.arguments {
+            // A trailing argument with no type (`T_NOTYPE`) marks a variadic function
+            if argument == TypeIndex(0) {
+                variadic = true;
+                break;
+            }
+            parameters.push(self.resolve(argument, depth + 1));
+        }
+        // A function without parameters has a single `void` argument
+        if parameters == [PdbType::Void] {
+            parameters.clear();
+        }
+        Some(PdbFunctionType {
+            return_type: return_type.map_or(PdbType::Void, |return_type| {
+                self.resolve(return_type, depth + 1)
+            }),
+            parameters,
+            variadic,
+        })
+    }
+
+    /// The members and enumerators in a type's field list and its continuations.
+    fn fields(&self, field_list: Option<TypeIndex>) -> Vec<TypeData<'t>> {
+        let mut fields = Vec::new();
+        let mut next_field_list = field_list;
+        for _ in 0..MAX_FIELD_LISTS {
+            let Some(TypeData::FieldList(field_list)) =
+                next_field_list.and_then(|index| self.parse(index))
+            else {
+                break;
+            };
+            fields.extend(field_list.fields);
+            next_field_list = field_list.continuation;
+        }
+        fields
+    }
+
+    /// The definition of a class, struct, union, or enum, unless it is a forward reference
+    /// or has no name.
+    fn resolve_definition(&self, type_data: &TypeData) -> Option<PdbTypeDefinition> {
+        let (name, kind, size, field_list) = match type_data {
+            TypeData::Class(class) if !class.properties.forward_reference() => {
+                let kind = match class.kind {
+                    ClassKind::Class => PdbStructKind::Class,
+                    ClassKind::Struct | ClassKind::Interface => PdbStructKind::Struct,
+                };
+                (class.name, kind, class.size, class.fields)
+            }
+            TypeData::Union(union) if !union.properties.forward_reference() => (
+                union.name,
+                PdbStructKind::Union,
+                union.size,
+                Some(union.fields),
+            ),
+            TypeData::Enumeration(enumeration)
+                if !enumeration.properties.forward_reference()
+                    && !is_anonymous(&enumeration.name.to_string()) =>
+            {
+                let enumerators = self
+                    .fields(Some(enumeration.fields))
+                    .into_iter()
+                    .filter_map(|field| match field {
+                        TypeData::Enumerate(enumerate) => Some((
+                            enumerate.name.to_string().into_owned(),
+                            variant_value(enumerate.value),
+                        )),
+                        _ => None,
+                    })
+                    .collect();
+                return Some(PdbTypeDefinition::Enum {
+                    name: enumeration.name.to_string().into_owned(),
+                    underlying: self.resolve(enumeration.underlying_type, 0),
+                    enumerators,
+                });
+            }
+            _ => return None,
+        };
+        let name = name.to_string().into_owned();
+        if is_anonymous(&name) {
+            return None;
+        }
+
+        let mut members = Vec::new();
+        let mut bitfield_offset = None;
+        for field in self.fields(field_list) {
+            let (member_name, member_type, offset) = match field {
+                TypeData::Member(member) => (
+                    member.name.to_string().into_owned(),
+                    member.field_type,
+                    member.offset,
+                ),
+                TypeData::BaseClass(base_class) => {
+                    let Some(TypeData::Class(class)) = self.parse(base_class.base_class) else {
+                        continue;
+                    };
+                    (
+                        class.name.to_string().into_owned(),
+                        base_class.base_class,
+                        base_class.offset as u64,
+                    )
+                }
+                _ => continue,
+            };
+            // Consecutive bitfields share the offset of their storage unit, which is
+            // only added once
+            let is_bitfield = matches!(self.parse(member_type), Some(TypeData::Bitfield(_)));
+            if is_bitfield && bitfield_offset == Some(offset) {
+                continue;
+            }
+            bitfield_offset = is_bitfield.then_some(offset);
+            members.push(PdbMember {
+                name: member_name,
+                member_type: self.resolve(member_type, 0),
+                offset,
+            });
+        }
+        Some(PdbTypeDefinition::Struct {
+            name,
+            kind,
+            size,
+            members,
+        })
+    }
+}
+
+/// Whether a type name is one the compiler made up for an anonymous type,
+/// such as `<unnamed-tag>` or `<anonymous-tag>`.
+fn is_anonymous(name: &str) -> bool {
+    name.starts_with('<') || name.starts_with("__unnamed")
+}
+
+/// The value of an enumerator, with signed values sign extended.
+fn variant_value(value: Variant) -> u64 {
+    match value {
+        Variant::U8(value) => value.into(),
+        Variant::U16(value) => value.into(),
+        Variant::U32(value) => value.into(),
+        Variant::U64(value) => value,
+        Variant::I8(value) => value as u64,
+        Variant::I16(value) => value as u64,
+        Variant::I32(value) => value as u64,
+        Variant::I64(value) => value as u64,
+    }
+}
+
+/// The size of a type in bytes, if it is known without looking up other types.
+fn pdb_type_size(pdb_type: &PdbType) -> Option<u64> {
+    match pdb_type {
+        PdbType::Bool(width) | PdbType::WideChar(width) | PdbType::Float(width) => {
+            Some(*width as u64)
+        }
+        PdbType::Int { width, .. } => Some(*width as u64),
+        PdbType::Char => Some(1),
+        PdbType::Pointer { size, .. } => Some(*size as u64),
+        PdbType::Array { element, count } => Some(pdb_type_size(element)? * count),
+        PdbType::Enum { underlying, .. } => pdb_type_size(underlying),
+        _ => None,
+    }
+}
+
+/// Read the names, RVAs, and signatures of the functions in a PDB, from its public symbols and
+/// from the procedure symbols in each of its modules.
+///
+/// Where a public symbol and a procedure symbol are at the same RVA, the procedure symbol is
+/// used, since its name is not decorated and its type gives the function's signature.
+pub fn read_pdb_functions(path: &Path) -> pdb::Result<BTreeMap<u32, PdbFunction>> {
+    let mut pdb = PDB::open(File::open(path)?)?;
+    let address_map = pdb.address_map()?;
+    let mut functions = BTreeMap::new();
+
+    let type_information = pdb.type_information()?;
+    let type_finder = type_finder(&type_information)?;
+    let type_resolver = TypeResolver {
+        type_finder: &type_finder,
+    };
+
+    let global_symbols = pdb.global_symbols()?;
+    let mut symbols = global_symbols.iter();
+    while let Some(symbol) = symbols.next()? {
+        if let Ok(SymbolData::Public(public_symbol)) = symbol.parse() {
+            if !public_symbol.function {
+                continue;
+            }
+            if let Some(rva) = public_symbol.offset.to_rva(&address_map) {
+                functions.insert(
+                    rva.0,
+                    PdbFunction {
+                        name: public_symbol.name.to_string().into_owned(),
+                        function_type: None,
+                    },
+                );
+            }
+        }
+    }
+
+    let debug_information = pdb.debug_information()?;
+    let mut modules = debug_information.modules()?;
+    while let Some(module) = modules.next()? {
+        let Some(module_info) = pdb.module_info(&module)? else {
+            continue;
+        };
+        let mut symbols = module_info.symbols()?;
+        while let Some(symbol) = symbols.next()? {
+            if let Ok(SymbolData::Procedure(procedure_symbol)) = symbol.parse() {
+                if let Some(rva) = procedure_symbol.offset.to_rva(&address_map) {
+                    functions.insert(
+                        rva.0,
+                        PdbFunction {
+                            name: procedure_symbol.name.to_string().into_owned(),
+                            function_type: type_resolver
+                                .resolve_function(procedure_symbol.type_index, 0),
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(functions)
+}
+
+/// Read the definitions of the named classes, structs, unions, and enums in a PDB, by name.
+///
+/// Where several types have the same name, the first definition is used.
+pub fn read_pdb_types(path: &Path) -> pdb::Result<BTreeMap<String, PdbTypeDefinition>> {
+    let mut pdb = PDB::open(File::open(path)?)?;
+    let type_information = pdb.type_information()?;
+    let type_finder = type_finder(&type_information)?;
+    let type_resolver = TypeResolver {
+        type_finder: &type_finder,
+    };
+
+    let mut definitions = BTreeMap::new();
+    let mut types = type_information.iter();
+    while let Some(type_item) = types.next()? {
+        let Ok(type_data) = type_item.parse() else {
+            continue;
+        };
+        if let Some(definition) = type_resolver.resolve_definition(&type_data) {
+            definitions
+                .entry(definition.name().to_string())
+                .or_insert(definition);
+        }
+    }
+    Ok(definitions)
+}
+
+/// Index every type in a PDB's type information, so that types can be looked up by index.
+fn type_finder<'t>(
+    type_information: &'t TypeInformation,
+) -> pdb::Result<ItemFinder<'t, TypeIndex>> {
+    let mut type_finder = type_information.finder();
+    let mut types = type_information.iter();
+    while types.next()?.is_some() {
+        type_finder.update(&types);
+    }
+    Ok(type_finder)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// The debug identifier of `testdata/test.pdb`, which is generated from `testdata/test.pdb.yaml`
+    /// by `testdata/make_test_pdb.py`. Its age is the DBI stream's age of 3 rather than the
+    /// PDB information stream's age of 2.
+    const DEBUG_ID: &str = "3F2E5D1A9B7C4E0F8A6B5C4D3E2F1A0B3";
+
+    const INT: PdbType = PdbType::Int {
+        width: 4,
+        signed: true,
+    };
+
+    fn test_pdb() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/test.pdb")
+    }
+
+    /// Copy the test PDB to a path in a new temporary directory.
+    fn pdb_directory(name: &str, pdb_path: &Path) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "minidump_layout_pdb_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let pdb_path = directory.join(pdb_path);
+        fs::create_dir_all(pdb_path.parent().unwrap()).unwrap();
+        fs::copy(test_pdb(), pdb_path).unwrap();
+        directory
+    }
+
+    #[test]
+    fn reads_debug_id() {
+        assert_eq!(read_pdb_debug_id(&test_pdb()).unwrap(), DEBUG_ID);
+    }
+
+    #[test]
+    fn finds_pdbs_in_symbol_stores() {
+        let symbol_store_path = Path::new("test.pdb").join(DEBUG_ID).join("test.pdb");
+        let directory = pdb_directory("symbol_store", &symbol_store_path);
+        let search_paths = [directory.join("missing"), directory.clone()];
+        assert_eq!(
+            find_pdb(&search_paths, "test.pdb", DEBUG_ID),
+            Some(directory.join(&symbol_store_path))
+        );
+        assert_eq!(find_pdb(&search_paths, "other.pdb", DEBUG_ID), None);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn finds_pdbs_in_plain_directories() {
+        let directory = pdb_directory("plain", Path::new("test.pdb"));
+        let search_paths = [directory.clone()];
+        assert_eq!(
+            find_pdb(&search_paths, "test.pdb", DEBUG_ID),
+            Some(directory.join("test.pdb"))
+        );
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn rejects_pdbs_with_another_guid_or_age() {
+        // The age from the PDB information stream rather than the DBI stream
+        let other_age = "3F2E5D1A9B7C4E0F8A6B5C4D3E2F1A0B2";
+        let other_guid = "0F2E5D1A9B7C4E0F8A6B5C4D3E2F1A0B3";
+        let plain_directory = pdb_directory("mismatch_plain", Path::new("test.pdb"));
+        // A symbol store directory named after the other age, holding the test PDB
+        let symbol_store = pdb_directory(
+            "mismatch_symbol_store",
+            &Path::new("test.pdb").join(other_age).join("test.pdb"),
+        );
+        let search_paths = [symbol_store.clone(), plain_directory.clone()];
+        assert_eq!(find_pdb(&search_paths, "test.pdb", other_age), None);
+        assert_eq!(find_pdb(&search_paths, "test.pdb", other_guid), None);
+        fs::remove_dir_all(plain_directory).unwrap();
+        fs::remove_dir_all(symbol_store).unwrap();
+    }
+
+    #[test]
+    fn reads_functions() {
+        let functions = read_pdb_functions(&test_pdb()).unwrap();
+        let functions: Vec<_> = functions
+            .into_iter()
+            .map(|(rva, function)| (rva, function.name, function.function_type))
+            .collect();
+        // The procedure symbol replaces the public symbol at the same RVA, and the public
+        // symbol for data is skipped
+        assert_eq!(
+            functions,
+            [
+                (
+                    0x1010,
+                    "move_point".to_string(),
+                    Some(PdbFunctionType {
+                        return_type: INT,
+                        parameters: vec![
+                            PdbType::Pointer {
+                                target: Box::new(PdbType::Struct {
+                                    name: "POINT".to_string()
+                                }),
+                                size: 8
+                            },
+                            INT
+                        ],
+                        variadic: false,
+                    })
+                ),
+                (0x1040, "exported_function".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_type_definitions() {
+        let types = read_pdb_types(&test_pdb()).unwrap();
+        let member = |name: &str, member_type: PdbType, offset| PdbMember {
+            name: name.to_string(),
+            member_type,
+            offset,
+        };
+        assert_eq!(
+            types.keys().collect::<Vec<_>>(),
+            ["COLOR", "POINT", "SHAPE", "VALUE"]
+        );
+        assert_eq!(
+            types["POINT"],
+            PdbTypeDefinition::Struct {
+                name: "POINT".to_string(),
+                kind: PdbStructKind::Struct,
+                size: 8,
+                members: vec![member("x", INT, 0), member("y", INT, 4)],
+            }
+        );
+        assert_eq!(
+            types["COLOR"],
+            PdbTypeDefinition::Enum {
+                name: "COLOR".to_string(),
+                underlying: INT,
+                enumerators: vec![
+                    ("RED".to_string(), 0),
+                    ("GREEN".to_string(), 1),
+                    ("BLUE".to_string(), 4)
+                ],
+            }
+        );
+        assert_eq!(
+            types["VALUE"],
+            PdbTypeDefinition::Struct {
+                name: "VALUE".to_string(),
+                kind: PdbStructKind::Union,
+                size: 4,
+                members: vec![member("i", INT, 0), member("f", PdbType::Float(4), 0)],
+            }
+        );
+        assert_eq!(
+            types["SHAPE"],
+            PdbTypeDefinition::Struct {
+                name: "SHAPE".to_string(),
+                kind: PdbStructKind::Struct,
+                size: 16,
+                members: vec![
+                    member(
+                        "origin",
+                        PdbType::Struct {
+                            name: "POINT".to_string()
+                        },
+                        0
+                    ),
+                    member(
+                        "color",
+                        PdbType::Enum {
+                            name: "COLOR".to_string(),
+                            underlying: Box::new(INT)
+                        },
+                        8
+                    ),
+                    member(
+                        "value",
+                        PdbType::Struct {
+                            name: "VALUE".to_string()
+                        },
+                        12
+                    ),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn converts_primitive_types() {
+        assert_eq!(primitive_type(PrimitiveKind::Void, None), PdbType::Void);
+        assert_eq!(
+            primitive_type(PrimitiveKind::ULong, None),
+            PdbType::Int {
+                width: 4,
+                signed: false
+            }
+        );
+        assert_eq!(
+            primitive_type(PrimitiveKind::WChar, None),
+            PdbType::WideChar(2)
+        );
+        assert_eq!(primitive_type(PrimitiveKind::Bool8, None), PdbType::Bool(1));
+        assert_eq!(
+            primitive_type(PrimitiveKind::Char, Some(Indirection::Near64)),
+            PdbType::Pointer {
+                target: Box::new(PdbType::Char),
+                size: 8
+            }
+        );
+        assert_eq!(
+            primitive_type(PrimitiveKind::Void, Some(Indirection::Near32)),
+            PdbType::Pointer {
+                target: Box::new(PdbType::Void),
+                size: 4
+            }
+        );
+    }
+
+    #[test]
+    fn sizes_types() {
+        let int = PdbType::Int {
+            width: 4,
+            signed: true,
+        };
+        assert_eq!(
+            pdb_type_size(&PdbType::Array {
+                element: Box::new(int.clone()),
+                count: 3
+            }),
+            Some(12)
+        );
+        assert_eq!(
+            pdb_type_size(&PdbType::Enum {
+                name: "COLOR".to_string(),
+                underlying: Box::new(int)
+            }),
+            Some(4)
+        );
+        assert_eq!(
+            pdb_type_size(&PdbType::Struct {
+                name: "POINT".to_string()
+            }),
+            None
+        );
+    }
+}
@@ -0,0 +1,1094 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use minidump::system_info::Cpu;
+
+use crate::layout::module_file_name;
+use crate::memory::DumpMemory;
+
+pub const IMAGE_FILE_MACHINE_I386: u16 = 0x014c;
+pub const IMAGE_FILE_MACHINE_AMD64: u16 = 0x8664;
+pub const IMAGE_FILE_MACHINE_ARMNT: u16 = 0x01c4;
+pub const IMAGE_FILE_MACHINE_ARM64: u16 = 0xaa64;
+
+const IMAGE_NT_OPTIONAL_HDR32_MAGIC: u16 = 0x10b;
+const IMAGE_NT_OPTIONAL_HDR64_MAGIC: u16 = 0x20b;
+
+pub const IMAGE_DIRECTORY_ENTRY_EXPORT: usize = 0;
+pub const IMAGE_DIRECTORY_ENTRY_IMPORT: usize = 1;
+pub const IMAGE_DIRECTORY_ENTRY_EXCEPTION: usize = 3;
+pub const IMAGE_DIRECTORY_ENTRY_TLS: usize = 9;
+pub const IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT: usize = 13;
+
+pub const IMAGE_SCN_CNT_CODE: u32 = 0x0000_0020;
+pub const IMAGE_SCN_CNT_INITIALIZED_DATA: u32 = 0x0000_0040;
+pub const IMAGE_SCN_CNT_UNINITIALIZED_DATA: u32 = 0x0000_0080;
+pub const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+pub const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
+
+const IMAGE_SIZEOF_SECTION_HEADER: usize = 40;
+const IMAGE_SIZEOF_IMPORT_DESCRIPTOR: u64 = 20;
+const IMAGE_SIZEOF_DELAYLOAD_DESCRIPTOR: u64 = 32;
+const IMAGE_SIZEOF_RUNTIME_FUNCTION_ENTRY: u64 = 12;
+
+/// An upper bound on the number of entries read from tables in the dumped process's memory,
+/// in case the tables are damaged.
+const MAX_TABLE_ENTRIES: usize = 0x1000;
+/// An upper bound on the number of exports read from a module's export directory.
+/// This is much larger than `MAX_TABLE_ENTRIES`, since system DLLs have thousands of exports.
+const MAX_EXPORTS: usize = 0x10000;
+/// An upper bound on the length of the names in export and import tables.
+const MAX_NAME_LENGTH: usize = 0x400;
+/// An upper bound on the length of a chain of forwarded exports, in case the chain has a cycle.
+const MAX_FORWARDER_CHAIN_LENGTH: usize = 8;
+
+/// The headers of a PE image which has been mapped into the memory of the dumped process.
+///
+/// Since the image is mapped, every RVA in the headers is relative to the image's base address
+/// in the minidump's memory, rather than being a file offset.
+#[derive(Debug)]
+pub struct PeHeaders {
+    pub base_address: u64,
+    pub machine: u16,
+    /// Whether the optional header is a PE32+ (64-bit) optional header.
+    pub is_64bit: bool,
+    pub address_of_entry_point: u32,
+    pub size_of_image: u32,
+    /// The `(VirtualAddress, Size)` pairs in the optional header's data directory.
+    pub data_directories: Vec<(u32, u32)>,
+    pub sections: Vec<PeSection>,
+}
+
+/// An entry in the section table of a mapped PE image.
+#[derive(Debug)]
+pub struct PeSection {
+    pub name: String,
+    pub virtual_address: u32,
+    pub virtual_size: u32,
+    pub size_of_raw_data: u32,
+    pub characteristics: u32,
+}
+
+impl PeSection {
+    /// The size of the section once mapped into memory.
+    /// Some linkers leave `VirtualSize` as 0, in which case the size on disk is used instead.
+    pub fn mapped_size(&self) -> u32 {
+        if self.virtual_size != 0 {
+            self.virtual_size
+        } else {
+            self.size_of_raw_data
+        }
+    }
+
+    pub fn is_code(&self) -> bool {
+        self.characteristics & (IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE) != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_WRITE != 0
+    }
+
+    pub fn is_data(&self) -> bool {
+        self.characteristics & (IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_CNT_UNINITIALIZED_DATA)
+            != 0
+    }
+}
+
+impl PeHeaders {
+    pub fn read(memory: &DumpMemory, base_address: u64) -> Option<Self> {
+        let dos_header = memory.read(base_address, 0x40)?;
+        if &dos_header[0..2] != b"MZ" {
+            return None;
+        }
+        let e_lfanew = u32::from_le_bytes(dos_header[0x3c..0x40].try_into().ok()?) as u64;
+
+        // IMAGE_NT_HEADERS: the "PE\0\0" signature, followed by the IMAGE_FILE_HEADER
+        let nt_headers_address = base_address.checked_add(e_lfanew)?;
+        let nt_headers = memory.read(nt_headers_address, 0x18)?;
+        if &nt_headers[0..4] != b"PE\0\0" {
+            return None;
+        }
+        let machine = u16::from_le_bytes(nt_headers[4..6].try_into().ok()?);
+        let number_of_sections = u16::from_le_bytes(nt_headers[6..8].try_into().ok()?);
+        let size_of_optional_header = u16::from_le_bytes(nt_headers[0x14..0x16].try_into().ok()?);
+
+        let optional_header_address = nt_headers_address.checked_add(0x18)?;
+        let optional_header =
+            memory.read(optional_header_address, size_of_optional_header as usize)?;
+        let read_u32 = |offset: usize| -> Option<u32> {
+            Some(u32::from_le_bytes(
+                optional_header.get(offset..offset + 4)?.try_into().ok()?,
+            ))
+        };
+
+        let magic = u16::from_le_bytes(optional_header.get(0..2)?.try_into().ok()?);
+        let (is_64bit, data_directory_offset) = match magic {
+            IMAGE_NT_OPTIONAL_HDR32_MAGIC => (false, 0x60),
+            IMAGE_NT_OPTIONAL_HDR64_MAGIC => (true, 0x70),
+            _ => return None,
+        };
+        let address_of_entry_point = read_u32(0x10)?;
+        let size_of_image = read_u32(0x38)?;
+        let number_of_rva_and_sizes = read_u32(data_directory_offset - 4)?;
+
+        let mut data_directories = Vec::new();
+        for index in 0..number_of_rva_and_sizes.min(16) as usize {
+            let entry_offset = data_directory_offset + index * 8;
+            match (read_u32(entry_offset), read_u32(entry_offset + 4)) {
+                (Some(virtual_address), Some(size)) => {
+                    data_directories.push((virtual_address, size))
+                }
+                _ => break,
+            }
+        }
+
+        // The section table immediately follows the optional header
+        let section_table_address = optional_header_address + size_of_optional_header as u64;
+        let mut sections = Vec::new();
+        if let Some(section_table) = memory.read(
+            section_table_address,
+            number_of_sections as usize * IMAGE_SIZEOF_SECTION_HEADER,
+        ) {
+            for section_header in section_table.chunks_exact(IMAGE_SIZEOF_SECTION_HEADER) {
+                let read_section_u32 = |offset: usize| {
+                    u32::from_le_bytes(section_header[offset..offset + 4].try_into().unwrap())
+                };
+                let name_end = section_header[0..8]
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .unwrap_or(8);
+                sections.push(PeSection {
+                    name: String::from_utf8_lossy(&section_header[0..name_end]).into_owned(),
+                    virtual_size: read_section_u32(8),
+                    virtual_address: read_section_u32(12),
+                    size_of_raw_data: read_section_u32(16),
+                    characteristics: read_section_u32(36),
+                });
+            }
+        }
+
+        Some(PeHeaders {
+            base_address,
+            machine,
+            is_64bit,
+            address_of_entry_point,
+            size_of_image,
+            data_directories,
+            sections,
+        })
+    }
+
+    /// Look up a data directory entry, returning the virtual address and size of the directory
+    /// if it is present in the image.
+    pub fn data_directory(&self, index: usize) -> Option<(u64, u32)> {
+        match self.data_directories.get(index) {
+            Some(&(virtual_address, size)) if virtual_address != 0 && size != 0 => {
+                Some((self.base_address + virtual_address as u64, size))
+            }
+            _ => None,
+        }
+    }
+
+    /// The virtual address of the image's entry point (e.g. `DllMain` for a DLL), if it has one.
+    pub fn entry_point(&self) -> Option<u64> {
+        if self.address_of_entry_point != 0 && self.address_of_entry_point < self.size_of_image {
+            Some(self.base_address + self.address_of_entry_point as u64)
+        } else {
+            None
+        }
+    }
+
+    /// The virtual addresses of the image's TLS callbacks, which run before the entry point.
+    pub fn tls_callbacks(&self, memory: &DumpMemory) -> Vec<u64> {
+        let mut callbacks = Vec::new();
+        let Some((tls_directory_address, _)) = self.data_directory(IMAGE_DIRECTORY_ENTRY_TLS)
+        else {
+            return callbacks;
+        };
+
+        // IMAGE_TLS_DIRECTORY.AddressOfCallBacks is a VA (not an RVA) which has already been
+        // relocated by the loader, as have the callback pointers in the array it points to.
+        let pointer_size = if self.is_64bit { 8 } else { 4 };
+        let Some(mut callback_pointer_address) = tls_directory_address
+            .checked_add(3 * pointer_size)
+            .and_then(|address| memory.read_pointer(address, self.is_64bit))
+        else {
+            return callbacks;
+        };
+        while callback_pointer_address != 0 && callbacks.len() < MAX_TABLE_ENTRIES {
+            match memory.read_pointer(callback_pointer_address, self.is_64bit) {
+                Some(callback) if callback != 0 => callbacks.push(callback),
+                _ => break,
+            }
+            let Some(next_callback_pointer_address) =
+                callback_pointer_address.checked_add(pointer_size)
+            else {
+                break;
+            };
+            callback_pointer_address = next_callback_pointer_address;
+        }
+        callbacks
+    }
+
+    /// Look up the entry in the image's x64 function table (the `.pdata` section) for the function
+    /// containing a virtual address. Functions without an entry are leaf functions, which don't
+    /// change the stack pointer or save any registers.
+    pub fn runtime_function(&self, memory: &DumpMemory, address: u64) -> Option<RuntimeFunction> {
+        let (table_address, table_size) = self.data_directory(IMAGE_DIRECTORY_ENTRY_EXCEPTION)?;
+        let entry_count = table_size as u64 / IMAGE_SIZEOF_RUNTIME_FUNCTION_ENTRY;
+
+        // The function table is sorted by BeginAddress
+        let (mut low, mut high) = (0, entry_count);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let entry = RuntimeFunction::read(
+                memory,
+                table_address + middle * IMAGE_SIZEOF_RUNTIME_FUNCTION_ENTRY,
+                self.base_address,
+            )?;
+            if address < entry.begin_address {
+                high = middle;
+            } else if address >= entry.end_address {
+                low = middle + 1;
+            } else {
+                return Some(entry);
+            }
+        }
+        None
+    }
+
+    /// The CPU architecture of the image, in the same form as reported by a `MinidumpSystemInfo` stream.
+    pub fn minidump_cpu(&self) -> Option<Cpu> {
+        match self.machine {
+            IMAGE_FILE_MACHINE_I386 => Some(Cpu::X86),
+            IMAGE_FILE_MACHINE_AMD64 => Some(Cpu::X86_64),
+            IMAGE_FILE_MACHINE_ARMNT => Some(Cpu::Arm),
+            IMAGE_FILE_MACHINE_ARM64 => Some(Cpu::Arm64),
+            _ => None,
+        }
+    }
+}
+
+/// An entry in the x64 function table of a mapped PE image (`RUNTIME_FUNCTION`),
+/// with its RVAs converted to virtual addresses.
+#[derive(Debug, Clone, Copy)]
+pub struct RuntimeFunction {
+    pub begin_address: u64,
+    pub end_address: u64,
+    /// The virtual address of the function's `UNWIND_INFO`.
+    pub unwind_info_address: u64,
+}
+
+impl RuntimeFunction {
+    pub fn read(memory: &DumpMemory, address: u64, image_base: u64) -> Option<Self> {
+        Some(RuntimeFunction {
+            begin_address: image_base + memory.read_u32(address)? as u64,
+            end_address: image_base + memory.read_u32(address + 4)? as u64,
+            unwind_info_address: image_base + memory.read_u32(address + 8)? as u64,
+        })
+    }
+}
+
+/// Where an exported symbol of a PE image actually lives.
+#[derive(Debug, Clone)]
+pub enum ExportTarget {
+    /// The virtual address of the exported function or data.
+    Address(u64),
+    /// A forwarder string (e.g. `NTDLL.RtlAllocateHeap` or `NTDLL.#123`),
+    /// which means the export is actually implemented by another module.
+    Forwarder(String),
+}
+
+/// An entry in the export directory of a mapped PE image.
+#[derive(Debug, Clone)]
+pub struct PeExport {
+    /// The names of the export, which is empty if the export is only exported by ordinal.
+    /// An export has several names when they are aliases for the same function or data.
+    pub names: Vec<String>,
+    pub ordinal: u32,
+    pub target: ExportTarget,
+}
+
+impl PeExport {
+    /// The names to use for the export's symbols: each of its names,
+    /// or `Ordinal_<n>` for exports without names.
+    pub fn symbol_names(&self) -> Vec<String> {
+        if self.names.is_empty() {
+            vec![format!("Ordinal_{}", self.ordinal)]
+        } else {
+            self.names.clone()
+        }
+    }
+
+    /// The name to describe the export by: its first name, or `Ordinal_<n>` for exports without names.
+    pub fn display_name(&self) -> String {
+        match self.names.first() {
+            Some(name) => name.clone(),
+            None => format!("Ordinal_{}", self.ordinal),
+        }
+    }
+}
+
+impl PeHeaders {
+    /// Read the export directory of the image.
+    pub fn exports(&self, memory: &DumpMemory) -> Vec<PeExport> {
+        let mut exports = Vec::new();
+        let Some((export_directory_address, export_directory_size)) =
+            self.data_directory(IMAGE_DIRECTORY_ENTRY_EXPORT)
+        else {
+            return exports;
+        };
+        let Some(export_directory) = memory.read(export_directory_address, 0x28) else {
+            return exports;
+        };
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes(export_directory[offset..offset + 4].try_into().unwrap())
+        };
+        let ordinal_base = read_u32(0x10);
+        let number_of_functions = (read_u32(0x14) as usize).min(MAX_EXPORTS);
+        let number_of_names = (read_u32(0x18) as usize).min(MAX_EXPORTS);
+        let address_of_functions = self.base_address + read_u32(0x1c) as u64;
+        let address_of_names = self.base_address + read_u32(0x20) as u64;
+        let address_of_name_ordinals = self.base_address + read_u32(0x24) as u64;
+
+        // Map the indices into AddressOfFunctions to the names which refer to them.
+        // Several names can refer to the same function, e.g. when one is an alias of another.
+        let mut names_by_function_index: HashMap<usize, Vec<String>> = HashMap::new();
+        for name_index in 0..number_of_names as u64 {
+            let (Some(name_rva), Some(name_bytes)) = (
+                memory.read_u32(address_of_names + name_index * 4),
+                memory.read(address_of_name_ordinals + name_index * 2, 2),
+            ) else {
+                break;
+            };
+            let function_index = u16::from_le_bytes([name_bytes[0], name_bytes[1]]);
+            if let Some(name) =
+                memory.read_c_string(self.base_address + name_rva as u64, MAX_NAME_LENGTH)
+            {
+                names_by_function_index
+                    .entry(function_index as usize)
+                    .or_default()
+                    .push(name);
+            }
+        }
+
+        let export_directory_range =
+            export_directory_address..export_directory_address + export_directory_size as u64;
+        for function_index in 0..number_of_functions {
+            let Some(function_rva) =
+                memory.read_u32(address_of_functions + function_index as u64 * 4)
+            else {
+                break;
+            };
+            // Unused slots in the table of functions are left as 0
+            if function_rva == 0 {
+                continue;
+            }
+            let function_address = self.base_address + function_rva as u64;
+
+            // Exports whose address points into the export directory itself are forwarders
+            let target = if export_directory_range.contains(&function_address) {
+                match memory.read_c_string(function_address, MAX_NAME_LENGTH) {
+                    Some(forwarder) => ExportTarget::Forwarder(forwarder),
+                    None => continue,
+                }
+            } else {
+                ExportTarget::Address(function_address)
+            };
+
+            exports.push(PeExport {
+                names: names_by_function_index
+                    .remove(&function_index)
+                    .unwrap_or_default(),
+                ordinal: ordinal_base + function_index as u32,
+                target,
+            });
+        }
+        exports
+    }
+}
+
+/// How an entry in an import table refers to the function or data which it imports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportName {
+    Name(String),
+    Ordinal(u16),
+}
+
+/// A slot in the import address table (IAT) of a mapped PE image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeImport {
+    /// The name of the imported DLL, as written in the import descriptor (e.g. `KERNEL32.dll`).
+    pub dll_name: String,
+    /// The imported function or data, or `None` if the image has no import name table
+    /// (i.e. only has a pre-bound IAT).
+    pub name: Option<ImportName>,
+    pub slot_address: u64,
+    /// The address in the IAT slot in the dumped process, which the loader has already bound
+    /// to the imported function or data.
+    pub slot_value: Option<u64>,
+    pub is_delay_load: bool,
+}
+
+impl PeHeaders {
+    /// Read the import directory and delay-load import directory of the image.
+    pub fn imports(&self, memory: &DumpMemory) -> Vec<PeImport> {
+        let mut imports = Vec::new();
+
+        if let Some((import_directory_address, _)) =
+            self.data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT)
+        {
+            for index in 0..MAX_TABLE_ENTRIES as u64 {
+                let descriptor_address =
+                    import_directory_address + index * IMAGE_SIZEOF_IMPORT_DESCRIPTOR;
+                // IMAGE_IMPORT_DESCRIPTOR
+                let (Some(original_first_thunk), Some(name_rva), Some(first_thunk)) = (
+                    memory.read_u32(descriptor_address),
+                    memory.read_u32(descriptor_address + 12),
+                    memory.read_u32(descriptor_address + 16),
+                ) else {
+                    break;
+                };
+                // The import directory is terminated by an all-zero descriptor
+                if name_rva == 0 || first_thunk == 0 {
+                    break;
+                }
+                self.read_import_thunks(
+                    memory,
+                    name_rva,
+                    original_first_thunk,
+                    first_thunk,
+                    false,
+                    &mut imports,
+                );
+            }
+        }
+
+        if let Some((delay_import_directory_address, _)) =
+            self.data_directory(IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT)
+        {
+            for index in 0..MAX_TABLE_ENTRIES as u64 {
+                let descriptor_address =
+                    delay_import_directory_address + index * IMAGE_SIZEOF_DELAYLOAD_DESCRIPTOR;
+                // IMAGE_DELAYLOAD_DESCRIPTOR
+                let (
+                    Some(attributes),
+                    Some(name_rva),
+                    Some(import_address_table_rva),
+                    Some(import_name_table_rva),
+                ) = (
+                    memory.read_u32(descriptor_address),
+                    memory.read_u32(descriptor_address + 4),
+                    memory.read_u32(descriptor_address + 12),
+                    memory.read_u32(descriptor_address + 16),
+                )
+                else {
+                    break;
+                };
+                if name_rva == 0 || import_address_table_rva == 0 {
+                    break;
+                }
+                // Very old linkers wrote VAs instead of RVAs into the descriptor;
+                // these images are rare enough that we don't bother with them.
+                if attributes & 1 == 0 {
+                    continue;
+                }
+                self.read_import_thunks(
+                    memory,
+                    name_rva,
+                    import_name_table_rva,
+                    import_address_table_rva,
+                    true,
+                    &mut imports,
+                );
+            }
+        }
+
+        imports
+    }
+
+    fn read_import_thunks(
+        &self,
+        memory: &DumpMemory,
+        dll_name_rva: u32,
+        name_table_rva: u32,
+        address_table_rva: u32,
+        is_delay_load: bool,
+        imports: &mut Vec<PeImport>,
+    ) {
+        let Some(dll_name) =
+            memory.read_c_string(self.base_address + dll_name_rva as u64, MAX_NAME_LENGTH)
+        else {
+            return;
+        };
+        let pointer_size = if self.is_64bit { 8 } else { 4 };
+        let ordinal_flag = if self.is_64bit { 1 << 63 } else { 1 << 31 };
+        let address_table_address = self.base_address + address_table_rva as u64;
+        // Without an import name table, the IAT itself is the only table, and it has been
+        // overwritten by the loader; the names can then only be recovered from the bound addresses.
+        let name_table_address = if name_table_rva != 0 {
+            Some(self.base_address + name_table_rva as u64)
+        } else {
+            None
+        };
+
+        for index in 0..MAX_TABLE_ENTRIES as u64 {
+            let slot_address = address_table_address + index * pointer_size;
+            let slot_value = memory.read_pointer(slot_address, self.is_64bit);
+            let name = match name_table_address {
+                Some(name_table_address) => {
+                    let Some(thunk) = memory
+                        .read_pointer(name_table_address + index * pointer_size, self.is_64bit)
+                    else {
+                        break;
+                    };
+                    if thunk == 0 {
+                        break;
+                    }
+                    if thunk & ordinal_flag != 0 {
+                        Some(ImportName::Ordinal(thunk as u16))
+                    } else {
+                        // IMAGE_IMPORT_BY_NAME: a 16-bit hint, followed by the name
+                        memory
+                            .read_c_string(
+                                self.base_address + (thunk & 0x7fff_ffff) + 2,
+                                MAX_NAME_LENGTH,
+                            )
+                            .map(ImportName::Name)
+                    }
+                }
+                None => match slot_value {
+                    Some(0) | None => break,
+                    Some(_) => None,
+                },
+            };
+            imports.push(PeImport {
+                dll_name: dll_name.clone(),
+                name,
+                slot_address,
+                slot_value,
+                is_delay_load,
+            });
+        }
+    }
+}
+
+/// An IAT slot which doesn't point to the export it imports, e.g. because the import has been hooked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportMismatch {
+    /// The address of the export which the slot imports.
+    pub expected_address: u64,
+    /// The address which the slot actually points to.
+    pub slot_value: u64,
+}
+
+#[derive(Debug, Default)]
+struct ModuleExportTable {
+    by_name: HashMap<String, ExportTarget>,
+    by_ordinal: HashMap<u32, ExportTarget>,
+}
+
+/// The exports of every PE module in a minidump, used to resolve forwarded exports
+/// and imports to their targets in other modules.
+///
+/// Modules are looked up case-insensitively by their file name without the extension
+/// (e.g. `ntdll`), since this is how forwarder strings refer to modules.
+#[derive(Debug, Default)]
+pub struct ExportIndex {
+    modules: HashMap<String, ModuleExportTable>,
+    /// The names of the exports (e.g. `ntdll.dll!RtlAllocateHeap`) implemented at each address.
+    names_by_address: HashMap<u64, String>,
+}
+
+impl ExportIndex {
+    /// The key under which a module is stored in the index, e.g. `ntdll` for `C:\Windows\System32\ntdll.dll`.
+    pub fn module_key(module_name: &str) -> String {
+        let file_name = module_file_name(module_name);
+        let stem = match file_name.rsplit_once('.') {
+            Some((stem, _extension)) => stem,
+            None => file_name,
+        };
+        stem.to_ascii_lowercase()
+    }
+
+    pub fn insert(&mut self, module_name: &str, exports: &[PeExport]) {
+        let module_table = self
+            .modules
+            .entry(ExportIndex::module_key(module_name))
+            .or_default();
+        let module_file_name = module_file_name(module_name);
+        for export in exports {
+            if let ExportTarget::Address(address) = export.target {
+                self.names_by_address
+                    .entry(address)
+                    .or_insert_with(|| format!("{}!{}", module_file_name, export.display_name()));
+            }
+            for name in export.names.iter() {
+                module_table
+                    .by_name
+                    .insert(name.clone(), export.target.clone());
+            }
+            module_table
+                .by_ordinal
+                .insert(export.ordinal, export.target.clone());
+        }
+    }
+
+    /// Resolve an import, by the name of its DLL and its name or ordinal, to the address
+    /// of the export which implements it.
+    pub fn resolve_import(&self, dll_name: &str, import_name: &ImportName) -> Option<u64> {
+        let module_table = self.modules.get(&ExportIndex::module_key(dll_name))?;
+        let target = match import_name {
+            ImportName::Name(name) => module_table.by_name.get(name)?,
+            ImportName::Ordinal(ordinal) => module_table.by_ordinal.get(&(*ordinal as u32))?,
+        };
+        self.resolve_target(target, 0)
+    }
+
+    /// Check whether a bound IAT slot points to the export it imports. Returns the mismatch if it
+    /// points somewhere else, or `None` if it doesn't, or if the import can't be resolved.
+    ///
+    /// `importing_module_range` is the range of the module which the IAT belongs to: delay-load
+    /// imports which have not been called yet still point to the delay-load helper thunks in
+    /// the importing module itself, which isn't a mismatch.
+    pub fn import_mismatch(
+        &self,
+        import: &PeImport,
+        importing_module_range: &Range<u64>,
+    ) -> Option<ImportMismatch> {
+        let expected_address = self.resolve_import(&import.dll_name, import.name.as_ref()?)?;
+        let slot_value = import.slot_value?;
+        if import.is_delay_load && importing_module_range.contains(&slot_value) {
+            return None;
+        }
+        (slot_value != expected_address).then_some(ImportMismatch {
+            expected_address,
+            slot_value,
+        })
+    }
+
+    /// The name of the export implemented at an address, if any (e.g. `ntdll.dll!RtlAllocateHeap`).
+    pub fn name_at(&self, address: u64) -> Option<&str> {
+        self.names_by_address.get(&address).map(String::as_str)
+    }
+
+    /// Resolve an export to the address which implements it, following forwarders to other modules.
+    pub fn resolve(&self, target: &ExportTarget) -> Option<u64> {
+        self.resolve_target(target, 0)
+    }
+
+    fn resolve_target(&self, target: &ExportTarget, depth: usize) -> Option<u64> {
+        match target {
+            ExportTarget::Address(address) => Some(*address),
+            ExportTarget::Forwarder(forwarder) => {
+                if depth >= MAX_FORWARDER_CHAIN_LENGTH {
+                    return None;
+                }
+                let (module_name, export) = forwarder.rsplit_once('.')?;
+                let module_table = self.modules.get(&module_name.to_ascii_lowercase())?;
+                let next_target = match export.strip_prefix('#') {
+                    Some(ordinal) => module_table.by_ordinal.get(&ordinal.parse().ok()?)?,
+                    None => module_table.by_name.get(export)?,
+                };
+                self.resolve_target(next_target, depth + 1)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const IMAGE_BASE: u64 = 0x7ff6_0000_0000;
+
+    fn put(image: &mut Vec<u8>, offset: usize, bytes: &[u8]) {
+        if image.len() < offset + bytes.len() {
+            image.resize(offset + bytes.len(), 0);
+        }
+        image[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn put_u32(image: &mut Vec<u8>, offset: usize, value: u32) {
+        put(image, offset, &value.to_le_bytes());
+    }
+
+    /// The bound addresses of `KERNEL32.dll!CreateFileW` and `KERNEL32.dll!Ordinal_5`
+    /// in the IAT of [`pe_image`].
+    pub(crate) const CREATE_FILE_W: u64 = 0x7ffa_0000_2000;
+    pub(crate) const KERNEL32_ORDINAL_5: u64 = 0x7ffa_0000_3000;
+
+    /// A mapped x64 image with a `.text` section, an export directory, a function table,
+    /// an import directory, and a delay-load import directory.
+    ///
+    /// The exports are `Alpha` and its alias `AlphaAlias` at RVA 0x1000, ordinal 2 without a name
+    /// at RVA 0x1010, and `Forwarded`, which is forwarded to `NTDLL.RtlAllocateHeap`. The function
+    /// table has entries for 0x1000..0x1010 and 0x1010..0x1040, with their `UNWIND_INFO` at
+    /// RVAs 0x2300 and 0x2310. The `UNWIND_INFO` and any code are written from the
+    /// `(rva, bytes)` pairs in `contents`.
+    ///
+    /// The image imports `CreateFileW` and ordinal 5 from `KERNEL32.dll`, with their IAT slots at
+    /// RVA 0x2520 bound to [`CREATE_FILE_W`] and [`KERNEL32_ORDINAL_5`], and two functions from
+    /// `test.dll` without an import name table, with their IAT slots at RVA 0x2540 bound to
+    /// RVAs 0x1000 and 0x1010 of the image. It delay-loads `MessageBoxW` from `USER32.dll`, with
+    /// its IAT slot at RVA 0x2570 still pointing to the delay-load helper thunk at RVA 0x1030.
+    pub(crate) fn pe_image(contents: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut image = vec![0; 0x3000];
+        put(&mut image, 0, b"MZ");
+        put_u32(&mut image, 0x3c, 0x40);
+
+        put(&mut image, 0x40, b"PE\0\0");
+        put(&mut image, 0x44, &IMAGE_FILE_MACHINE_AMD64.to_le_bytes());
+        put(&mut image, 0x46, &1u16.to_le_bytes()); // NumberOfSections
+        put(&mut image, 0x54, &0xf0u16.to_le_bytes()); // SizeOfOptionalHeader
+
+        let optional_header = 0x58;
+        put(
+            &mut image,
+            optional_header,
+            &IMAGE_NT_OPTIONAL_HDR64_MAGIC.to_le_bytes(),
+        );
+        put_u32(&mut image, optional_header + 0x10, 0x1000); // AddressOfEntryPoint
+        put_u32(&mut image, optional_header + 0x38, 0x3000); // SizeOfImage
+        put_u32(&mut image, optional_header + 0x6c, 16); // NumberOfRvaAndSizes
+        let data_directory = |index: usize| optional_header + 0x70 + index * 8;
+        put_u32(
+            &mut image,
+            data_directory(IMAGE_DIRECTORY_ENTRY_EXPORT),
+            0x2000,
+        );
+        put_u32(
+            &mut image,
+            data_directory(IMAGE_DIRECTORY_ENTRY_EXPORT) + 4,
+            0x100,
+        );
+        put_u32(
+            &mut image,
+            data_directory(IMAGE_DIRECTORY_ENTRY_EXCEPTION),
+            0x2200,
+        );
+        put_u32(
+            &mut image,
+            data_directory(IMAGE_DIRECTORY_ENTRY_EXCEPTION) + 4,
+            24,
+        );
+        put_u32(
+            &mut image,
+            data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT),
+            0x2400,
+        );
+        put_u32(
+            &mut image,
+            data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT) + 4,
+            3 * IMAGE_SIZEOF_IMPORT_DESCRIPTOR as u32,
+        );
+        put_u32(
+            &mut image,
+            data_directory(IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT),
+            0x2440,
+        );
+        put_u32(
+            &mut image,
+            data_directory(IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT) + 4,
+            2 * IMAGE_SIZEOF_DELAYLOAD_DESCRIPTOR as u32,
+        );
+
+        let section_table = optional_header + 0xf0;
+        put(&mut image, section_table, b".text");
+        put_u32(&mut image, section_table + 8, 0x1000); // VirtualSize
+        put_u32(&mut image, section_table + 12, 0x1000); // VirtualAddress
+        put_u32(
+            &mut image,
+            section_table + 36,
+            IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE,
+        );
+
+        // IMAGE_EXPORT_DIRECTORY
+        put_u32(&mut image, 0x2010, 1); // Base
+        put_u32(&mut image, 0x2014, 3); // NumberOfFunctions
+        put_u32(&mut image, 0x2018, 3); // NumberOfNames
+        put_u32(&mut image, 0x201c, 0x2028); // AddressOfFunctions
+        put_u32(&mut image, 0x2020, 0x2034); // AddressOfNames
+        put_u32(&mut image, 0x2024, 0x2040); // AddressOfNameOrdinals
+        for (index, function_rva) in [0x1000, 0x1010, 0x20a0].into_iter().enumerate() {
+            put_u32(&mut image, 0x2028 + index * 4, function_rva);
+        }
+        for (index, (name_rva, function_index)) in [(0x2050, 0u16), (0x2060, 0), (0x2070, 2)]
+            .into_iter()
+            .enumerate()
+        {
+            put_u32(&mut image, 0x2034 + index * 4, name_rva);
+            put(
+                &mut image,
+                0x2040 + index * 2,
+                &function_index.to_le_bytes(),
+            );
+        }
+        put(&mut image, 0x2050, b"Alpha\0");
+        put(&mut image, 0x2060, b"AlphaAlias\0");
+        put(&mut image, 0x2070, b"Forwarded\0");
+        put(&mut image, 0x20a0, b"NTDLL.RtlAllocateHeap\0");
+
+        // RUNTIME_FUNCTION entries
+        for (index, (begin, end, unwind_info_rva)) in
+            [(0x1000, 0x1010, 0x2300), (0x1010, 0x1040, 0x2310)]
+                .into_iter()
+                .enumerate()
+        {
+            put_u32(&mut image, 0x2200 + index * 12, begin);
+            put_u32(&mut image, 0x2200 + index * 12 + 4, end);
+            put_u32(&mut image, 0x2200 + index * 12 + 8, unwind_info_rva);
+        }
+        // IMAGE_IMPORT_DESCRIPTORs: OriginalFirstThunk, Name, and FirstThunk, then an all-zero terminator
+        for (index, (name_table_rva, name_rva, address_table_rva)) in
+            [(0x2500, 0x2480, 0x2520), (0, 0x2490, 0x2540)]
+                .into_iter()
+                .enumerate()
+        {
+            let descriptor = 0x2400 + index * IMAGE_SIZEOF_IMPORT_DESCRIPTOR as usize;
+            put_u32(&mut image, descriptor, name_table_rva);
+            put_u32(&mut image, descriptor + 12, name_rva);
+            put_u32(&mut image, descriptor + 16, address_table_rva);
+        }
+        // IMAGE_DELAYLOAD_DESCRIPTOR, with RVAs, then an all-zero terminator
+        put_u32(&mut image, 0x2440, 1); // Attributes
+        put_u32(&mut image, 0x2444, 0x24a0); // DllNameRVA
+        put_u32(&mut image, 0x244c, 0x2570); // ImportAddressTableRVA
+        put_u32(&mut image, 0x2450, 0x2560); // ImportNameTableRVA
+
+        put(&mut image, 0x2480, b"KERNEL32.dll\0");
+        put(&mut image, 0x2490, b"test.dll\0");
+        put(&mut image, 0x24a0, b"USER32.dll\0");
+        // IMAGE_IMPORT_BY_NAME: a hint, followed by the name
+        put(&mut image, 0x24b0, b"\x01\x00CreateFileW\0");
+        put(&mut image, 0x24c8, b"\x02\x00MessageBoxW\0");
+
+        let put_u64 = |image: &mut Vec<u8>, offset: usize, value: u64| {
+            put(image, offset, &value.to_le_bytes());
+        };
+        // KERNEL32.dll: the import name table, and the bound IAT
+        put_u64(&mut image, 0x2500, 0x24b0);
+        put_u64(&mut image, 0x2508, (1 << 63) | 5);
+        put_u64(&mut image, 0x2520, CREATE_FILE_W);
+        put_u64(&mut image, 0x2528, KERNEL32_ORDINAL_5);
+        // test.dll: only the bound IAT
+        put_u64(&mut image, 0x2540, IMAGE_BASE + 0x1000);
+        put_u64(&mut image, 0x2548, IMAGE_BASE + 0x1010);
+        // USER32.dll: the delay-load import name table, and the IAT before the import is first called
+        put_u64(&mut image, 0x2560, 0x24c8);
+        put_u64(&mut image, 0x2570, IMAGE_BASE + 0x1030);
+
+        for (rva, bytes) in contents {
+            put(&mut image, *rva as usize, bytes);
+        }
+        image
+    }
+
+    #[test]
+    fn reads_headers_and_sections() {
+        let image = pe_image(&[]);
+        let memory = DumpMemory::from_regions(&[(IMAGE_BASE, &image)]);
+        let pe_headers = PeHeaders::read(&memory, IMAGE_BASE).unwrap();
+        assert!(pe_headers.is_64bit);
+        assert_eq!(pe_headers.minidump_cpu(), Some(Cpu::X86_64));
+        assert_eq!(pe_headers.entry_point(), Some(IMAGE_BASE + 0x1000));
+        assert_eq!(pe_headers.sections.len(), 1);
+        assert_eq!(pe_headers.sections[0].name, ".text");
+        assert!(pe_headers.sections[0].is_code());
+        assert!(pe_headers.tls_callbacks(&memory).is_empty());
+    }
+
+    #[test]
+    fn rejects_headers_past_the_end_of_the_address_space() {
+        let base_address = u64::MAX - 0xff;
+        let mut dos_header = vec![0; 0x40];
+        dos_header[0..2].copy_from_slice(b"MZ");
+        dos_header[0x3c..0x40].copy_from_slice(&0x1000u32.to_le_bytes());
+        let memory = DumpMemory::from_regions(&[(base_address, &dos_header)]);
+        assert!(PeHeaders::read(&memory, base_address).is_none());
+    }
+
+    #[test]
+    fn reads_every_name_of_aliased_exports() {
+        let image = pe_image(&[]);
+        let memory = DumpMemory::from_regions(&[(IMAGE_BASE, &image)]);
+        let exports = PeHeaders::read(&memory, IMAGE_BASE)
+            .unwrap()
+            .exports(&memory);
+        assert_eq!(exports.len(), 3);
+        assert_eq!(exports[0].names, vec!["Alpha", "AlphaAlias"]);
+        assert_eq!(exports[0].ordinal, 1);
+        assert_eq!(exports[1].symbol_names(), vec!["Ordinal_2"]);
+        assert_eq!(exports[2].names, vec!["Forwarded"]);
+        assert!(matches!(
+            &exports[2].target,
+            ExportTarget::Forwarder(forwarder) if forwarder == "NTDLL.RtlAllocateHeap"
+        ));
+    }
+
+    #[test]
+    fn resolves_imports_through_aliases_and_forwarders() {
+        let image = pe_image(&[]);
+        let memory = DumpMemory::from_regions(&[(IMAGE_BASE, &image)]);
+        let exports = PeHeaders::read(&memory, IMAGE_BASE)
+            .unwrap()
+            .exports(&memory);
+        let mut export_index = ExportIndex::default();
+        export_index.insert(r"C:\Program Files\Test\test.dll", &exports);
+        export_index.insert(
+            r"C:\Windows\System32\ntdll.dll",
+            &[PeExport {
+                names: vec!["RtlAllocateHeap".to_string()],
+                ordinal: 7,
+                target: ExportTarget::Address(0x7ffa_0000_1000),
+            }],
+        );
+
+        let alias = ImportName::Name("AlphaAlias".to_string());
+        assert_eq!(
+            export_index.resolve_import("TEST.dll", &alias),
+            Some(IMAGE_BASE + 0x1000)
+        );
+        let forwarded = ImportName::Name("Forwarded".to_string());
+        assert_eq!(
+            export_index.resolve_import("test.dll", &forwarded),
+            Some(0x7ffa_0000_1000)
+        );
+        assert_eq!(
+            export_index.resolve_import("test.dll", &ImportName::Ordinal(2)),
+            Some(IMAGE_BASE + 0x1010)
+        );
+        assert_eq!(
+            export_index.name_at(IMAGE_BASE + 0x1000),
+            Some("test.dll!Alpha")
+        );
+    }
+
+    #[test]
+    fn reads_imports_and_delay_load_imports() {
+        let image = pe_image(&[]);
+        let memory = DumpMemory::from_regions(&[(IMAGE_BASE, &image)]);
+        let imports = PeHeaders::read(&memory, IMAGE_BASE)
+            .unwrap()
+            .imports(&memory);
+        let import = |dll_name: &str, name, slot_rva: u64, slot_value, is_delay_load| PeImport {
+            dll_name: dll_name.to_string(),
+            name,
+            slot_address: IMAGE_BASE + slot_rva,
+            slot_value: Some(slot_value),
+            is_delay_load,
+        };
+        assert_eq!(
+            imports,
+            [
+                import(
+                    "KERNEL32.dll",
+                    Some(ImportName::Name("CreateFileW".to_string())),
+                    0x2520,
+                    CREATE_FILE_W,
+                    false,
+                ),
+                import(
+                    "KERNEL32.dll",
+                    Some(ImportName::Ordinal(5)),
+                    0x2528,
+                    KERNEL32_ORDINAL_5,
+                    false,
+                ),
+                // Without an import name table, the IAT is read until its first empty slot
+                import("test.dll", None, 0x2540, IMAGE_BASE + 0x1000, false),
+                import("test.dll", None, 0x2548, IMAGE_BASE + 0x1010, false),
+                import(
+                    "USER32.dll",
+                    Some(ImportName::Name("MessageBoxW".to_string())),
+                    0x2570,
+                    IMAGE_BASE + 0x1030,
+                    true,
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_import_mismatches() {
+        let image = pe_image(&[]);
+        let memory = DumpMemory::from_regions(&[(IMAGE_BASE, &image)]);
+        let imports = PeHeaders::read(&memory, IMAGE_BASE)
+            .unwrap()
+            .imports(&memory);
+        let mut export_index = ExportIndex::default();
+        export_index.insert(
+            r"C:\Windows\System32\kernel32.dll",
+            &[
+                PeExport {
+                    names: vec!["CreateFileW".to_string()],
+                    ordinal: 4,
+                    target: ExportTarget::Address(CREATE_FILE_W),
+                },
+                PeExport {
+                    names: Vec::new(),
+                    ordinal: 5,
+                    target: ExportTarget::Address(0x7ffa_0000_4000),
+                },
+            ],
+        );
+        export_index.insert(
+            r"C:\Windows\System32\user32.dll",
+            &[PeExport {
+                names: vec!["MessageBoxW".to_string()],
+                ordinal: 1,
+                target: ExportTarget::Address(0x7ffb_0000_1000),
+            }],
+        );
+
+        let module_range = IMAGE_BASE..IMAGE_BASE + 0x3000;
+        let mismatches: Vec<_> = imports
+            .iter()
+            .map(|import| export_index.import_mismatch(import, &module_range))
+            .collect();
+        assert_eq!(
+            mismatches,
+            [
+                None,
+                // The slot of ordinal 5 has been overwritten
+                Some(ImportMismatch {
+                    expected_address: 0x7ffa_0000_4000,
+                    slot_value: KERNEL32_ORDINAL_5,
+                }),
+                // Imports without names can't be checked
+                None,
+                None,
+                // A delay-load import which hasn't been called yet
+                None,
+            ]
+        );
+
+        // Once called, a delay-load import must point to the export too
+        let called_delay_load_import = PeImport {
+            slot_value: Some(0x7ffb_0000_2000),
+            ..imports[4].clone()
+        };
+        assert_eq!(
+            export_index.import_mismatch(&called_delay_load_import, &module_range),
+            Some(ImportMismatch {
+                expected_address: 0x7ffb_0000_1000,
+                slot_value: 0x7ffb_0000_2000,
+            })
+        );
+    }
+
+    #[test]
+    fn finds_runtime_functions() {
+        let image = pe_image(&[]);
+        let memory = DumpMemory::from_regions(&[(IMAGE_BASE, &image)]);
+        let pe_headers = PeHeaders::read(&memory, IMAGE_BASE).unwrap();
+        let runtime_function = pe_headers
+            .runtime_function(&memory, IMAGE_BASE + 0x1020)
+            .unwrap();
+        assert_eq!(runtime_function.begin_address, IMAGE_BASE + 0x1010);
+        assert_eq!(runtime_function.unwind_info_address, IMAGE_BASE + 0x2310);
+        assert!(pe_headers
+            .runtime_function(&memory, IMAGE_BASE + 0x1040)
+            .is_none());
+    }
+}
@@ -0,0 +1,477 @@
+use std::io::{self, Write};
+use std::ops::Deref;
+
+use minidump::format::MINIDUMP_STREAM_TYPE;
+use minidump::{
+    Endian, Minidump, MinidumpAssertion, MinidumpBreakpadInfo, MinidumpCrashpadInfo,
+    MinidumpException, MinidumpLinuxCpuInfo, MinidumpLinuxEnviron, MinidumpLinuxLsbRelease,
+    MinidumpLinuxMaps, MinidumpLinuxProcStatus, MinidumpMacCrashInfo, MinidumpMemory64List,
+    MinidumpMemoryInfoList, MinidumpMemoryList, MinidumpMiscInfo, MinidumpModuleList,
+    MinidumpSystemInfo, MinidumpThreadList, MinidumpThreadNames, MinidumpUnloadedModuleList,
+};
+
+use crate::linux::{read_u32, LinuxProcessInfo};
+
+/// The size of a `MINIDUMP_DIRECTORY` entry.
+const MINIDUMP_DIRECTORY_SIZE: usize = 12;
+
+/// An entry in the stream directory of a minidump (`MINIDUMP_DIRECTORY`).
+#[derive(Debug, Clone, Copy)]
+pub struct StreamDirectoryEntry {
+    pub stream_type: u32,
+    pub data_size: u32,
+    pub rva: u32,
+}
+
+/// Read the stream directory of a minidump from the raw data of the minidump file,
+/// including the entries for streams which the minidump crate doesn't know about.
+pub fn read_stream_directory(dump_data: &[u8], endian: Endian) -> Vec<StreamDirectoryEntry> {
+    let (Some(stream_count), Some(stream_directory_rva)) = (
+        read_u32(dump_data, 8, endian),
+        read_u32(dump_data, 12, endian),
+    ) else {
+        return Vec::new();
+    };
+    (0..stream_count as usize)
+        .map_while(|index| {
+            let offset = stream_directory_rva as usize + index * MINIDUMP_DIRECTORY_SIZE;
+            Some(StreamDirectoryEntry {
+                stream_type: read_u32(dump_data, offset, endian)?,
+                data_size: read_u32(dump_data, offset + 4, endian)?,
+                rva: read_u32(dump_data, offset + 8, endian)?,
+            })
+        })
+        .collect()
+}
+
+/// Every stream type which has a name, along with whether [`print_stream`] can print it in a
+/// human-readable form (every other stream is printed as a hexdump).
+///
+/// The names are the ones from the Windows SDK for standard streams, and from Breakpad, Crashpad,
+/// and Mozilla for their own streams (e.g. `ThreadListStream`, `LinuxMaps`).
+const STREAM_TYPES: &[(u32, &str, bool)] = &[
+    (
+        MINIDUMP_STREAM_TYPE::UnusedStream as u32,
+        "UnusedStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::ThreadListStream as u32,
+        "ThreadListStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::ModuleListStream as u32,
+        "ModuleListStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::MemoryListStream as u32,
+        "MemoryListStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::ExceptionStream as u32,
+        "ExceptionStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::SystemInfoStream as u32,
+        "SystemInfoStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::ThreadExListStream as u32,
+        "ThreadExListStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::Memory64ListStream as u32,
+        "Memory64ListStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::CommentStreamA as u32,
+        "CommentStreamA",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::CommentStreamW as u32,
+        "CommentStreamW",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::HandleDataStream as u32,
+        "HandleDataStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::FunctionTable as u32,
+        "FunctionTableStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::UnloadedModuleListStream as u32,
+        "UnloadedModuleListStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::MiscInfoStream as u32,
+        "MiscInfoStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::MemoryInfoListStream as u32,
+        "MemoryInfoListStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::ThreadInfoListStream as u32,
+        "ThreadInfoListStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::HandleOperationListStream as u32,
+        "HandleOperationListStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::TokenStream as u32,
+        "TokenStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::JavaScriptDataStream as u32,
+        "JavaScriptDataStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::SystemMemoryInfoStream as u32,
+        "SystemMemoryInfoStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::ProcessVmCountersStream as u32,
+        "ProcessVmCountersStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::IptTraceStream as u32,
+        "IptTraceStream",
+        false,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::ThreadNamesStream as u32,
+        "ThreadNamesStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::BreakpadInfoStream as u32,
+        "BreakpadInfoStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::AssertionInfoStream as u32,
+        "AssertionInfoStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::LinuxCpuInfo as u32,
+        "LinuxCpuInfo",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::LinuxProcStatus as u32,
+        "LinuxProcStatus",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::LinuxLsbRelease as u32,
+        "LinuxLsbRelease",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::LinuxCmdLine as u32,
+        "LinuxCmdLine",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::LinuxEnviron as u32,
+        "LinuxEnviron",
+        true,
+    ),
+    (MINIDUMP_STREAM_TYPE::LinuxAuxv as u32, "LinuxAuxv", true),
+    (MINIDUMP_STREAM_TYPE::LinuxMaps as u32, "LinuxMaps", true),
+    (
+        MINIDUMP_STREAM_TYPE::LinuxDsoDebug as u32,
+        "LinuxDsoDebug",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::CrashpadInfoStream as u32,
+        "CrashpadInfoStream",
+        true,
+    ),
+    (
+        MINIDUMP_STREAM_TYPE::MozMacosCrashInfoStream as u32,
+        "MozMacosCrashInfoStream",
+        true,
+    ),
+    // Mozilla's streams which the minidump crate doesn't define a type for.
+    (0x4d7a_0002, "MozMacosBootargsStream", false),
+    (0x4d7a_0003, "MozLinuxLimits", false),
+    (0x4d7a_0004, "MozSoftErrors", false),
+];
+
+/// The name of a stream type, e.g. `ThreadListStream` or `LinuxMaps`.
+pub fn stream_type_name(stream_type: u32) -> Option<&'static str> {
+    STREAM_TYPES
+        .iter()
+        .find(|(known_stream_type, _, _)| *known_stream_type == stream_type)
+        .map(|(_, name, _)| *name)
+}
+
+/// The stream types which [`print_stream`] can print in a human-readable form, with their names.
+pub fn printable_stream_types() -> impl Iterator<Item = (u32, &'static str)> {
+    STREAM_TYPES
+        .iter()
+        .filter(|(_, _, printable)| *printable)
+        .map(|(stream_type, name, _)| (*stream_type, *name))
+}
+
+/// Print a human-readable description of a stream, in the same format as `minidump-dump`.
+///
+/// Streams which the minidump crate can't parse, or which it doesn't know about,
+/// are printed as a hexdump of the stream's data. The already parsed `MinidumpSystemInfo` and
+/// `MinidumpMiscInfo` streams are needed to print thread contexts.
+pub fn print_stream<'a, T, W>(
+    minidump_obj: &'a Minidump<'a, T>,
+    dump_data: &[u8],
+    stream: &StreamDirectoryEntry,
+    system_info: Option<&MinidumpSystemInfo>,
+    misc_info: Option<&MinidumpMiscInfo>,
+    is_64bit: bool,
+    f: &mut W,
+) -> io::Result<()>
+where
+    T: Deref<Target = [u8]> + 'a,
+    W: Write,
+{
+    let stream_name = stream_type_name(stream.stream_type);
+    writeln!(
+        f,
+        "Stream {:#x} ({}) at offset {:#x}, {:#x} bytes",
+        stream.stream_type,
+        stream_name.unwrap_or("unknown"),
+        stream.rva,
+        stream.data_size
+    )?;
+    let stream_data = dump_data
+        .get(stream.rva as usize..)
+        .and_then(|data| data.get(..stream.data_size as usize));
+
+    let parse_result = match stream_name {
+        Some("ThreadListStream") => minidump_obj
+            .get_stream::<MinidumpThreadList>()
+            .map(|stream| stream.print(f, None, system_info, misc_info, false)),
+        Some("ModuleListStream") => minidump_obj
+            .get_stream::<MinidumpModuleList>()
+            .map(|stream| stream.print(f)),
+        Some("MemoryListStream") => minidump_obj
+            .get_stream::<MinidumpMemoryList>()
+            .map(|stream| stream.print(f, false)),
+        Some("ExceptionStream") => minidump_obj
+            .get_stream::<MinidumpException>()
+            .map(|stream| stream.print(f, system_info, misc_info)),
+        Some("SystemInfoStream") => minidump_obj
+            .get_stream::<MinidumpSystemInfo>()
+            .map(|stream| stream.print(f)),
+        Some("Memory64ListStream") => minidump_obj
+            .get_stream::<MinidumpMemory64List>()
+            .map(|stream| stream.print(f, false)),
+        Some("UnloadedModuleListStream") => minidump_obj
+            .get_stream::<MinidumpUnloadedModuleList>()
+            .map(|stream| stream.print(f)),
+        Some("MiscInfoStream") => minidump_obj
+            .get_stream::<MinidumpMiscInfo>()
+            .map(|stream| stream.print(f)),
+        Some("MemoryInfoListStream") => minidump_obj
+            .get_stream::<MinidumpMemoryInfoList>()
+            .map(|stream| stream.print(f)),
+        Some("ThreadNamesStream") => minidump_obj
+            .get_stream::<MinidumpThreadNames>()
+            .map(|stream| stream.print(f)),
+        Some("BreakpadInfoStream") => minidump_obj
+            .get_stream::<MinidumpBreakpadInfo>()
+            .map(|stream| stream.print(f)),
+        Some("AssertionInfoStream") => minidump_obj
+            .get_stream::<MinidumpAssertion>()
+            .map(|stream| stream.print(f)),
+        Some("LinuxCpuInfo") => minidump_obj
+            .get_stream::<MinidumpLinuxCpuInfo>()
+            .map(|stream| print_text_stream(&stream.raw_bytes(), f)),
+        Some("LinuxProcStatus") => minidump_obj
+            .get_stream::<MinidumpLinuxProcStatus>()
+            .map(|stream| print_text_stream(&stream.raw_bytes(), f)),
+        Some("LinuxLsbRelease") => minidump_obj
+            .get_stream::<MinidumpLinuxLsbRelease>()
+            .map(|stream| print_text_stream(&stream.raw_bytes(), f)),
+        Some("LinuxEnviron") => minidump_obj
+            .get_stream::<MinidumpLinuxEnviron>()
+            .map(|stream| print_text_stream(&stream.raw_bytes(), f)),
+        Some("LinuxMaps") => minidump_obj
+            .get_stream::<MinidumpLinuxMaps>()
+            .map(|stream| stream.print(f)),
+        Some("CrashpadInfoStream") => minidump_obj
+            .get_stream::<MinidumpCrashpadInfo>()
+            .map(|stream| stream.print(f)),
+        Some("MozMacosCrashInfoStream") => minidump_obj
+            .get_stream::<MinidumpMacCrashInfo>()
+            .map(|stream| stream.print(f)),
+        // The minidump crate has no types for these streams, so print what was read from them instead.
+        Some(name @ ("LinuxCmdLine" | "LinuxAuxv" | "LinuxDsoDebug")) => {
+            return match LinuxProcessInfo::read(minidump_obj, dump_data, is_64bit) {
+                Some(linux_info) => print_linux_process_info(name, &linux_info, f),
+                None => print_hexdump(stream_data, f),
+            };
+        }
+        _ => return print_hexdump(stream_data, f),
+    };
+
+    match parse_result {
+        Ok(print_result) => print_result,
+        Err(error) => {
+            writeln!(f, "Could not parse the stream: {}", error)?;
+            print_hexdump(stream_data, f)
+        }
+    }
+}
+
+/// Print the part of the Linux process information which was read from one of the Linux streams
+/// that the minidump crate has no type for.
+fn print_linux_process_info<W: Write>(
+    stream_name: &str,
+    linux_info: &LinuxProcessInfo,
+    f: &mut W,
+) -> io::Result<()> {
+    match stream_name {
+        "LinuxCmdLine" => {
+            for (index, argument) in linux_info.command_line.iter().enumerate() {
+                writeln!(f, "  argv[{}] = {:?}", index, argument)?;
+            }
+        }
+        "LinuxAuxv" => {
+            for (auxv_type, auxv_value) in linux_info.auxv.iter() {
+                writeln!(f, "  {:#x} = {:#x}", auxv_type, auxv_value)?;
+            }
+        }
+        _ => {
+            for link_map_entry in linux_info.link_map.iter() {
+                writeln!(
+                    f,
+                    "  l_addr = {:#x}, l_ld = {:#x}, l_name = {:?}",
+                    link_map_entry.load_bias, link_map_entry.dynamic, link_map_entry.name
+                )?;
+            }
+        }
+    }
+    writeln!(f)
+}
+
+/// Print the contents of one of the Linux streams which are copies of text files from `/proc`
+/// or `/etc` (e.g. `/proc/cpuinfo`), which the minidump crate has no printer for.
+fn print_text_stream<W: Write>(stream_data: &[u8], f: &mut W) -> io::Result<()> {
+    for line in String::from_utf8_lossy(stream_data).lines() {
+        writeln!(f, "  {}", line)?;
+    }
+    writeln!(f)
+}
+
+/// Print the data of a stream as a hexdump, with 16 bytes per line.
+fn print_hexdump<W: Write>(stream_data: Option<&[u8]>, f: &mut W) -> io::Result<()> {
+    let Some(stream_data) = stream_data else {
+        return writeln!(f, "The stream's data is outside of the minidump file\n");
+    };
+    for (line_index, line) in stream_data.chunks(16).enumerate() {
+        write!(f, "  {:08x}: ", line_index * 16)?;
+        for column in 0..16 {
+            match line.get(column) {
+                Some(byte) => write!(f, "{:02x} ", byte)?,
+                None => write!(f, "   ")?,
+            }
+        }
+        let text: String = line
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        writeln!(f, " {}", text)?;
+    }
+    writeln!(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn stream_types_are_listed_once() {
+        let stream_types: HashSet<u32> = STREAM_TYPES
+            .iter()
+            .map(|(stream_type, _, _)| *stream_type)
+            .collect();
+        let names: HashSet<&str> = STREAM_TYPES.iter().map(|(_, name, _)| *name).collect();
+        assert_eq!(stream_types.len(), STREAM_TYPES.len());
+        assert_eq!(names.len(), STREAM_TYPES.len());
+    }
+
+    #[test]
+    fn names_stream_types() {
+        assert_eq!(stream_type_name(3), Some("ThreadListStream"));
+        assert_eq!(stream_type_name(0x4767_0009), Some("LinuxMaps"));
+        assert_eq!(stream_type_name(0x4d7a_0004), Some("MozSoftErrors"));
+        assert_eq!(stream_type_name(0x1234_5678), None);
+    }
+
+    #[test]
+    fn printable_stream_types_have_printers() {
+        let printable: Vec<(u32, &str)> = printable_stream_types().collect();
+        assert!(printable.contains(&(3, "ThreadListStream")));
+        assert!(printable.contains(&(0x4767_0003, "LinuxCpuInfo")));
+        // The minidump crate has no parser for handle data, so it is printed as a hexdump.
+        assert!(!printable
+            .iter()
+            .any(|(_, name)| *name == "HandleDataStream"));
+    }
+
+    #[test]
+    fn prints_hexdump_of_unknown_data() {
+        let mut output = Vec::new();
+        print_hexdump(Some(b"ABCDEFGHIJKLMNOPQ"), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "  00000000: 41 42 43 44 45 46 47 48 49 4a 4b 4c 4d 4e 4f 50  ABCDEFGHIJKLMNOP"
+        );
+        assert!(lines[1].starts_with("  00000010: 51 "));
+        assert!(lines[1].ends_with(" Q"));
+    }
+
+    #[test]
+    fn prints_text_streams_line_by_line() {
+        let mut output = Vec::new();
+        print_text_stream(b"processor\t: 0\nmodel name\t: Test CPU\n", &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "  processor\t: 0\n  model name\t: Test CPU\n\n"
+        );
+    }
+}
@@ -0,0 +1,950 @@
+use std::fmt;
+use std::ops::Range;
+
+use log::debug;
+use minidump::{
+    MinidumpContext, MinidumpException, MinidumpMiscInfo, MinidumpModuleList, MinidumpRawContext,
+    MinidumpSystemInfo, MinidumpThreadList, Module,
+};
+
+use crate::memory::DumpMemory;
+use crate::pe::{PeHeaders, RuntimeFunction, IMAGE_DIRECTORY_ENTRY_EXCEPTION};
+
+/// An upper bound on the number of frames in a call stack, in case unwinding goes around in circles.
+const MAX_FRAMES: usize = 256;
+/// The number of pointer-sized stack slots to search for a return address when scanning the stack.
+/// Four times as many slots are searched for the caller of the innermost frame,
+/// since the innermost function may not have finished setting up its frame.
+const MAX_SCAN_SLOTS: u64 = 40;
+/// An upper bound on the length of a chain of x64 unwind info structures.
+const MAX_CHAINED_UNWIND_INFO: usize = 32;
+
+// x64 unwind operation codes (UNWIND_CODE.UnwindOp)
+const UWOP_PUSH_NONVOL: u8 = 0;
+const UWOP_ALLOC_LARGE: u8 = 1;
+const UWOP_ALLOC_SMALL: u8 = 2;
+const UWOP_SET_FPREG: u8 = 3;
+const UWOP_SAVE_NONVOL: u8 = 4;
+const UWOP_SAVE_NONVOL_FAR: u8 = 5;
+const UWOP_EPILOG: u8 = 6;
+const UWOP_SAVE_XMM128: u8 = 8;
+const UWOP_SAVE_XMM128_FAR: u8 = 9;
+const UWOP_PUSH_MACHFRAME: u8 = 10;
+
+const UNW_FLAG_CHAININFO: u8 = 0x4;
+
+/// An upper bound on the number of `pop` instructions in an x64 epilog.
+const MAX_EPILOG_POPS: usize = 16;
+
+// The x64 integer registers, numbered the same way as in x64 unwind codes
+const RSP: usize = 4;
+const RBP: usize = 5;
+
+/// How the caller of a frame was found, from most to least reliable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameTrust {
+    /// The innermost frame, from the thread's `CONTEXT`.
+    Context,
+    /// Unwound with call frame information (the x64 function tables of PE modules).
+    CallFrameInfo,
+    /// Unwound by following the chain of saved frame pointers.
+    FramePointer,
+    /// The return address is in the link register of the innermost frame (ARM and AArch64 leaf functions).
+    LinkRegister,
+    /// Found by searching the stack for something which looks like a return address.
+    Scan,
+}
+
+impl fmt::Display for FrameTrust {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            FrameTrust::Context => "context",
+            FrameTrust::CallFrameInfo => "call frame info",
+            FrameTrust::FramePointer => "frame pointer",
+            FrameTrust::LinkRegister => "link register",
+            FrameTrust::Scan => "stack scanning",
+        };
+        f.write_str(description)
+    }
+}
+
+/// A frame in a thread's call stack.
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    /// The instruction pointer of the innermost frame, or the return address of every other frame.
+    pub instruction: u64,
+    pub stack_pointer: u64,
+    pub trust: FrameTrust,
+}
+
+/// The call stack of a thread, innermost frame first.
+#[derive(Debug)]
+pub struct ThreadStack {
+    pub thread_id: u32,
+    /// Whether this is the thread in the `MinidumpException` stream,
+    /// in which case the call stack is unwound from the context of the exception.
+    pub is_crashing_thread: bool,
+    pub frames: Vec<StackFrame>,
+}
+
+/// The registers which are needed to unwind a frame.
+#[derive(Debug, Clone)]
+struct Registers {
+    instruction_pointer: u64,
+    stack_pointer: u64,
+    frame_pointer: Option<u64>,
+    link_register: Option<u64>,
+    /// All 16 integer registers, on x64, since x64 unwind info can restore any of them.
+    x64_registers: Option<[u64; 16]>,
+}
+
+impl Registers {
+    fn from_context(context: &MinidumpContext) -> Self {
+        if let MinidumpRawContext::Amd64(raw_context) = &context.raw {
+            let x64_registers = [
+                raw_context.rax,
+                raw_context.rcx,
+                raw_context.rdx,
+                raw_context.rbx,
+                raw_context.rsp,
+                raw_context.rbp,
+                raw_context.rsi,
+                raw_context.rdi,
+                raw_context.r8,
+                raw_context.r9,
+                raw_context.r10,
+                raw_context.r11,
+                raw_context.r12,
+                raw_context.r13,
+                raw_context.r14,
+                raw_context.r15,
+            ];
+            return Registers {
+                instruction_pointer: raw_context.rip,
+                stack_pointer: raw_context.rsp,
+                frame_pointer: Some(raw_context.rbp),
+                link_register: None,
+                x64_registers: Some(x64_registers),
+            };
+        }
+
+        let (frame_pointer_name, link_register_name) = match &context.raw {
+            MinidumpRawContext::X86(_) => ("ebp", None),
+            MinidumpRawContext::Arm(_) => ("r11", Some("lr")),
+            MinidumpRawContext::Arm64(_) | MinidumpRawContext::OldArm64(_) => ("fp", Some("lr")),
+            _ => ("fp", None),
+        };
+        Registers {
+            instruction_pointer: context.get_instruction_pointer(),
+            stack_pointer: context.get_stack_pointer(),
+            frame_pointer: context.get_register(frame_pointer_name),
+            link_register: link_register_name.and_then(|name| context.get_register(name)),
+            x64_registers: None,
+        }
+    }
+
+    /// The registers of a caller whose instruction pointer and stack pointer have been found,
+    /// keeping the rest of the registers of the callee.
+    fn with_caller(
+        &self,
+        instruction_pointer: u64,
+        stack_pointer: u64,
+        frame_pointer: Option<u64>,
+    ) -> Self {
+        let mut x64_registers = self.x64_registers;
+        if let Some(x64_registers) = x64_registers.as_mut() {
+            x64_registers[RSP] = stack_pointer;
+            if let Some(frame_pointer) = frame_pointer {
+                x64_registers[RBP] = frame_pointer;
+            }
+        }
+        Registers {
+            instruction_pointer,
+            stack_pointer,
+            frame_pointer: frame_pointer.or(self.frame_pointer),
+            link_register: None,
+            x64_registers,
+        }
+    }
+}
+
+struct WalkerModule {
+    address_range: Range<u64>,
+    pe_headers: Option<PeHeaders>,
+}
+
+/// Unwinds the call stacks of the threads in a minidump, using the stack memory and module headers
+/// captured in the minidump.
+///
+/// For x64 modules with a function table (i.e. x64 PE modules), the unwind info in the `.pdata`
+/// and `.xdata` sections is used. Otherwise, the chain of saved frame pointers is followed,
+/// falling back to searching the stack for return addresses.
+pub struct StackWalker<'a> {
+    memory: &'a DumpMemory<'a>,
+    is_64bit: bool,
+    modules: Vec<WalkerModule>,
+}
+
+impl<'a> StackWalker<'a> {
+    pub fn new(
+        memory: &'a DumpMemory<'a>,
+        is_64bit: bool,
+        module_list: Option<&MinidumpModuleList>,
+    ) -> Self {
+        let mut modules: Vec<WalkerModule> = module_list
+            .map(|module_list| {
+                module_list
+                    .by_addr()
+                    .map(|module_info| WalkerModule {
+                        address_range: module_info.base_address()
+                            ..module_info
+                                .base_address()
+                                .saturating_add(module_info.size()),
+                        pe_headers: PeHeaders::read(memory, module_info.base_address()),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        modules.sort_by_key(|module| module.address_range.start);
+        StackWalker {
+            memory,
+            is_64bit,
+            modules,
+        }
+    }
+
+    fn pointer_size(&self) -> u64 {
+        if self.is_64bit {
+            8
+        } else {
+            4
+        }
+    }
+
+    fn module_containing(&self, address: u64) -> Option<&WalkerModule> {
+        let index = self
+            .modules
+            .partition_point(|module| module.address_range.start <= address)
+            .checked_sub(1)?;
+        let module = &self.modules[index];
+        module.address_range.contains(&address).then_some(module)
+    }
+
+    /// Whether an address could be a return address: it must be inside a module,
+    /// and inside one of the module's code sections if the module's section table is known.
+    fn is_code_address(&self, address: u64) -> bool {
+        let Some(module) = self.module_containing(address) else {
+            return false;
+        };
+        match &module.pe_headers {
+            Some(pe_headers) if !pe_headers.sections.is_empty() => {
+                pe_headers.sections.iter().any(|pe_section| {
+                    let section_start = pe_headers
+                        .base_address
+                        .saturating_add(pe_section.virtual_address as u64);
+                    let section_end = section_start.saturating_add(pe_section.mapped_size() as u64);
+                    pe_section.is_code() && (section_start..section_end).contains(&address)
+                })
+            }
+            _ => true,
+        }
+    }
+
+    /// Unwind the call stack of every thread in a `MinidumpThreadList` stream, or with
+    /// `only_crashing_thread`, only the call stack of the thread in the `MinidumpException` stream.
+    ///
+    /// The call stack of the crashing thread starts from the context in the `MinidumpException` stream,
+    /// rather than from the thread's own context, which is inside the exception handler.
+    pub fn walk_threads(
+        &self,
+        minidump_thread_list: &MinidumpThreadList,
+        minidump_exception: Option<&MinidumpException>,
+        minidump_system_info: &MinidumpSystemInfo,
+        minidump_misc_info: Option<&MinidumpMiscInfo>,
+        only_crashing_thread: bool,
+    ) -> Vec<ThreadStack> {
+        let mut thread_stacks = Vec::new();
+        for thread in minidump_thread_list.threads.iter() {
+            let thread_id = thread.raw.thread_id;
+            let exception_context = minidump_exception
+                .filter(|exception| exception.raw.thread_id == thread_id)
+                .and_then(|exception| exception.context(minidump_system_info, minidump_misc_info));
+            let is_crashing_thread = exception_context.is_some();
+            if only_crashing_thread && !is_crashing_thread {
+                continue;
+            }
+            let Some(context) = exception_context
+                .or_else(|| thread.context(minidump_system_info, minidump_misc_info))
+            else {
+                debug!("Could not find a valid CONTEXT for thread {:#x}", thread_id);
+                continue;
+            };
+            thread_stacks.push(ThreadStack {
+                thread_id,
+                is_crashing_thread,
+                frames: self.walk(Registers::from_context(&context)),
+            });
+        }
+        thread_stacks
+    }
+
+    fn walk(&self, registers: Registers) -> Vec<StackFrame> {
+        let mut frames = vec![StackFrame {
+            instruction: registers.instruction_pointer,
+            stack_pointer: registers.stack_pointer,
+            trust: FrameTrust::Context,
+        }];
+        let mut registers = registers;
+
+        while frames.len() < MAX_FRAMES {
+            let is_innermost_frame = frames.len() == 1;
+            let Some((caller_registers, trust)) = self.unwind_frame(&registers, is_innermost_frame)
+            else {
+                break;
+            };
+            frames.push(StackFrame {
+                instruction: caller_registers.instruction_pointer,
+                stack_pointer: caller_registers.stack_pointer,
+                trust,
+            });
+            registers = caller_registers;
+        }
+        frames
+    }
+
+    /// Find the caller of a frame, trying each unwinding method from most to least reliable.
+    fn unwind_frame(
+        &self,
+        registers: &Registers,
+        is_innermost_frame: bool,
+    ) -> Option<(Registers, FrameTrust)> {
+        let is_valid_caller = |caller_registers: &Registers, trust: FrameTrust| {
+            // The stack grows downwards, so every caller's frame is above its callee's,
+            // except when a leaf function hasn't pushed anything onto the stack.
+            let is_above_callee = caller_registers.stack_pointer > registers.stack_pointer
+                || (trust == FrameTrust::LinkRegister
+                    && caller_registers.stack_pointer == registers.stack_pointer);
+            is_above_callee && self.is_code_address(caller_registers.instruction_pointer)
+        };
+
+        // Call frame information is authoritative: if it exists for the frame,
+        // a caller which doesn't make sense means that the end of the stack has been reached.
+        if let Some(caller_registers) = self.unwind_x64_cfi(registers, is_innermost_frame) {
+            return is_valid_caller(&caller_registers, FrameTrust::CallFrameInfo)
+                .then_some((caller_registers, FrameTrust::CallFrameInfo));
+        }
+        if let Some(caller_registers) = self.unwind_frame_pointer(registers) {
+            if is_valid_caller(&caller_registers, FrameTrust::FramePointer) {
+                return Some((caller_registers, FrameTrust::FramePointer));
+            }
+        }
+        if is_innermost_frame {
+            if let Some(link_register) = registers.link_register {
+                let caller_registers =
+                    registers.with_caller(link_register, registers.stack_pointer, None);
+                if is_valid_caller(&caller_registers, FrameTrust::LinkRegister) {
+                    return Some((caller_registers, FrameTrust::LinkRegister));
+                }
+            }
+        }
+        self.unwind_scan(registers, is_innermost_frame)
+            .filter(|caller_registers| is_valid_caller(caller_registers, FrameTrust::Scan))
+            .map(|caller_registers| (caller_registers, FrameTrust::Scan))
+    }
+
+    /// Unwind a frame by following its saved frame pointer, assuming the usual frame layout
+    /// where the frame pointer points at the caller's saved frame pointer, followed by the return address.
+    fn unwind_frame_pointer(&self, registers: &Registers) -> Option<Registers> {
+        let frame_pointer = registers.frame_pointer?;
+        if frame_pointer < registers.stack_pointer {
+            return None;
+        }
+        let pointer_size = self.pointer_size();
+        let caller_frame_pointer = self.memory.read_pointer(frame_pointer, self.is_64bit)?;
+        let return_address = self
+            .memory
+            .read_pointer(frame_pointer.checked_add(pointer_size)?, self.is_64bit)?;
+        Some(registers.with_caller(
+            return_address,
+            frame_pointer.checked_add(2 * pointer_size)?,
+            Some(caller_frame_pointer),
+        ))
+    }
+
+    /// Unwind a frame by searching the stack for a value which points into the code of a module.
+    fn unwind_scan(&self, registers: &Registers, is_innermost_frame: bool) -> Option<Registers> {
+        let pointer_size = self.pointer_size();
+        let scan_slots = if is_innermost_frame {
+            MAX_SCAN_SLOTS * 4
+        } else {
+            MAX_SCAN_SLOTS
+        };
+        for slot in 0..scan_slots {
+            let slot_address = registers.stack_pointer.checked_add(slot * pointer_size)?;
+            let value = self.memory.read_pointer(slot_address, self.is_64bit)?;
+            if self.is_code_address(value) {
+                return Some(registers.with_caller(
+                    value,
+                    slot_address.checked_add(pointer_size)?,
+                    None,
+                ));
+            }
+        }
+        None
+    }
+
+    /// Unwind a frame in an x64 PE module, using the unwind info for the function in the
+    /// module's function table. Returns `None` if the frame isn't in a module with a function table.
+    ///
+    /// If the thread was stopped in the middle of a function's epilog, the epilog has already undone
+    /// part of the prolog, so the rest of the epilog is emulated instead of applying the unwind info.
+    fn unwind_x64_cfi(&self, registers: &Registers, is_innermost_frame: bool) -> Option<Registers> {
+        let mut x64_registers = registers.x64_registers?;
+        let pe_headers = self
+            .module_containing(registers.instruction_pointer)?
+            .pe_headers
+            .as_ref()?;
+        pe_headers.data_directory(IMAGE_DIRECTORY_ENTRY_EXCEPTION)?;
+
+        // Return addresses point just after the call instruction, which may be past the end
+        // of the calling function if the called function never returns.
+        let lookup_address = if is_innermost_frame {
+            registers.instruction_pointer
+        } else {
+            registers.instruction_pointer.checked_sub(1)?
+        };
+        // Only the innermost frame can be in an epilog: every other frame is stopped at a call.
+        let in_epilog = is_innermost_frame
+            && self.unwind_x64_epilog(&mut x64_registers, registers.instruction_pointer);
+        let runtime_function = if in_epilog {
+            None
+        } else {
+            pe_headers.runtime_function(self.memory, lookup_address)
+        };
+        if let Some(runtime_function) = runtime_function {
+            let offset_in_function = lookup_address - runtime_function.begin_address;
+            if let Some(machine_frame) = self.apply_x64_unwind_info(
+                &mut x64_registers,
+                runtime_function,
+                offset_in_function,
+                pe_headers.base_address,
+            )? {
+                // An interrupt or exception frame, which holds the interrupted instruction pointer
+                return Some(Registers {
+                    instruction_pointer: machine_frame,
+                    stack_pointer: x64_registers[RSP],
+                    frame_pointer: Some(x64_registers[RBP]),
+                    link_register: None,
+                    x64_registers: Some(x64_registers),
+                });
+            }
+        }
+
+        // The return address is now at the top of the stack. This is also the case for
+        // leaf functions, which have no entry in the function table.
+        let return_address = self.memory.read_u64(x64_registers[RSP])?;
+        x64_registers[RSP] = x64_registers[RSP].wrapping_add(8);
+        Some(Registers {
+            instruction_pointer: return_address,
+            stack_pointer: x64_registers[RSP],
+            frame_pointer: Some(x64_registers[RBP]),
+            link_register: None,
+            x64_registers: Some(x64_registers),
+        })
+    }
+
+    /// Check whether an instruction pointer is in an x64 epilog, and if it is, emulate the rest of
+    /// the epilog up to its `ret`, leaving the return address at the top of the stack.
+    ///
+    /// The x64 calling convention only allows epilogs of a fixed form, which is what Windows itself
+    /// recognizes when unwinding: an optional `add rsp, <constant>` or `lea rsp, [<frame register>
+    /// + <offset>]`, followed by any number of `pop <register>`, followed by `ret`.
+    fn unwind_x64_epilog(&self, x64_registers: &mut [u64; 16], instruction_pointer: u64) -> bool {
+        let byte = |offset: u64| -> Option<u8> {
+            let address = instruction_pointer.checked_add(offset)?;
+            self.memory.read(address, 1).map(|bytes| bytes[0])
+        };
+        let i32_at = |offset: u64| -> Option<i64> {
+            let address = instruction_pointer.checked_add(offset)?;
+            Some(self.memory.read_u32(address)? as i32 as i64)
+        };
+
+        let mut registers = *x64_registers;
+        let mut offset = 0;
+
+        // The stack deallocation, which can only be the first instruction of the epilog
+        match (byte(0), byte(1), byte(2)) {
+            // add rsp, imm8
+            (Some(0x48), Some(0x83), Some(0xc4)) => {
+                let Some(immediate) = byte(3) else {
+                    return false;
+                };
+                registers[RSP] = registers[RSP].wrapping_add_signed(immediate as i8 as i64);
+                offset = 4;
+            }
+            // add rsp, imm32
+            (Some(0x48), Some(0x81), Some(0xc4)) => {
+                let Some(immediate) = i32_at(3) else {
+                    return false;
+                };
+                registers[RSP] = registers[RSP].wrapping_add_signed(immediate);
+                offset = 7;
+            }
+            // lea rsp, [reg + disp8] or lea rsp, [reg + disp32], where the base register isn't rsp
+            // or r12 (which would need a SIB byte)
+            (Some(rex @ (0x48 | 0x49)), Some(0x8d), Some(modrm))
+                if (modrm >> 3) & 0x7 == RSP as u8 && modrm & 0x7 != RSP as u8 =>
+            {
+                let base_register = (modrm & 0x7) as usize | ((rex as usize & 0x1) << 3);
+                let displacement = match modrm >> 6 {
+                    1 => byte(3).map(|displacement| (displacement as i8 as i64, 4)),
+                    2 => i32_at(3).map(|displacement| (displacement, 7)),
+                    _ => None,
+                };
+                let Some((displacement, instruction_length)) = displacement else {
+                    return false;
+                };
+                registers[RSP] = registers[base_register].wrapping_add_signed(displacement);
+                offset = instruction_length;
+            }
+            _ => {}
+        }
+
+        for _ in 0..=MAX_EPILOG_POPS {
+            let (register, instruction_length) = match (byte(offset), byte(offset + 1)) {
+                // pop r8 - pop r15
+                (Some(0x41), Some(opcode @ 0x58..=0x5f)) => (8 + (opcode - 0x58) as usize, 2),
+                // pop rax - pop rdi
+                (Some(opcode @ 0x58..=0x5f), _) => ((opcode - 0x58) as usize, 1),
+                // ret, rep ret, or ret imm16
+                (Some(0xc3), _) | (Some(0xf3), Some(0xc3)) | (Some(0xc2), _) => {
+                    *x64_registers = registers;
+                    return true;
+                }
+                _ => return false,
+            };
+            let Some(value) = self.memory.read_u64(registers[RSP]) else {
+                return false;
+            };
+            registers[register] = value;
+            registers[RSP] = registers[RSP].wrapping_add(8);
+            offset += instruction_length;
+        }
+        false
+    }
+
+    /// Undo the effects of a function's prolog on the registers, following the unwind codes
+    /// in its `UNWIND_INFO` and in any chained `UNWIND_INFO`.
+    ///
+    /// Returns the interrupted instruction pointer if the function's frame is a machine frame
+    /// (`UWOP_PUSH_MACHFRAME`), or `None` if the return address is left at the top of the stack.
+    fn apply_x64_unwind_info(
+        &self,
+        x64_registers: &mut [u64; 16],
+        runtime_function: RuntimeFunction,
+        offset_in_function: u64,
+        image_base: u64,
+    ) -> Option<Option<u64>> {
+        let mut runtime_function = runtime_function;
+        // The unwind codes of chained unwind info describe a prolog which has always finished running
+        let mut offset_in_prolog = Some(offset_in_function);
+
+        for _ in 0..MAX_CHAINED_UNWIND_INFO {
+            let unwind_info_address = runtime_function.unwind_info_address;
+            let header = self.memory.read(unwind_info_address, 4)?;
+            let version = header[0] & 0x7;
+            let flags = header[0] >> 3;
+            let code_count = header[2] as usize;
+            let frame_register = (header[3] & 0xf) as usize;
+            let frame_offset = (header[3] >> 4) as u64 * 16;
+            let codes = self
+                .memory
+                .read(unwind_info_address.checked_add(4)?, code_count * 2)?;
+            let slot = |index: usize| -> Option<u64> {
+                Some(
+                    u16::from_le_bytes(codes.get(index * 2..index * 2 + 2)?.try_into().ok()?)
+                        as u64,
+                )
+            };
+            let is_executed = |code_offset: u8| {
+                offset_in_prolog.is_none_or(|offset| code_offset as u64 <= offset)
+            };
+
+            // Saved registers are addressed relative to the frame register, once it has been set.
+            // The operands of other codes can look like UWOP_SET_FPREG, so they are skipped.
+            let mut frame_pointer_is_set = false;
+            let mut index = 0;
+            while frame_register != 0 && index < code_count {
+                let operation = codes[index * 2 + 1] & 0xf;
+                if operation == UWOP_SET_FPREG && is_executed(codes[index * 2]) {
+                    frame_pointer_is_set = true;
+                    break;
+                }
+                let Some(slot_count) =
+                    unwind_code_slot_count(operation, (codes[index * 2 + 1] >> 4) as usize)
+                else {
+                    break;
+                };
+                index += slot_count;
+            }
+            let frame_base = if frame_pointer_is_set {
+                x64_registers[frame_register].wrapping_sub(frame_offset)
+            } else {
+                x64_registers[RSP]
+            };
+
+            let mut index = 0;
+            while index < code_count {
+                let code_offset = codes[index * 2];
+                let operation = codes[index * 2 + 1] & 0xf;
+                let operation_info = (codes[index * 2 + 1] >> 4) as usize;
+                let Some(slot_count) = unwind_code_slot_count(operation, operation_info) else {
+                    debug!(
+                        "Unknown x64 unwind operation {} in unwind info version {} at {:#x}",
+                        operation, version, unwind_info_address
+                    );
+                    return None;
+                };
+                if is_executed(code_offset) {
+                    match operation {
+                        UWOP_PUSH_NONVOL => {
+                            x64_registers[operation_info] =
+                                self.memory.read_u64(x64_registers[RSP])?;
+                            x64_registers[RSP] = x64_registers[RSP].wrapping_add(8);
+                        }
+                        UWOP_ALLOC_LARGE => {
+                            let size = if operation_info == 0 {
+                                slot(index + 1)? * 8
+                            } else {
+                                slot(index + 1)? | (slot(index + 2)? << 16)
+                            };
+                            x64_registers[RSP] = x64_registers[RSP].wrapping_add(size);
+                        }
+                        UWOP_ALLOC_SMALL => {
+                            x64_registers[RSP] =
+                                x64_registers[RSP].wrapping_add(operation_info as u64 * 8 + 8);
+                        }
+                        UWOP_SET_FPREG => {
+                            x64_registers[RSP] = frame_base;
+                        }
+                        UWOP_SAVE_NONVOL => {
+                            x64_registers[operation_info] = self
+                                .memory
+                                .read_u64(frame_base.wrapping_add(slot(index + 1)? * 8))?;
+                        }
+                        UWOP_SAVE_NONVOL_FAR => {
+                            let offset = slot(index + 1)? | (slot(index + 2)? << 16);
+                            x64_registers[operation_info] =
+                                self.memory.read_u64(frame_base.wrapping_add(offset))?;
+                        }
+                        UWOP_PUSH_MACHFRAME => {
+                            // With an error code pushed on top of the machine frame
+                            if operation_info != 0 {
+                                x64_registers[RSP] = x64_registers[RSP].wrapping_add(8);
+                            }
+                            let interrupted_instruction_pointer =
+                                self.memory.read_u64(x64_registers[RSP])?;
+                            x64_registers[RSP] =
+                                self.memory.read_u64(x64_registers[RSP].wrapping_add(24))?;
+                            return Some(Some(interrupted_instruction_pointer));
+                        }
+                        // XMM registers aren't needed to unwind, and epilog codes only describe
+                        // where the epilogs are
+                        _ => {}
+                    }
+                }
+                index += slot_count;
+            }
+
+            if flags & UNW_FLAG_CHAININFO == 0 {
+                return Some(None);
+            }
+            // The chained RUNTIME_FUNCTION follows the unwind codes, which are padded to an even count
+            let chained_function_address =
+                unwind_info_address.checked_add(4 + (code_count as u64).next_multiple_of(2) * 2)?;
+            runtime_function =
+                RuntimeFunction::read(self.memory, chained_function_address, image_base)?;
+            offset_in_prolog = None;
+        }
+        None
+    }
+}
+
+/// The number of slots which an x64 unwind code takes up in the array of unwind codes, including
+/// the slots holding its operands, or `None` if the operation is unknown.
+fn unwind_code_slot_count(operation: u8, operation_info: usize) -> Option<usize> {
+    match operation {
+        UWOP_PUSH_NONVOL | UWOP_ALLOC_SMALL | UWOP_SET_FPREG | UWOP_PUSH_MACHFRAME => Some(1),
+        UWOP_ALLOC_LARGE if operation_info == 0 => Some(2),
+        UWOP_ALLOC_LARGE => Some(3),
+        UWOP_SAVE_NONVOL | UWOP_EPILOG | UWOP_SAVE_XMM128 => Some(2),
+        UWOP_SAVE_NONVOL_FAR | UWOP_SAVE_XMM128_FAR => Some(3),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pe::tests::{pe_image, IMAGE_BASE};
+
+    const STACK_BASE: u64 = 0x0000_00a0_0000_1000;
+    const RBX: usize = 3;
+
+    /// The `UNWIND_INFO` of the function at RVA 0x1000, which has no prolog.
+    const NO_PROLOG: &[u8] = &[0x01, 0x00, 0x00, 0x00];
+    /// The `UNWIND_INFO` of the function at RVA 0x1010, whose prolog is `push rbx` (1 byte)
+    /// followed by `sub rsp, 0x20` (4 bytes).
+    const PUSH_RBX_ALLOC_0X20: &[u8] = &[
+        0x01,
+        0x05,
+        0x02,
+        0x00,
+        // sub rsp, 0x20 ends at offset 5: UWOP_ALLOC_SMALL of (0x20 - 8) / 8 = 3
+        0x05,
+        UWOP_ALLOC_SMALL | (3 << 4),
+        // push rbx ends at offset 1
+        0x01,
+        UWOP_PUSH_NONVOL | ((RBX as u8) << 4),
+    ];
+    /// The epilog of the function at RVA 0x1010: `add rsp, 0x20`, `pop rbx`, `ret`.
+    const EPILOG: &[u8] = &[0x48, 0x83, 0xc4, 0x20, 0x5b, 0xc3];
+
+    fn stack(slots: &[u64]) -> Vec<u8> {
+        slots.iter().flat_map(|slot| slot.to_le_bytes()).collect()
+    }
+
+    fn x64_image() -> Vec<u8> {
+        pe_image(&[
+            (0x2300, NO_PROLOG),
+            (0x2310, PUSH_RBX_ALLOC_0X20),
+            (0x1030, EPILOG),
+        ])
+    }
+
+    fn x64_walker<'a>(memory: &'a DumpMemory<'a>) -> StackWalker<'a> {
+        StackWalker {
+            memory,
+            is_64bit: true,
+            modules: vec![WalkerModule {
+                address_range: IMAGE_BASE..IMAGE_BASE + 0x3000,
+                pe_headers: PeHeaders::read(memory, IMAGE_BASE),
+            }],
+        }
+    }
+
+    fn x64_registers(instruction_pointer: u64, stack_pointer: u64) -> Registers {
+        let mut x64_registers = [0; 16];
+        x64_registers[RSP] = stack_pointer;
+        Registers {
+            instruction_pointer,
+            stack_pointer,
+            frame_pointer: Some(0),
+            link_register: None,
+            x64_registers: Some(x64_registers),
+        }
+    }
+
+    /// A walker for modules without headers, so that only the non-CFI methods apply.
+    fn walker_without_headers<'a>(
+        memory: &'a DumpMemory<'a>,
+        is_64bit: bool,
+        code_range: Range<u64>,
+    ) -> StackWalker<'a> {
+        StackWalker {
+            memory,
+            is_64bit,
+            modules: vec![WalkerModule {
+                address_range: code_range,
+                pe_headers: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn unwinds_x64_frames_with_unwind_info() {
+        let image = x64_image();
+        // 0x20 bytes of locals, the saved rbx, and the return address into the function at 0x1000,
+        // whose own return address of 0 ends the stack
+        let stack = stack(&[0, 0, 0, 0, 0x5a5a, IMAGE_BASE + 0x1008, 0]);
+        let memory = DumpMemory::from_regions(&[(IMAGE_BASE, &image), (STACK_BASE, &stack)]);
+        let stack_walker = x64_walker(&memory);
+
+        let frames = stack_walker.walk(x64_registers(IMAGE_BASE + 0x1020, STACK_BASE));
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].instruction, IMAGE_BASE + 0x1008);
+        assert_eq!(frames[1].stack_pointer, STACK_BASE + 0x30);
+        assert_eq!(frames[1].trust, FrameTrust::CallFrameInfo);
+
+        let (caller_registers, _) = stack_walker
+            .unwind_frame(&x64_registers(IMAGE_BASE + 0x1020, STACK_BASE), true)
+            .unwrap();
+        assert_eq!(caller_registers.x64_registers.unwrap()[RBX], 0x5a5a);
+    }
+
+    #[test]
+    fn unwinds_x64_frames_in_the_middle_of_a_prolog() {
+        let image = x64_image();
+        // Stopped after `push rbx`, but before `sub rsp, 0x20`
+        let stack = stack(&[0x5a5a, IMAGE_BASE + 0x1008, 0]);
+        let memory = DumpMemory::from_regions(&[(IMAGE_BASE, &image), (STACK_BASE, &stack)]);
+        let stack_walker = x64_walker(&memory);
+
+        let (caller_registers, trust) = stack_walker
+            .unwind_frame(&x64_registers(IMAGE_BASE + 0x1011, STACK_BASE), true)
+            .unwrap();
+        assert_eq!(trust, FrameTrust::CallFrameInfo);
+        assert_eq!(caller_registers.instruction_pointer, IMAGE_BASE + 0x1008);
+        assert_eq!(caller_registers.stack_pointer, STACK_BASE + 0x10);
+        assert_eq!(caller_registers.x64_registers.unwrap()[RBX], 0x5a5a);
+    }
+
+    #[test]
+    fn unwinds_x64_frames_in_the_middle_of_an_epilog() {
+        let image = x64_image();
+        // Stopped after `add rsp, 0x20`, so applying the unwind info would deallocate the locals twice
+        let stack = stack(&[0x5a5a, IMAGE_BASE + 0x1008, 0]);
+        let memory = DumpMemory::from_regions(&[(IMAGE_BASE, &image), (STACK_BASE, &stack)]);
+        let stack_walker = x64_walker(&memory);
+
+        let (caller_registers, trust) = stack_walker
+            .unwind_frame(&x64_registers(IMAGE_BASE + 0x1034, STACK_BASE), true)
+            .unwrap();
+        assert_eq!(trust, FrameTrust::CallFrameInfo);
+        assert_eq!(caller_registers.instruction_pointer, IMAGE_BASE + 0x1008);
+        assert_eq!(caller_registers.stack_pointer, STACK_BASE + 0x10);
+        assert_eq!(caller_registers.x64_registers.unwrap()[RBX], 0x5a5a);
+    }
+
+    #[test]
+    fn skips_unwind_code_operands_when_looking_for_the_frame_register() {
+        // The function at RVA 0x1010 has a frame register (rbp) which its prolog never sets, and
+        // saves rbx at [rsp + 0x1800]. The operand 0x300 of UWOP_SAVE_NONVOL looks like a
+        // UWOP_SET_FPREG code, which would address rbx relative to rbp instead.
+        let save_rbx: &[u8] = &[
+            0x01,
+            0x08,
+            0x02,
+            RBP as u8,
+            0x08,
+            UWOP_SAVE_NONVOL | ((RBX as u8) << 4),
+            0x00,
+            UWOP_SET_FPREG,
+        ];
+        let image = pe_image(&[(0x2300, NO_PROLOG), (0x2310, save_rbx)]);
+        let mut stack_slots = vec![0; 0x301];
+        stack_slots[0] = IMAGE_BASE + 0x1008;
+        stack_slots[0x300] = 0x5a5a;
+        let stack = stack(&stack_slots);
+        let memory = DumpMemory::from_regions(&[(IMAGE_BASE, &image), (STACK_BASE, &stack)]);
+        let stack_walker = x64_walker(&memory);
+
+        let (caller_registers, trust) = stack_walker
+            .unwind_frame(&x64_registers(IMAGE_BASE + 0x1020, STACK_BASE), true)
+            .unwrap();
+        assert_eq!(trust, FrameTrust::CallFrameInfo);
+        assert_eq!(caller_registers.instruction_pointer, IMAGE_BASE + 0x1008);
+        assert_eq!(caller_registers.x64_registers.unwrap()[RBX], 0x5a5a);
+    }
+
+    #[test]
+    fn emulates_x64_epilogs() {
+        let stack = stack(&[0x1111, 0x2222, 0x3333]);
+        // lea rsp, [rbp + 0x10]; pop r12; pop rbp; ret 8
+        let lea_epilog: &[u8] = &[0x48, 0x8d, 0x65, 0x10, 0x41, 0x5c, 0x5d, 0xc2, 0x08, 0x00];
+        // add rsp, 0x20; mov eax, 1
+        let not_an_epilog: &[u8] = &[0x48, 0x83, 0xc4, 0x20, 0xb8, 0x01, 0x00, 0x00, 0x00];
+        let memory = DumpMemory::from_regions(&[
+            (0x1000, lea_epilog),
+            (0x2000, not_an_epilog),
+            (STACK_BASE, &stack),
+        ]);
+        let stack_walker = StackWalker {
+            memory: &memory,
+            is_64bit: true,
+            modules: Vec::new(),
+        };
+
+        let mut x64_registers = [0; 16];
+        x64_registers[RBP] = STACK_BASE - 0x10;
+        assert!(stack_walker.unwind_x64_epilog(&mut x64_registers, 0x1000));
+        assert_eq!(x64_registers[12], 0x1111);
+        assert_eq!(x64_registers[RBP], 0x2222);
+        assert_eq!(x64_registers[RSP], STACK_BASE + 0x10);
+
+        // The registers are left alone when the instructions aren't an epilog
+        let mut x64_registers = [0; 16];
+        x64_registers[RSP] = STACK_BASE;
+        assert!(!stack_walker.unwind_x64_epilog(&mut x64_registers, 0x2000));
+        assert_eq!(x64_registers[RSP], STACK_BASE);
+    }
+
+    #[test]
+    fn follows_frame_pointers() {
+        let code = vec![0x90; 0x100];
+        // Two frames, each with the caller's saved ebp followed by the return address
+        let stack: Vec<u8> = [0u32, 0, 0x0ab0_1010, 0x0040_0020, 0, 0x0040_0040]
+            .iter()
+            .flat_map(|slot| slot.to_le_bytes())
+            .collect();
+        let memory = DumpMemory::from_regions(&[(0x0040_0000, &code), (0x0ab0_1000, &stack)]);
+        let stack_walker = walker_without_headers(&memory, false, 0x0040_0000..0x0040_0100);
+
+        let frames = stack_walker.walk(Registers {
+            instruction_pointer: 0x0040_0010,
+            stack_pointer: 0x0ab0_1000,
+            frame_pointer: Some(0x0ab0_1008),
+            link_register: None,
+            x64_registers: None,
+        });
+        let instructions: Vec<u64> = frames.iter().map(|frame| frame.instruction).collect();
+        assert_eq!(instructions, vec![0x0040_0010, 0x0040_0020, 0x0040_0040]);
+        assert_eq!(frames[1].trust, FrameTrust::FramePointer);
+        assert_eq!(frames[1].stack_pointer, 0x0ab0_1010);
+    }
+
+    #[test]
+    fn uses_the_link_register_of_leaf_functions() {
+        let code = vec![0; 0x100];
+        let memory = DumpMemory::from_regions(&[(0x1000, &code)]);
+        let stack_walker = walker_without_headers(&memory, true, 0x1000..0x1100);
+
+        let (caller_registers, trust) = stack_walker
+            .unwind_frame(
+                &Registers {
+                    instruction_pointer: 0x1010,
+                    stack_pointer: 0x8000,
+                    frame_pointer: None,
+                    link_register: Some(0x1080),
+                    x64_registers: None,
+                },
+                true,
+            )
+            .unwrap();
+        assert_eq!(trust, FrameTrust::LinkRegister);
+        assert_eq!(caller_registers.instruction_pointer, 0x1080);
+        assert_eq!(caller_registers.stack_pointer, 0x8000);
+    }
+
+    #[test]
+    fn scans_stacks_at_the_top_of_the_address_space() {
+        let code = vec![0; 0x100];
+        let stack = stack(&[0x1040, 0]);
+        let memory = DumpMemory::from_regions(&[(0x1000, &code), (u64::MAX - 0xf, &stack)]);
+        let stack_walker = walker_without_headers(&memory, true, 0x1000..0x1100);
+
+        let registers = Registers {
+            instruction_pointer: 0x1010,
+            stack_pointer: u64::MAX - 0xf,
+            frame_pointer: None,
+            link_register: None,
+            x64_registers: None,
+        };
+        let caller_registers = stack_walker.unwind_scan(&registers, true).unwrap();
+        assert_eq!(caller_registers.instruction_pointer, 0x1040);
+        assert_eq!(caller_registers.stack_pointer, u64::MAX - 0x7);
+
+        // Scanning past the last slot would run off the end of the address space
+        assert!(stack_walker.unwind_scan(&caller_registers, true).is_none());
+    }
+}
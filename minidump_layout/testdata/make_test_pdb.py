@@ -0,0 +1,153 @@
+#!/usr/bin/env python3
+"""Regenerates test.pdb from test.pdb.yaml.
+
+`llvm-pdbutil yaml2pdb` writes the PDB info, DBI, TPI, and IPI streams, but neither the public
+symbols nor the section headers that map segment offsets to RVAs. This adds both to its output
+and repacks the MSF with 512-byte blocks to keep the fixture small.
+
+Usage: make_test_pdb.py [llvm-pdbutil]
+"""
+
+import os
+import struct
+import subprocess
+import sys
+import tempfile
+
+MSF_MAGIC = b"Microsoft C/C++ MSF 7.00\r\n\x1aDS\0\0\0"
+BLOCK_SIZE = 512
+DBI_STREAM = 3
+SECTION_HEADER_DEBUG_STREAM = 5
+S_PUB32 = 0x110E
+PUBLIC_FUNCTION = 2
+
+# (name, flags, offset, segment)
+PUBLICS = [
+    ("?move_point@@YAHPEAUPOINT@@H@Z", PUBLIC_FUNCTION, 0x10, 1),
+    ("exported_function", PUBLIC_FUNCTION, 0x40, 1),
+    ("exported_data", 0, 0x0, 2),
+]
+
+# (name, virtual address, virtual size, characteristics)
+SECTIONS = [
+    (b".text", 0x1000, 0x1000, 0x60000020),
+    (b".data", 0x2000, 0x1000, 0xC0000040),
+]
+
+
+def read_msf(data):
+    assert data[: len(MSF_MAGIC)] == MSF_MAGIC
+    block_size, _, _, directory_size, _, block_map_block = struct.unpack_from("<6I", data, 32)
+
+    def block(index):
+        return data[index * block_size : (index + 1) * block_size]
+
+    directory_block_count = -(-directory_size // block_size)
+    directory_blocks = struct.unpack_from(f"<{directory_block_count}I", block(block_map_block))
+    directory = b"".join(block(index) for index in directory_blocks)[:directory_size]
+
+    stream_count = struct.unpack_from("<I", directory)[0]
+    sizes = struct.unpack_from(f"<{stream_count}I", directory, 4)
+    position = 4 + 4 * stream_count
+    streams = []
+    for size in sizes:
+        if size == 0xFFFFFFFF:
+            streams.append(None)
+            continue
+        count = -(-size // block_size)
+        blocks = struct.unpack_from(f"<{count}I", directory, position)
+        position += 4 * count
+        streams.append(b"".join(block(index) for index in blocks)[:size])
+    return streams
+
+
+def write_msf(streams):
+    blocks = [b"", b"\xff" * BLOCK_SIZE, b"\xff" * BLOCK_SIZE]
+
+    def allocate(data):
+        indices = []
+        for start in range(0, len(data), BLOCK_SIZE):
+            indices.append(len(blocks))
+            blocks.append(data[start : start + BLOCK_SIZE].ljust(BLOCK_SIZE, b"\0"))
+        return indices
+
+    directory = struct.pack("<I", len(streams))
+    directory += b"".join(
+        struct.pack("<I", 0xFFFFFFFF if stream is None else len(stream)) for stream in streams
+    )
+    for stream in streams:
+        if stream:
+            directory += struct.pack(f"<{-(-len(stream) // BLOCK_SIZE)}I", *allocate(stream))
+
+    directory_blocks = allocate(directory)
+    block_map = struct.pack(f"<{len(directory_blocks)}I", *directory_blocks)
+    block_map_block = allocate(block_map)[0]
+
+    # Mark every allocated block as in use in the free block map.
+    free_block_map = bytearray(b"\xff" * BLOCK_SIZE)
+    for index in range(len(blocks)):
+        free_block_map[index // 8] &= ~(1 << (index % 8))
+    blocks[1] = bytes(free_block_map)
+
+    blocks[0] = (
+        MSF_MAGIC
+        + struct.pack("<6I", BLOCK_SIZE, 1, len(blocks), len(directory), 0, block_map_block)
+    ).ljust(BLOCK_SIZE, b"\0")
+    return b"".join(blocks)
+
+
+def public_symbols():
+    records = b""
+    for name, flags, offset, segment in PUBLICS:
+        body = struct.pack("<HIIH", S_PUB32, flags, offset, segment) + name.encode() + b"\0"
+        body = body.ljust((len(body) + 2 + 3) // 4 * 4 - 2, b"\0")
+        records += struct.pack("<H", len(body)) + body
+    return records
+
+
+def section_headers():
+    headers = b""
+    for name, address, size, characteristics in SECTIONS:
+        headers += name.ljust(8, b"\0")
+        headers += struct.pack("<6I2HI", size, address, size, 0, 0, 0, 0, 0, characteristics)
+    return headers
+
+
+def add_streams(streams):
+    dbi = bytearray(streams[DBI_STREAM])
+    symbol_records_stream = len(streams)
+    section_header_stream = symbol_records_stream + 1
+    streams += [public_symbols(), section_headers()]
+
+    # The optional debug header is the last DBI substream, made of stream indices.
+    optional_header_size = struct.unpack_from("<I", dbi, 48)[0]
+    optional_header_offset = len(dbi) - optional_header_size
+    debug_streams = list(
+        struct.unpack_from(f"<{optional_header_size // 2}H", dbi, optional_header_offset)
+    )
+    debug_streams += [0xFFFF] * (11 - len(debug_streams))
+    debug_streams[SECTION_HEADER_DEBUG_STREAM] = section_header_stream
+    del dbi[optional_header_offset:]
+    dbi += struct.pack("<11H", *debug_streams)
+    struct.pack_into("<I", dbi, 48, 22)
+    struct.pack_into("<H", dbi, 20, symbol_records_stream)
+    streams[DBI_STREAM] = bytes(dbi)
+
+
+def main():
+    pdbutil = sys.argv[1] if len(sys.argv) > 1 else "llvm-pdbutil"
+    directory = os.path.dirname(os.path.abspath(__file__))
+    with tempfile.TemporaryDirectory() as temporary:
+        intermediate = os.path.join(temporary, "test.pdb")
+        yaml = os.path.join(directory, "test.pdb.yaml")
+        subprocess.run([pdbutil, "yaml2pdb", f"--pdb={intermediate}", yaml], check=True)
+        with open(intermediate, "rb") as file:
+            streams = read_msf(file.read())
+
+    add_streams(streams)
+    with open(os.path.join(directory, "test.pdb"), "wb") as file:
+        file.write(write_msf(streams))
+
+
+if __name__ == "__main__":
+    main()
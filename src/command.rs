@@ -1,44 +1,519 @@
+use std::fmt::Write;
 use std::str;
+use std::sync::atomic::Ordering;
+use std::thread;
 
-use log::{debug, error, info};
-use minidump::{Minidump, MinidumpMemoryInfoList};
+use log::{debug, error, info, warn};
+use minidump::{MinidumpContext, Module};
 
+use binaryninja::architecture::{Architecture, Register, RegisterId, RegisterInfo};
 use binaryninja::binary_view::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::function::Function;
+use binaryninja::rc::Ref;
+use binaryninja::variable::{PossibleValueSet, RegisterValueType};
 
-use crate::view::DataBufferWrapper;
-
-pub fn print_memory_information(bv: &BinaryView) {
-    debug!("Printing memory information");
-    if let Some(minidump_bv) = bv.parent_view() {
-        if let Ok(read_buffer) = minidump_bv.read_buffer(0, minidump_bv.len() as usize) {
-            let read_buffer = DataBufferWrapper::new(read_buffer);
-            if let Ok(minidump_obj) = Minidump::read(read_buffer) {
-                if let Ok(memory_info_list) = minidump_obj.get_stream::<MinidumpMemoryInfoList>() {
-                    let mut memory_info_list_writer = Vec::new();
-                    match memory_info_list.print(&mut memory_info_list_writer) {
-                        Ok(_) => {
-                            if let Ok(memory_info_str) = str::from_utf8(&memory_info_list_writer) {
-                                info!("{memory_info_str}");
-                            } else {
-                                error!("Could not convert the memory information description from minidump into a valid string");
-                            }
-                        }
-                        Err(_) => {
-                            error!("Could not get memory information from minidump");
-                        }
-                    }
-                } else {
-                    error!(
-                        "Could not parse a valid MinidumpMemoryInfoList stream from the minidump"
-                    );
+use minidump_layout::extended_registers::{context_bytes, read_extended_registers};
+use minidump_layout::layout::module_file_name;
+use minidump_layout::streams::{print_stream, stream_type_name};
+
+use crate::model::MinidumpModel;
+use crate::view::add_stack_frame_tags;
+
+/// Print a human-readable description of the streams in the loaded minidump, like `minidump-dump` does,
+/// either of every stream or only of the streams with the given stream type.
+pub fn print_streams(bv: &BinaryView, stream_type: Option<u32>) {
+    debug!("Printing minidump streams");
+    let Some(model) = MinidumpModel::for_view(bv) else {
+        return;
+    };
+    let mut found_stream = false;
+    for stream in model.stream_directory.iter() {
+        if stream_type.is_some_and(|stream_type| stream_type != stream.stream_type) {
+            continue;
+        }
+        found_stream = true;
+        let mut stream_writer = Vec::new();
+        if print_stream(
+            model.minidump(),
+            &model.dump_data,
+            stream,
+            model.system_info.as_ref(),
+            model.misc_info.as_ref(),
+            bv.address_size() == 8,
+            &mut stream_writer,
+        )
+        .is_err()
+        {
+            error!(
+                "Could not get a description of stream {:#x} from minidump",
+                stream.stream_type
+            );
+            continue;
+        }
+        if let Ok(stream_str) = str::from_utf8(&stream_writer) {
+            info!("{stream_str}");
+        } else {
+            error!("Could not convert the stream description from minidump into a valid string");
+        }
+    }
+    if !found_stream {
+        if let Some(stream_type) = stream_type {
+            warn!(
+                "The minidump has no {} stream",
+                stream_type_name(stream_type).unwrap_or("such")
+            );
+        }
+    }
+}
+
+/// The register state of a thread, from either the thread's own `CONTEXT`
+/// or the `CONTEXT` of the exception in the `MinidumpException` stream.
+struct ThreadContext<'a> {
+    thread_id: u32,
+    /// Whether this is the context of the exception, rather than of the thread itself.
+    is_crash_site: bool,
+    context: MinidumpContext,
+    /// The raw bytes of the context, including any extended state after it.
+    context_bytes: &'a [u8],
+}
+
+impl ThreadContext<'_> {
+    fn description(&self) -> String {
+        if self.is_crash_site {
+            format!("crash site in thread {:#x}", self.thread_id)
+        } else {
+            format!("thread {:#x}", self.thread_id)
+        }
+    }
+}
+
+/// Collect the context of the exception in the `MinidumpException` stream, and with
+/// `include_other_threads`, the context of every other thread in the `MinidumpThreadList` stream.
+fn read_thread_contexts(
+    model: &MinidumpModel,
+    include_other_threads: bool,
+) -> Vec<ThreadContext<'_>> {
+    let mut thread_contexts = Vec::new();
+    let Some(minidump_system_info) = &model.system_info else {
+        error!(
+            "Could not decode thread contexts: could not find a valid MinidumpSystemInfo stream"
+        );
+        return thread_contexts;
+    };
+    let minidump_misc_info = model.misc_info.as_ref();
+    let dump_data = &model.dump_data;
+
+    let crashing_thread_id = match model.exception() {
+        Some(minidump_exception) => {
+            match minidump_exception.context(minidump_system_info, minidump_misc_info) {
+                Some(context) => thread_contexts.push(ThreadContext {
+                    thread_id: minidump_exception.raw.thread_id,
+                    is_crash_site: true,
+                    context: context.into_owned(),
+                    context_bytes: context_bytes(dump_data, &minidump_exception.raw.thread_context),
+                }),
+                None => warn!("Could not find a valid CONTEXT in the MinidumpException stream"),
+            }
+            Some(minidump_exception.raw.thread_id)
+        }
+        None => {
+            warn!("Could not find a MinidumpException stream in minidump");
+            None
+        }
+    };
+    if include_other_threads {
+        if let Some(minidump_thread_list) = model.thread_list() {
+            for thread in minidump_thread_list.threads.iter() {
+                let thread_id = thread.raw.thread_id;
+                // The crashing thread's own context is inside the exception handler
+                if Some(thread_id) == crashing_thread_id {
+                    continue;
                 }
+                if let Some(context) = thread.context(minidump_system_info, minidump_misc_info) {
+                    thread_contexts.push(ThreadContext {
+                        thread_id,
+                        is_crash_site: false,
+                        context: context.into_owned(),
+                        context_bytes: context_bytes(dump_data, &thread.raw.thread_context),
+                    });
+                }
+            }
+        }
+    }
+    thread_contexts
+}
+
+/// The offset of an address from the start of the function or module it was found in, which is
+/// negative when a function's code comes before its start (e.g. a separately placed cold block).
+fn symbol_offset(address: u64, start: u64) -> String {
+    match address.checked_sub(start) {
+        Some(offset) => format!("+{:#x}", offset),
+        None => format!("-{:#x}", start - address),
+    }
+}
+
+/// Unwind the call stack of every thread in the minidump, and show the symbolized call stacks as a report.
+///
+/// The first time this runs, the return addresses in the call stacks of the threads other than
+/// the crashing thread are tagged as well.
+pub fn show_thread_call_stacks(bv: &BinaryView) {
+    debug!("Showing thread call stacks");
+    let Some(model) = MinidumpModel::for_view(bv) else {
+        return;
+    };
+    let dump_memory = model.memory();
+    let minidump_module_list = model.module_list.as_ref();
+    let minidump_thread_names = model.thread_names.as_ref();
+
+    // Name an address after the function containing it, or failing that, the module containing it.
+    // Return addresses point just after the call instruction, which may be past the end
+    // of the calling function, so they're looked up by the address of the call instead.
+    let symbolize = |address: u64, lookup_address: u64| -> String {
+        if let Some(function) = bv.functions_containing(lookup_address).iter().next() {
+            return format!(
+                "{}{}",
+                function.symbol().full_name(),
+                symbol_offset(address, function.start())
+            );
+        }
+        match minidump_module_list
+            .and_then(|module_list| module_list.module_at_address(lookup_address))
+        {
+            Some(module_info) => format!(
+                "{}{}",
+                module_file_name(&module_info.name),
+                symbol_offset(address, module_info.base_address())
+            ),
+            None => String::from("???"),
+        }
+    };
+
+    let mut markdown = String::from("# Thread Call Stacks\n\n");
+    let mut plain_text = String::new();
+    let thread_stacks = model.walk_threads(bv.address_size() == 8, false);
+    // Only the crashing thread's call stack is tagged when the view is loaded
+    if !model
+        .other_thread_stacks_tagged
+        .swap(true, Ordering::Relaxed)
+    {
+        add_stack_frame_tags(
+            bv,
+            thread_stacks
+                .iter()
+                .filter(|thread_stack| !thread_stack.is_crashing_thread),
+            dump_memory,
+        );
+    }
+    for thread_stack in &thread_stacks {
+        let mut thread_description = format!("Thread {:#x}", thread_stack.thread_id);
+        if let Some(thread_name) = minidump_thread_names
+            .and_then(|thread_names| thread_names.get_name(thread_stack.thread_id))
+        {
+            let _ = write!(thread_description, " ({})", thread_name);
+        }
+        if thread_stack.is_crashing_thread {
+            thread_description.push_str(" (crashed)");
+        }
+        let _ = write!(
+            markdown,
+            "## {}\n\n| Frame | Address | Location | Found by |\n| --- | --- | --- | --- |\n",
+            thread_description
+        );
+        let _ = writeln!(plain_text, "{}", thread_description);
+
+        for (frame_number, frame) in thread_stack.frames.iter().enumerate() {
+            let lookup_address = if frame_number == 0 {
+                frame.instruction
             } else {
-                error!("Could not parse a valid minidump file from the parent binary view's data buffer");
+                frame.instruction.saturating_sub(1)
+            };
+            let location = symbolize(frame.instruction, lookup_address);
+            let _ = writeln!(
+                markdown,
+                "| {} | `{:#x}` | `{}` | {} |",
+                frame_number, frame.instruction, location, frame.trust
+            );
+            let _ = writeln!(
+                plain_text,
+                "  {:>3}  {:#018x}  {}  ({})",
+                frame_number, frame.instruction, location, frame.trust
+            );
+        }
+        markdown.push('\n');
+        plain_text.push('\n');
+    }
+
+    bv.show_markdown_report("Minidump Thread Call Stacks", &markdown, &plain_text);
+}
+
+/// Inform the data flow analysis of the register values at the crash site, from the `CONTEXT`
+/// in the `MinidumpException` stream, so that IL at the crash site shows the actual values
+/// being operated on. With `all_threads`, the register values at the instruction pointer of every
+/// other thread, from the thread's own `CONTEXT`, are applied too.
+///
+/// User-informed values are attached to a definition of a variable, so each register's value is
+/// applied to the definition of the register which reaches the address (in MLIL SSA form), or to
+/// the function's entry if the register isn't written to on the way there. This covers every
+/// general-purpose register other than the stack pointer and instruction pointer, which analysis
+/// already tracks, whether or not the function has a named variable for it. Once analysis has
+/// been updated in the background, the values which data flow still doesn't see at the address
+/// (e.g. because of a merge with another path) are logged.
+///
+/// Vector registers (e.g. `ymm0` or `v0`) don't fit in a data flow value, so the non-zero ones
+/// are listed in the comment at the address instead.
+pub fn apply_register_values(bv: &BinaryView, all_threads: bool) {
+    debug!("Applying register values from thread contexts");
+    let Some(model) = MinidumpModel::for_view(bv) else {
+        return;
+    };
+    let xstate_config = model.xstate_config.as_ref();
+
+    // The register values applied in each function, to check once analysis has caught up
+    let mut applied_values = Vec::new();
+    for thread_context in read_thread_contexts(&model, all_threads) {
+        let description = thread_context.description();
+        let address = thread_context.context.get_instruction_pointer();
+        let functions = bv.functions_containing(address);
+        if functions.is_empty() {
+            warn!(
+                "Could not apply register values for {}: there is no function containing {:#x}",
+                description, address
+            );
+            continue;
+        }
+        for function in functions.iter() {
+            let Some((mlil, instruction)) = function.medium_level_il().ok().and_then(|mlil| {
+                let instruction = mlil.instruction_at(address)?;
+                Some((mlil, instruction))
+            }) else {
+                warn!(
+                    "Could not apply register values for {}: function {} has no MLIL instruction at {:#x}",
+                    description,
+                    function.symbol().full_name(),
+                    address
+                );
+                continue;
+            };
+            let mlil_ssa = mlil.ssa_form();
+            let arch = function.arch();
+            let mut register_values = Vec::new();
+            for register in arch.registers_full_width() {
+                let Some(value) =
+                    general_purpose_register_value(&thread_context.context, &register.name())
+                else {
+                    continue;
+                };
+                let variable = instruction.variable_for_register(register.id());
+                let ssa_variable = instruction.ssa_variable_version(variable);
+                // Version 0 of a variable has no definition: it holds the value from the function's entry
+                let (definition_address, after_definition) =
+                    match mlil_ssa.ssa_variable_definition(&ssa_variable) {
+                        Some(definition) => (definition.address, true),
+                        None => (function.start(), false),
+                    };
+                function.set_user_var_value(
+                    &variable,
+                    definition_address,
+                    PossibleValueSet::ConstantValue {
+                        value: value as i64,
+                    },
+                    after_definition,
+                );
+                register_values.push(AppliedRegisterValue {
+                    id: register.id(),
+                    name: register.name().to_string(),
+                    size: register.info().size(),
+                    value,
+                });
+            }
+            info!(
+                "Applied {} register values for {} at {:#x} in function {}",
+                register_values.len(),
+                description,
+                address,
+                function.symbol().full_name(),
+            );
+            applied_values.push(AppliedRegisterValues {
+                function: function.to_owned(),
+                address,
+                description: description.clone(),
+                register_values,
+            });
+        }
+
+        // Vector registers are wider than the values which data flow analysis can hold,
+        // so the ones which are in use are added to the comment at the address instead.
+        let extended_registers = read_extended_registers(
+            &thread_context.context,
+            thread_context.context_bytes,
+            xstate_config,
+        );
+        let extended_register_lines: Vec<String> = extended_registers
+            .iter()
+            .filter(|register| register.value.iter().any(|byte| *byte != 0))
+            .map(|register| format!("{} = {}", register.name, register.value_hex()))
+            .collect();
+        if !extended_register_lines.is_empty() {
+            let existing_comment = bv.comment_at(address).to_string();
+            let heading = format!("Vector registers ({}):", description);
+            // Don't repeat the registers if the command is run more than once
+            if !existing_comment.contains(&heading) {
+                let mut comment = existing_comment;
+                if !comment.is_empty() {
+                    comment.push('\n');
+                }
+                let _ = write!(
+                    comment,
+                    "{}\n{}",
+                    heading,
+                    extended_register_lines.join("\n")
+                );
+                bv.set_comment_at(address, &comment);
+            }
+        }
+    }
+
+    // Waiting for analysis would block the UI, so the values are checked in the background
+    let bv = bv.to_owned();
+    thread::spawn(move || {
+        bv.update_analysis_and_wait();
+        for applied_values in applied_values {
+            log_missing_register_values(&applied_values);
+        }
+    });
+}
+
+/// A register value applied by [`apply_register_values`].
+struct AppliedRegisterValue {
+    id: RegisterId,
+    name: String,
+    size: usize,
+    value: u64,
+}
+
+/// The register values applied at an address in a function, for one thread.
+struct AppliedRegisterValues {
+    function: Ref<Function>,
+    address: u64,
+    description: String,
+    register_values: Vec<AppliedRegisterValue>,
+}
+
+/// Log the applied register values which data flow analysis doesn't see at their address.
+fn log_missing_register_values(applied_values: &AppliedRegisterValues) {
+    let AppliedRegisterValues {
+        function,
+        address,
+        description,
+        register_values,
+    } = applied_values;
+    let Some(instruction) = function
+        .medium_level_il()
+        .ok()
+        .and_then(|mlil| mlil.instruction_at(*address))
+    else {
+        return;
+    };
+    let missing_registers: Vec<&str> = register_values
+        .iter()
+        .filter(|register| {
+            let register_value = instruction.register_value(register.id);
+            let mask = match register.size {
+                size @ 1..=7 => (1 << (size * 8)) - 1,
+                _ => u64::MAX,
+            };
+            register_value.state != RegisterValueType::ConstantValue
+                || register_value.value as u64 & mask != register.value & mask
+        })
+        .map(|register| register.name.as_str())
+        .collect();
+    if !missing_registers.is_empty() {
+        warn!(
+            "Data flow analysis doesn't see the values of {} of {} registers for {} at {:#x} in function {}: {}",
+            missing_registers.len(),
+            register_values.len(),
+            description,
+            address,
+            function.symbol().full_name(),
+            missing_registers.join(", "),
+        );
+    }
+}
+
+/// Show the registers of every thread in the minidump as a report, including extended register
+/// state (`xmm`/`ymm`/`zmm` and AVX-512 mask registers on x64, NEON registers on AArch64).
+pub fn show_thread_registers(bv: &BinaryView) {
+    debug!("Showing thread registers");
+    let Some(model) = MinidumpModel::for_view(bv) else {
+        return;
+    };
+    let xstate_config = model.xstate_config.as_ref();
+
+    let mut markdown = String::from("# Thread Registers\n\n");
+    let mut plain_text = String::new();
+    for thread_context in read_thread_contexts(&model, true) {
+        let description = thread_context.description();
+        let _ = write!(
+            markdown,
+            "## {}\n\n| Register | Value |\n| --- | --- |\n",
+            description
+        );
+        let _ = writeln!(plain_text, "{}", description);
+
+        let context = &thread_context.context;
+        for register_name in context.general_purpose_registers() {
+            if let Some(value) = context.get_register(register_name) {
+                let _ = writeln!(markdown, "| {} | `{:#x}` |", register_name, value);
+                let _ = writeln!(plain_text, "  {:>6} = {:#x}", register_name, value);
             }
-        } else {
-            error!("Could not read data from parent binary view");
         }
-    } else {
-        error!("Could not get the parent binary view");
+        for register in
+            read_extended_registers(context, thread_context.context_bytes, xstate_config)
+        {
+            let _ = writeln!(
+                markdown,
+                "| {} | `{}` |",
+                register.name,
+                register.value_hex()
+            );
+            let _ = writeln!(
+                plain_text,
+                "  {:>6} = {}",
+                register.name,
+                register.value_hex()
+            );
+        }
+        markdown.push('\n');
+        plain_text.push('\n');
+    }
+
+    bv.show_markdown_report("Minidump Thread Registers", &markdown, &plain_text);
+}
+
+/// The registers in a minidump `CONTEXT` which are left to analysis rather than applied as values:
+/// the stack pointer and instruction pointer of each architecture, and the flags.
+const UNAPPLIED_CONTEXT_REGISTERS: &[&str] = &["eip", "esp", "eflags", "rip", "rsp", "pc", "sp"];
+
+/// Look up a general-purpose register other than the stack pointer and instruction pointer in a
+/// minidump `CONTEXT` by its name in Binary Ninja's architecture plugins, which don't always use
+/// the same names as the minidump crate (e.g. `x29` for `fp` on AArch64). Flags, segment registers,
+/// and the stack pointer and instruction pointer have no value.
+fn general_purpose_register_value(context: &MinidumpContext, register_name: &str) -> Option<u64> {
+    let alias = match register_name {
+        "x29" | "r11" => Some("fp"),
+        "x30" | "r14" => Some("lr"),
+        "r13" => Some("sp"),
+        "r15" => Some("pc"),
+        "fp" => Some("x29"),
+        "lr" => Some("x30"),
+        _ => None,
+    };
+    let context_register_name = [Some(register_name), alias]
+        .into_iter()
+        .flatten()
+        .find(|name| context.general_purpose_registers().contains(name))?;
+    if UNAPPLIED_CONTEXT_REGISTERS.contains(&context_register_name) {
+        return None;
     }
+    context.get_register(context_register_name)
 }
@@ -0,0 +1,407 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+use log::{debug, info, warn};
+use minidump::Module;
+
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use binaryninja::debuginfo::{CustomDebugInfoParser, DebugFunctionInfo, DebugInfo};
+use binaryninja::rc::Ref;
+use binaryninja::settings::Settings;
+use binaryninja::types::{
+    EnumerationBuilder, FunctionParameter, MemberAccess, MemberScope, StructureBuilder,
+    StructureType, Type,
+};
+
+use minidump_layout::breakpad_symbols::{find_breakpad_symbols, read_breakpad_symbols};
+use minidump_layout::layout::module_file_name;
+use minidump_layout::pdb_symbols::{
+    find_pdb, read_pdb_functions, read_pdb_types, PdbFunction, PdbFunctionType, PdbStructKind,
+    PdbType, PdbTypeDefinition,
+};
+
+use crate::model::MinidumpModel;
+use crate::view::VIEW_TYPE_NAME;
+
+pub const DEBUG_INFO_PARSER_NAME: &str = "Minidump Modules";
+
+/// The setting holding the local directories and symbol stores to search for the PDBs of
+/// the modules in a minidump.
+const PDB_SEARCH_PATHS_SETTING: &str = "minidump.symbols.pdbSearchPaths";
+
+/// The setting holding the local directories to search for the Breakpad symbol files of
+/// the modules in a minidump.
+const BREAKPAD_SEARCH_PATHS_SETTING: &str = "minidump.symbols.breakpadSearchPaths";
+
+pub fn register_settings() {
+    let settings = Settings::new();
+    settings.register_group("minidump", "Minidump");
+    settings.register_setting_json(
+        PDB_SEARCH_PATHS_SETTING,
+        r#"{
+            "title": "PDB Search Paths",
+            "type": "array",
+            "elementType": "string",
+            "default": [],
+            "description": "Local directories to search for the PDB files of the modules in a minidump. Each directory can either contain the PDB files directly, or be a local symbol store (e.g. a WinDbg symbol cache) laid out as <name>.pdb/<GUID><age>/<name>.pdb. PDB files are only used if their GUID and age match the CodeView record of the module.",
+            "ignore": ["SettingsProjectScope"]
+        }"#,
+    );
+    settings.register_setting_json(
+        BREAKPAD_SEARCH_PATHS_SETTING,
+        r#"{
+            "title": "Breakpad Symbol Search Paths",
+            "type": "array",
+            "elementType": "string",
+            "default": [],
+            "description": "Local directories to search for the Breakpad symbol files (.sym) of the modules in a minidump, laid out as <debug file>/<debug identifier>/<debug file>.sym. Symbol files are only used if the debug identifier in their MODULE record matches the module. Breakpad symbol files are used for modules without a matching PDB.",
+            "ignore": ["SettingsProjectScope"]
+        }"#,
+    );
+}
+
+fn search_paths(setting: &str) -> Vec<PathBuf> {
+    Settings::new()
+        .get_string_list(setting)
+        .iter()
+        .map(|path| PathBuf::from(path.to_string()))
+        .collect()
+}
+
+/// A debug info parser which applies the debug information of every module loaded in a minidump,
+/// rebased to the address at which each module is loaded.
+///
+/// Binary Ninja runs this parser itself when a _Minidump_ binary view is opened,
+/// with the minidump view acting as both the view and the debug file.
+pub struct MinidumpDebugInfoParser;
+
+impl CustomDebugInfoParser for MinidumpDebugInfoParser {
+    fn is_valid(&self, view: &BinaryView) -> bool {
+        let view_type = view.view_type().to_string();
+        view_type == VIEW_TYPE_NAME
+    }
+
+    fn parse_info(
+        &self,
+        debug_info: &mut DebugInfo,
+        view: &BinaryView,
+        _debug_file: &BinaryView,
+        progress: Box<dyn Fn(usize, usize) -> Result<(), ()>>,
+    ) -> bool {
+        let Some(model) = MinidumpModel::for_view(view) else {
+            return false;
+        };
+        let Some(minidump_module_list) = &model.module_list else {
+            warn!("Could not find valid module information in minidump: could not find a valid MinidumpModuleList stream");
+            return false;
+        };
+
+        let pdb_search_paths = search_paths(PDB_SEARCH_PATHS_SETTING);
+        let breakpad_search_paths = search_paths(BREAKPAD_SEARCH_PATHS_SETTING);
+        let platform = view.default_platform();
+        let module_count = minidump_module_list.iter().count();
+        let mut found_debug_info = false;
+
+        for (module_index, module_info) in minidump_module_list.iter().enumerate() {
+            if progress(module_index, module_count).is_err() {
+                warn!("Applying debug information for minidump modules was cancelled");
+                break;
+            }
+
+            // Only modules with a CodeView record (or, outside of Windows, a build ID)
+            // have both a debug file name and a debug identifier
+            let (Some(debug_file), Some(debug_id)) =
+                (module_info.debug_file(), module_info.debug_identifier())
+            else {
+                continue;
+            };
+            let debug_file = module_file_name(&debug_file);
+            let debug_id = debug_id.breakpad().to_string();
+
+            let (functions, type_definitions) = if let Some(pdb_symbols) =
+                read_module_pdb_symbols(&pdb_search_paths, debug_file, &debug_id)
+            {
+                pdb_symbols
+            } else if let Some(symbol_path) =
+                find_breakpad_symbols(&breakpad_search_paths, debug_file, &debug_id)
+            {
+                let symbols = match read_breakpad_symbols(&symbol_path) {
+                    Ok(symbols) => symbols,
+                    Err(error) => {
+                        warn!(
+                            "Could not read Breakpad symbol file {}: {}",
+                            symbol_path.display(),
+                            error
+                        );
+                        continue;
+                    }
+                };
+                info!(
+                    "Applying {} functions from Breakpad symbol file {} to module {}",
+                    symbols.functions.len(),
+                    symbol_path.display(),
+                    module_info.name,
+                );
+                let functions = symbols
+                    .functions
+                    .into_iter()
+                    .map(|(address, name)| {
+                        let function = PdbFunction {
+                            name,
+                            function_type: None,
+                        };
+                        (address, function)
+                    })
+                    .collect();
+                (functions, BTreeMap::new())
+            } else {
+                debug!(
+                    "Could not find debug information {} with debug identifier {} for module {}",
+                    debug_file, debug_id, module_info.name,
+                );
+                continue;
+            };
+
+            let module_name = module_file_name(&module_info.name);
+            for type_definition in type_definitions.values() {
+                debug_info.add_type(
+                    type_definition.name(),
+                    &definition_type(type_definition),
+                    &[module_name],
+                );
+            }
+            for (address, function) in functions {
+                // A damaged symbol file can have addresses past the end of the address space
+                let Some(address) = module_info.base_address().checked_add(address) else {
+                    debug!(
+                        "Skipping function {} at {:#x} past the end of the address space in module {}",
+                        function.name, address, module_info.name,
+                    );
+                    continue;
+                };
+                let full_name = format!("{}!{}", module_name, function.name);
+                debug_info.add_function(DebugFunctionInfo::new(
+                    Some(full_name.clone()),
+                    Some(full_name),
+                    Some(function.name),
+                    function.function_type.as_ref().map(function_type),
+                    Some(address),
+                    platform.clone(),
+                    vec![module_name.to_string()],
+                    vec![],
+                ));
+            }
+            found_debug_info = true;
+        }
+
+        found_debug_info
+    }
+}
+
+/// Apply the line records from the Breakpad symbol files of the modules in a minidump as
+/// `file:line` comments at the start of each line's code.
+///
+/// Debug info has no representation for line information, so this is a separate command rather
+/// than part of the debug info parser. Addresses which already have a comment are left alone,
+/// so that running it never overwrites comments from the user or from the plugin.
+pub fn apply_line_comments(view: &BinaryView) {
+    let Some(model) = MinidumpModel::for_view(view) else {
+        return;
+    };
+    let Some(minidump_module_list) = &model.module_list else {
+        warn!("Could not find valid module information in minidump: could not find a valid MinidumpModuleList stream");
+        return;
+    };
+    let breakpad_search_paths = search_paths(BREAKPAD_SEARCH_PATHS_SETTING);
+    let (mut applied_count, mut skipped_count) = (0, 0);
+
+    for module_info in minidump_module_list.iter() {
+        let (Some(debug_file), Some(debug_id)) =
+            (module_info.debug_file(), module_info.debug_identifier())
+        else {
+            continue;
+        };
+        let debug_file = module_file_name(&debug_file);
+        let debug_id = debug_id.breakpad().to_string();
+        let Some(symbol_path) =
+            find_breakpad_symbols(&breakpad_search_paths, debug_file, &debug_id)
+        else {
+            continue;
+        };
+        let symbols = match read_breakpad_symbols(&symbol_path) {
+            Ok(symbols) => symbols,
+            Err(error) => {
+                warn!(
+                    "Could not read Breakpad symbol file {}: {}",
+                    symbol_path.display(),
+                    error
+                );
+                continue;
+            }
+        };
+        debug!(
+            "Applying {} line records from Breakpad symbol file {} to module {}",
+            symbols.lines.len(),
+            symbol_path.display(),
+            module_info.name,
+        );
+        for (address, file_name, line_number) in symbols.lines {
+            let Some(address) = module_info.base_address().checked_add(address) else {
+                continue;
+            };
+            if !view.comment_at(address).to_string().is_empty() {
+                skipped_count += 1;
+                continue;
+            }
+            view.set_comment_at(address, &format!("{}:{}", file_name, line_number));
+            applied_count += 1;
+        }
+    }
+
+    info!(
+        "Applied {} line comments from Breakpad symbol files, and skipped {} addresses which already had a comment",
+        applied_count, skipped_count
+    );
+}
+
+/// Read the functions from a module's PDB, with addresses relative to the module's base address,
+/// and the definitions of its types, if its debug file is a PDB and a matching PDB can be found
+/// in the search paths.
+fn read_module_pdb_symbols(
+    search_paths: &[PathBuf],
+    debug_file: &str,
+    debug_id: &str,
+) -> Option<(
+    BTreeMap<u64, PdbFunction>,
+    BTreeMap<String, PdbTypeDefinition>,
+)> {
+    if !debug_file.to_ascii_lowercase().ends_with(".pdb") {
+        return None;
+    }
+    let pdb_path = find_pdb(search_paths, debug_file, debug_id)?;
+    let functions = match read_pdb_functions(&pdb_path) {
+        Ok(functions) => functions,
+        Err(error) => {
+            warn!("Could not read PDB {}: {}", pdb_path.display(), error);
+            return None;
+        }
+    };
+    // Functions are still applied without the definitions of their types
+    let type_definitions = read_pdb_types(&pdb_path).unwrap_or_else(|error| {
+        warn!(
+            "Could not read types from PDB {}: {}",
+            pdb_path.display(),
+            error
+        );
+        BTreeMap::new()
+    });
+    info!(
+        "Applying {} functions and {} types from PDB {}",
+        functions.len(),
+        type_definitions.len(),
+        pdb_path.display(),
+    );
+    let functions = functions
+        .into_iter()
+        .map(|(rva, function)| (rva as u64, function))
+        .collect();
+    Some((functions, type_definitions))
+}
+
+/// Convert a type from a PDB into a Binary Ninja type.
+fn pdb_type(pdb_type: &PdbType) -> Ref<Type> {
+    match pdb_type {
+        PdbType::Void | PdbType::Unknown => Type::void(),
+        PdbType::Bool(_) => Type::bool(),
+        PdbType::Char => Type::char(),
+        PdbType::WideChar(width) => Type::wide_char(*width),
+        PdbType::Int { width, signed } => Type::int(*width, *signed),
+        PdbType::Float(width) => Type::float(*width),
+        PdbType::Pointer { target, size } => {
+            Type::pointer_of_width(&pdb_type(target), *size, false, false, None)
+        }
+        PdbType::Array { element, count } => Type::array(&pdb_type(element), *count),
+        // Classes, structs, and enums are referred to by name, to be filled in by their
+        // definitions from the PDB, or by types from other sources (e.g. a type library).
+        PdbType::Struct { name } => Type::named_type_from_type(
+            name.as_str(),
+            &Type::structure(&StructureBuilder::new().finalize()),
+        ),
+        PdbType::Enum { name, underlying } => {
+            Type::named_type_from_type(name.as_str(), &pdb_type(underlying))
+        }
+        PdbType::Function(pdb_function_type) => function_type(pdb_function_type),
+    }
+}
+
+/// Convert the definition of a class, struct, union, or enum from a PDB into a Binary Ninja type.
+fn definition_type(type_definition: &PdbTypeDefinition) -> Ref<Type> {
+    match type_definition {
+        PdbTypeDefinition::Struct {
+            kind,
+            size,
+            members,
+            ..
+        } => {
+            let mut structure = StructureBuilder::new();
+            structure
+                .structure_type(match kind {
+                    PdbStructKind::Class => StructureType::ClassStructureType,
+                    PdbStructKind::Struct => StructureType::StructStructureType,
+                    PdbStructKind::Union => StructureType::UnionStructureType,
+                })
+                .width(*size);
+            for member in members {
+                structure.insert(
+                    &pdb_type(&member.member_type),
+                    &member.name,
+                    member.offset,
+                    false,
+                    MemberAccess::NoAccess,
+                    MemberScope::NoScope,
+                );
+            }
+            Type::structure(&structure.finalize())
+        }
+        PdbTypeDefinition::Enum {
+            underlying,
+            enumerators,
+            ..
+        } => {
+            let mut enumeration = EnumerationBuilder::new();
+            for (name, value) in enumerators {
+                enumeration.insert(name, *value);
+            }
+            let (width, signed) = match underlying {
+                PdbType::Int { width, signed } => (*width, *signed),
+                _ => (4, true),
+            };
+            Type::enumeration(
+                &enumeration.finalize(),
+                NonZeroUsize::new(width).unwrap_or(NonZeroUsize::MIN),
+                signed,
+            )
+        }
+    }
+}
+
+/// Convert the signature of a function from a PDB into a Binary Ninja function type.
+///
+/// PDB type information doesn't include the names of parameters,
+/// so they are named `arg1`, `arg2`, etc.
+fn function_type(pdb_function_type: &PdbFunctionType) -> Ref<Type> {
+    let parameters = pdb_function_type
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(index, parameter)| {
+            FunctionParameter::new(pdb_type(parameter), format!("arg{}", index + 1), None)
+        })
+        .collect();
+    Type::function(
+        &pdb_type(&pdb_function_type.return_type),
+        parameters,
+        pdb_function_type.variadic,
+    )
+}
@@ -0,0 +1,149 @@
+use std::fs::File;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use binaryninja::binary_view::{BinaryView, BinaryViewBase, BinaryViewExt};
+use binaryninja::data_buffer::DataBuffer;
+use log::{debug, error};
+use memmap2::Mmap;
+
+/// The size of a `MINIDUMP_HEADER`.
+const MINIDUMP_HEADER_SIZE: usize = 32;
+/// The size of a `MINIDUMP_DIRECTORY` entry.
+const MINIDUMP_DIRECTORY_SIZE: usize = 12;
+/// An upper bound on the number of bytes at the start of a memory-mapped minidump file which are
+/// compared with the parent view, in case the stream directory claims to be huge.
+const MAX_COMPARED_LEN: usize = 0x10_0000;
+
+/// The offset of the end of the stream directory in a minidump file, or of the end of the
+/// header if the stream directory can't be found.
+fn stream_directory_end(data: &[u8]) -> usize {
+    let read_u32 = |offset: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize)
+    };
+    let (Some(stream_count), Some(directory_rva)) = (read_u32(8), read_u32(12)) else {
+        return MINIDUMP_HEADER_SIZE.min(data.len());
+    };
+    directory_rva
+        .saturating_add(stream_count.saturating_mul(MINIDUMP_DIRECTORY_SIZE))
+        .max(MINIDUMP_HEADER_SIZE)
+        .min(data.len())
+}
+
+/// A wrapper around a `binaryninja::databuffer::DataBuffer`, from which a `[u8]` buffer can be obtained
+/// to pass to `minidump::Minidump::read`.
+///
+/// This code is taken from [`dwarfdump`](https://github.com/Vector35/binaryninja-api/blob/9d8bc846bd213407fb1a7a19af2a96f17501ac3b/rust/examples/dwarfdump/src/lib.rs#L81)
+/// in the Rust API examples.
+#[derive(Clone)]
+pub struct DataBufferWrapper {
+    inner: Arc<DataBuffer>,
+}
+
+impl DataBufferWrapper {
+    pub fn new(buf: DataBuffer) -> Self {
+        DataBufferWrapper {
+            inner: Arc::new(buf),
+        }
+    }
+}
+
+// SAFETY: `DataBuffer` owns a core `BNDataBuffer`, which isn't tied to the thread which created it.
+// The wrapper only ever hands out shared references to the buffer's contents, and never a
+// `&mut DataBuffer`, so the buffer is never modified after it is read from the parent view; it is
+// freed once, when the last clone of the `Arc` is dropped, on whichever thread that happens.
+// This lets the parsed minidump be shared between the view and its commands (see `MinidumpModel`).
+unsafe impl Send for DataBufferWrapper {}
+// SAFETY: Shared access only reads the buffer's contents through `BNGetDataBufferContents`,
+// which doesn't modify the buffer, so reading from several threads at once is safe.
+unsafe impl Sync for DataBufferWrapper {}
+
+impl Deref for DataBufferWrapper {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        self.inner.get_data()
+    }
+}
+
+/// The data of a minidump file, from which a `[u8]` buffer can be obtained
+/// to pass to `minidump::Minidump::read`.
+#[derive(Clone)]
+pub enum DumpData {
+    /// The minidump file, memory-mapped from disk. Only the pages which are actually accessed
+    /// (the header, the stream directory, and the streams which are parsed) are read from the file,
+    /// which matters for full memory dumps which are several gigabytes large.
+    ///
+    /// If another process truncates the file while it is mapped, accessing the pages past the new
+    /// end of the file raises `SIGBUS` (or an in-page error on Windows), which crashes Binary Ninja.
+    /// Minidump files aren't normally written to after they are complete, so this is accepted in
+    /// exchange for not reading gigabytes of memory up front.
+    Mapped(Arc<Mmap>),
+    /// A copy of the whole minidump file, read from the parent view.
+    Buffered(DataBufferWrapper),
+}
+
+impl DumpData {
+    /// Get the data of the minidump file underneath a raw parent view.
+    ///
+    /// The file which the parent view was opened from is memory-mapped if possible.
+    /// Otherwise (e.g. if the view was opened from a database rather than from the minidump file),
+    /// the whole file is read from the parent view instead.
+    pub fn read(parent_view: &BinaryView) -> Option<Self> {
+        if let Some(dump_data) = DumpData::map_file(parent_view) {
+            return Some(dump_data);
+        }
+        debug!("Could not memory-map the minidump file; reading it from the parent view instead");
+        match parent_view.read_buffer(0, parent_view.len() as usize) {
+            Ok(read_buffer) => Some(DumpData::Buffered(DataBufferWrapper::new(read_buffer))),
+            Err(_) => {
+                error!("Could not read data from parent binary view");
+                None
+            }
+        }
+    }
+
+    /// Memory-map the file which the parent view was opened from, if it still holds the data of
+    /// the parent view.
+    ///
+    /// The file on disk doesn't have any changes made to the parent view (e.g. bytes patched in
+    /// the raw view before the _Minidump_ view was opened), so the file is only used if the view
+    /// is unmodified, and if its header and stream directory match the parent view's.
+    /// The rest of the file isn't compared, since that would read the whole file.
+    fn map_file(parent_view: &BinaryView) -> Option<Self> {
+        if parent_view.file().is_modified() {
+            debug!("Not memory-mapping the minidump file, since the parent view has been modified");
+            return None;
+        }
+        let file_name = parent_view.file().filename().to_string();
+        let file = File::open(&file_name).ok()?;
+        // SAFETY: The file could be modified or truncated by another process while it is mapped,
+        // which would change the data under the minidump parser, or make accessing it fault
+        // (see `DumpData::Mapped`). This is the same trade-off which `minidump::Minidump::read_path` makes.
+        let mapped_file = unsafe { Mmap::map(&file) }.ok()?;
+        // This isn't the case if the view was opened from a database, for example
+        if mapped_file.len() as u64 != parent_view.len() || !mapped_file.starts_with(b"MDMP") {
+            return None;
+        }
+        let compared_len = stream_directory_end(&mapped_file).min(MAX_COMPARED_LEN);
+        let parent_bytes = parent_view.read_vec(0, compared_len);
+        if parent_bytes != mapped_file[..compared_len] {
+            debug!(
+                "Not memory-mapping minidump file {}, since it doesn't match the parent view",
+                file_name
+            );
+            return None;
+        }
+        debug!("Memory-mapped minidump file {}", file_name);
+        Some(DumpData::Mapped(Arc::new(mapped_file)))
+    }
+}
+
+impl Deref for DumpData {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        match self {
+            DumpData::Mapped(mapped_file) => mapped_file,
+            DumpData::Buffered(read_buffer) => read_buffer,
+        }
+    }
+}
@@ -0,0 +1,946 @@
+use std::cell::RefCell;
+use std::ops::Range;
+
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use binaryninja::rc::Ref;
+use binaryninja::section::{Section, Semantics};
+use binaryninja::segment::{Segment, SegmentFlags};
+use binaryninja::symbol::{Symbol, SymbolType};
+use binaryninja::types::{MemberAccess, MemberScope, StructureBuilder, Type};
+use log::{debug, warn};
+use minidump::Endian;
+
+use minidump_layout::linux::{read_u32, read_u64};
+use minidump_layout::streams::{read_stream_directory, stream_type_name};
+
+/// The source of the types defined for the minidump file format, in the parent view's type library.
+const TYPE_SOURCE: &str = "minidump_bn";
+
+/// The `Signature` of the `MINIDUMP_HEADER`, `MDMP`, read as a little-endian integer.
+const MINIDUMP_SIGNATURE: u32 = 0x504d_444d;
+
+/// The bits of `ContextFlags` which identify the CPU which a `CONTEXT` is for.
+const CONTEXT_CPU_MASK: u32 = 0xffff_ff00;
+const CONTEXT_X86: u32 = 0x0001_0000;
+const CONTEXT_AMD64: u32 = 0x0010_0000;
+const CONTEXT_ARM64: u32 = 0x0040_0000;
+const CONTEXT_ARM: u32 = 0x4000_0000;
+
+/// The streams whose contents are mapped into the metadata segment of the _Minidump_ view.
+const METADATA_STREAMS: &[&str] = &[
+    "ThreadListStream",
+    "ModuleListStream",
+    "ExceptionStream",
+    "SystemInfoStream",
+];
+
+/// The name of the section covering the metadata segment of the _Minidump_ view.
+const METADATA_SECTION_NAME: &str = "minidump metadata";
+
+/// The alignment of each range of the minidump file in the metadata segment.
+const METADATA_ALIGNMENT: u64 = 0x10;
+
+/// The `CvSignature` of a `CV_INFO_PDB70` record, `RSDS`, read as a little-endian integer.
+const CV_SIGNATURE_RSDS: u32 = 0x5344_5352;
+
+/// The Binary Ninja types for the structures of the minidump file format,
+/// as they are declared in `minidumpapiset.h` (and in Breakpad, for the non-Windows contexts).
+struct FileStructureTypes {
+    header: Ref<Type>,
+    directory: Ref<Type>,
+    thread: Ref<Type>,
+    module: Ref<Type>,
+    unloaded_module: Ref<Type>,
+    memory_descriptor: Ref<Type>,
+    memory_descriptor64: Ref<Type>,
+    memory64_list: Ref<Type>,
+    unloaded_module_list: Ref<Type>,
+    memory_info_list: Ref<Type>,
+    memory_info: Ref<Type>,
+    thread_name: Ref<Type>,
+    exception_stream: Ref<Type>,
+    system_info: Ref<Type>,
+    misc_info: Ref<Type>,
+    context_x86: Ref<Type>,
+    context_amd64: Ref<Type>,
+    context_arm: Ref<Type>,
+    context_arm64: Ref<Type>,
+}
+
+/// Build a structure type from its members, which are laid out one after another without padding,
+/// since the structures of the minidump file format are packed.
+fn structure_type(members: &[(&str, Ref<Type>)]) -> Ref<Type> {
+    let mut structure_builder = StructureBuilder::new();
+    let mut offset = 0;
+    for (name, member_type) in members {
+        structure_builder.insert(
+            member_type.as_ref(),
+            *name,
+            offset,
+            false,
+            MemberAccess::PublicAccess,
+            MemberScope::NoScope,
+        );
+        offset += member_type.width();
+    }
+    Type::structure(&structure_builder.finalize())
+}
+
+/// Define a named structure type in the view, and return a reference to the named type.
+fn define_structure(view: &BinaryView, name: &str, members: &[(&str, Ref<Type>)]) -> Ref<Type> {
+    let structure = structure_type(members);
+    view.define_auto_type(name, TYPE_SOURCE, &structure);
+    Type::named_type_from_type(name, &structure)
+}
+
+fn u8_type() -> Ref<Type> {
+    Type::int(1, false)
+}
+
+fn u16_type() -> Ref<Type> {
+    Type::int(2, false)
+}
+
+fn u32_type() -> Ref<Type> {
+    Type::int(4, false)
+}
+
+fn u64_type() -> Ref<Type> {
+    Type::int(8, false)
+}
+
+fn array_type(element_type: &Type, count: u64) -> Ref<Type> {
+    Type::array(element_type, count)
+}
+
+impl FileStructureTypes {
+    fn define(view: &BinaryView) -> Self {
+        let location_descriptor = define_structure(
+            view,
+            "MINIDUMP_LOCATION_DESCRIPTOR",
+            &[("DataSize", u32_type()), ("Rva", u32_type())],
+        );
+        let memory_descriptor = define_structure(
+            view,
+            "MINIDUMP_MEMORY_DESCRIPTOR",
+            &[
+                ("StartOfMemoryRange", u64_type()),
+                ("Memory", location_descriptor.clone()),
+            ],
+        );
+        let memory_descriptor64 = define_structure(
+            view,
+            "MINIDUMP_MEMORY_DESCRIPTOR64",
+            &[("StartOfMemoryRange", u64_type()), ("DataSize", u64_type())],
+        );
+        let header = define_structure(
+            view,
+            "MINIDUMP_HEADER",
+            &[
+                ("Signature", u32_type()),
+                ("Version", u32_type()),
+                ("NumberOfStreams", u32_type()),
+                ("StreamDirectoryRva", u32_type()),
+                ("CheckSum", u32_type()),
+                ("TimeDateStamp", u32_type()),
+                ("Flags", u64_type()),
+            ],
+        );
+        let directory = define_structure(
+            view,
+            "MINIDUMP_DIRECTORY",
+            &[
+                ("StreamType", u32_type()),
+                ("Location", location_descriptor.clone()),
+            ],
+        );
+        let thread = define_structure(
+            view,
+            "MINIDUMP_THREAD",
+            &[
+                ("ThreadId", u32_type()),
+                ("SuspendCount", u32_type()),
+                ("PriorityClass", u32_type()),
+                ("Priority", u32_type()),
+                ("Teb", u64_type()),
+                ("Stack", memory_descriptor.clone()),
+                ("ThreadContext", location_descriptor.clone()),
+            ],
+        );
+        let fixed_file_info = define_structure(
+            view,
+            "VS_FIXEDFILEINFO",
+            &[
+                ("dwSignature", u32_type()),
+                ("dwStrucVersion", u32_type()),
+                ("dwFileVersionMS", u32_type()),
+                ("dwFileVersionLS", u32_type()),
+                ("dwProductVersionMS", u32_type()),
+                ("dwProductVersionLS", u32_type()),
+                ("dwFileFlagsMask", u32_type()),
+                ("dwFileFlags", u32_type()),
+                ("dwFileOS", u32_type()),
+                ("dwFileType", u32_type()),
+                ("dwFileSubtype", u32_type()),
+                ("dwFileDateMS", u32_type()),
+                ("dwFileDateLS", u32_type()),
+            ],
+        );
+        let module = define_structure(
+            view,
+            "MINIDUMP_MODULE",
+            &[
+                ("BaseOfImage", u64_type()),
+                ("SizeOfImage", u32_type()),
+                ("CheckSum", u32_type()),
+                ("TimeDateStamp", u32_type()),
+                ("ModuleNameRva", u32_type()),
+                ("VersionInfo", fixed_file_info),
+                ("CvRecord", location_descriptor.clone()),
+                ("MiscRecord", location_descriptor.clone()),
+                ("Reserved0", u64_type()),
+                ("Reserved1", u64_type()),
+            ],
+        );
+        let unloaded_module = define_structure(
+            view,
+            "MINIDUMP_UNLOADED_MODULE",
+            &[
+                ("BaseOfImage", u64_type()),
+                ("SizeOfImage", u32_type()),
+                ("CheckSum", u32_type()),
+                ("TimeDateStamp", u32_type()),
+                ("ModuleNameRva", u32_type()),
+            ],
+        );
+        let memory_info = define_structure(
+            view,
+            "MINIDUMP_MEMORY_INFO",
+            &[
+                ("BaseAddress", u64_type()),
+                ("AllocationBase", u64_type()),
+                ("AllocationProtect", u32_type()),
+                ("__alignment1", u32_type()),
+                ("RegionSize", u64_type()),
+                ("State", u32_type()),
+                ("Protect", u32_type()),
+                ("Type", u32_type()),
+                ("__alignment2", u32_type()),
+            ],
+        );
+        let thread_name = define_structure(
+            view,
+            "MINIDUMP_THREAD_NAME",
+            &[("ThreadId", u32_type()), ("RvaOfThreadName", u64_type())],
+        );
+        let exception = define_structure(
+            view,
+            "MINIDUMP_EXCEPTION",
+            &[
+                ("ExceptionCode", u32_type()),
+                ("ExceptionFlags", u32_type()),
+                ("ExceptionRecord", u64_type()),
+                ("ExceptionAddress", u64_type()),
+                ("NumberParameters", u32_type()),
+                ("__unusedAlignment", u32_type()),
+                ("ExceptionInformation", array_type(&u64_type(), 15)),
+            ],
+        );
+        let exception_stream = define_structure(
+            view,
+            "MINIDUMP_EXCEPTION_STREAM",
+            &[
+                ("ThreadId", u32_type()),
+                ("__alignment", u32_type()),
+                ("ExceptionRecord", exception),
+                ("ThreadContext", location_descriptor),
+            ],
+        );
+        let system_info = define_structure(
+            view,
+            "MINIDUMP_SYSTEM_INFO",
+            &[
+                ("ProcessorArchitecture", u16_type()),
+                ("ProcessorLevel", u16_type()),
+                ("ProcessorRevision", u16_type()),
+                ("NumberOfProcessors", u8_type()),
+                ("ProductType", u8_type()),
+                ("MajorVersion", u32_type()),
+                ("MinorVersion", u32_type()),
+                ("BuildNumber", u32_type()),
+                ("PlatformId", u32_type()),
+                ("CSDVersionRva", u32_type()),
+                ("SuiteMask", u16_type()),
+                ("Reserved2", u16_type()),
+                ("Cpu", array_type(&u8_type(), 24)),
+            ],
+        );
+        let memory64_list = define_structure(
+            view,
+            "MINIDUMP_MEMORY64_LIST",
+            &[
+                ("NumberOfMemoryRanges", u64_type()),
+                ("BaseRva", u64_type()),
+            ],
+        );
+        let unloaded_module_list = define_structure(
+            view,
+            "MINIDUMP_UNLOADED_MODULE_LIST",
+            &[
+                ("SizeOfHeader", u32_type()),
+                ("SizeOfEntry", u32_type()),
+                ("NumberOfEntries", u32_type()),
+            ],
+        );
+        let memory_info_list = define_structure(
+            view,
+            "MINIDUMP_MEMORY_INFO_LIST",
+            &[
+                ("SizeOfHeader", u32_type()),
+                ("SizeOfEntry", u32_type()),
+                ("NumberOfEntries", u64_type()),
+            ],
+        );
+        let misc_info = define_structure(
+            view,
+            "MINIDUMP_MISC_INFO",
+            &[
+                ("SizeOfInfo", u32_type()),
+                ("Flags1", u32_type()),
+                ("ProcessId", u32_type()),
+                ("ProcessCreateTime", u32_type()),
+                ("ProcessUserTime", u32_type()),
+                ("ProcessKernelTime", u32_type()),
+            ],
+        );
+
+        let m128a = define_structure(view, "M128A", &[("Low", u64_type()), ("High", u64_type())]);
+        let floating_save_area = define_structure(
+            view,
+            "FLOATING_SAVE_AREA",
+            &[
+                ("ControlWord", u32_type()),
+                ("StatusWord", u32_type()),
+                ("TagWord", u32_type()),
+                ("ErrorOffset", u32_type()),
+                ("ErrorSelector", u32_type()),
+                ("DataOffset", u32_type()),
+                ("DataSelector", u32_type()),
+                ("RegisterArea", array_type(&u8_type(), 80)),
+                ("Cr0NpxState", u32_type()),
+            ],
+        );
+        let mut context_x86_members = vec![("ContextFlags", u32_type())];
+        for name in ["Dr0", "Dr1", "Dr2", "Dr3", "Dr6", "Dr7"] {
+            context_x86_members.push((name, u32_type()));
+        }
+        context_x86_members.push(("FloatSave", floating_save_area));
+        for name in [
+            "SegGs", "SegFs", "SegEs", "SegDs", "Edi", "Esi", "Ebx", "Edx", "Ecx", "Eax", "Ebp",
+            "Eip", "SegCs", "EFlags", "Esp", "SegSs",
+        ] {
+            context_x86_members.push((name, u32_type()));
+        }
+        context_x86_members.push(("ExtendedRegisters", array_type(&u8_type(), 512)));
+        let context_x86 = define_structure(view, "CONTEXT_X86", &context_x86_members);
+
+        let xmm_save_area = define_structure(
+            view,
+            "XMM_SAVE_AREA32",
+            &[
+                ("ControlWord", u16_type()),
+                ("StatusWord", u16_type()),
+                ("TagWord", u8_type()),
+                ("Reserved1", u8_type()),
+                ("ErrorOpcode", u16_type()),
+                ("ErrorOffset", u32_type()),
+                ("ErrorSelector", u16_type()),
+                ("Reserved2", u16_type()),
+                ("DataOffset", u32_type()),
+                ("DataSelector", u16_type()),
+                ("Reserved3", u16_type()),
+                ("MxCsr", u32_type()),
+                ("MxCsr_Mask", u32_type()),
+                ("FloatRegisters", array_type(&m128a, 8)),
+                ("XmmRegisters", array_type(&m128a, 16)),
+                ("Reserved4", array_type(&u8_type(), 96)),
+            ],
+        );
+        let mut context_amd64_members = Vec::new();
+        for name in ["P1Home", "P2Home", "P3Home", "P4Home", "P5Home", "P6Home"] {
+            context_amd64_members.push((name, u64_type()));
+        }
+        context_amd64_members.push(("ContextFlags", u32_type()));
+        context_amd64_members.push(("MxCsr", u32_type()));
+        for name in ["SegCs", "SegDs", "SegEs", "SegFs", "SegGs", "SegSs"] {
+            context_amd64_members.push((name, u16_type()));
+        }
+        context_amd64_members.push(("EFlags", u32_type()));
+        for name in [
+            "Dr0", "Dr1", "Dr2", "Dr3", "Dr6", "Dr7", "Rax", "Rcx", "Rdx", "Rbx", "Rsp", "Rbp",
+            "Rsi", "Rdi", "R8", "R9", "R10", "R11", "R12", "R13", "R14", "R15", "Rip",
+        ] {
+            context_amd64_members.push((name, u64_type()));
+        }
+        context_amd64_members.push(("FltSave", xmm_save_area));
+        context_amd64_members.push(("VectorRegister", array_type(&m128a, 26)));
+        for name in [
+            "VectorControl",
+            "DebugControl",
+            "LastBranchToRip",
+            "LastBranchFromRip",
+            "LastExceptionToRip",
+            "LastExceptionFromRip",
+        ] {
+            context_amd64_members.push((name, u64_type()));
+        }
+        let context_amd64 = define_structure(view, "CONTEXT_AMD64", &context_amd64_members);
+
+        let context_arm = define_structure(
+            view,
+            "CONTEXT_ARM",
+            &[
+                ("ContextFlags", u32_type()),
+                ("Iregs", array_type(&u32_type(), 16)),
+                ("Cpsr", u32_type()),
+                ("Fpscr", u64_type()),
+                ("FloatRegs", array_type(&u64_type(), 32)),
+                ("FloatExtra", array_type(&u32_type(), 8)),
+            ],
+        );
+        let context_arm64 = define_structure(
+            view,
+            "CONTEXT_ARM64",
+            &[
+                ("ContextFlags", u32_type()),
+                ("Cpsr", u32_type()),
+                ("X", array_type(&u64_type(), 29)),
+                ("Fp", u64_type()),
+                ("Lr", u64_type()),
+                ("Sp", u64_type()),
+                ("Pc", u64_type()),
+                ("V", array_type(&m128a, 32)),
+                ("Fpcr", u32_type()),
+                ("Fpsr", u32_type()),
+                ("Bcr", array_type(&u32_type(), 8)),
+                ("Bvr", array_type(&u64_type(), 8)),
+                ("Wcr", array_type(&u32_type(), 2)),
+                ("Wvr", array_type(&u64_type(), 2)),
+            ],
+        );
+
+        FileStructureTypes {
+            header,
+            directory,
+            thread,
+            module,
+            unloaded_module,
+            memory_descriptor,
+            memory_descriptor64,
+            memory64_list,
+            unloaded_module_list,
+            memory_info_list,
+            memory_info,
+            thread_name,
+            exception_stream,
+            system_info,
+            misc_info,
+            context_x86,
+            context_amd64,
+            context_arm,
+            context_arm64,
+        }
+    }
+}
+
+/// A range of the minidump file which is mapped into the metadata segment of the _Minidump_ view.
+#[derive(Debug, Clone)]
+struct MetadataMapping {
+    file_range: Range<u64>,
+    address: u64,
+}
+
+/// Where the structures found in the minidump file are defined.
+enum Placement<'a> {
+    /// At their offsets in the raw view of the minidump file.
+    RawView,
+    /// Nowhere; the ranges of the file which they occupy are only recorded,
+    /// to decide what to map into the metadata segment.
+    Record(RefCell<Vec<Range<u64>>>),
+    /// At the addresses which the metadata segment maps them to.
+    MetadataSegment(&'a [MetadataMapping]),
+}
+
+/// Applies the types of the minidump file format's structures as data variables,
+/// either at their offsets in the raw view of a minidump file, or in the metadata segment.
+struct FileStructureAnnotator<'a> {
+    view: &'a BinaryView,
+    dump_data: &'a [u8],
+    endian: Endian,
+    types: FileStructureTypes,
+    placement: Placement<'a>,
+}
+
+impl FileStructureAnnotator<'_> {
+    fn read_u32(&self, offset: u64) -> Option<u32> {
+        read_u32(self.dump_data, usize::try_from(offset).ok()?, self.endian)
+    }
+
+    fn read_u64(&self, offset: u64) -> Option<u64> {
+        read_u64(self.dump_data, usize::try_from(offset).ok()?, self.endian)
+    }
+
+    /// The address in the view of a range of the minidump file, if the whole range is placed in the view.
+    fn address_of(&self, file_range: Range<u64>) -> Option<u64> {
+        match &self.placement {
+            Placement::RawView => Some(file_range.start),
+            Placement::Record(recorded_ranges) => {
+                recorded_ranges.borrow_mut().push(file_range);
+                None
+            }
+            Placement::MetadataSegment(mappings) => mappings
+                .iter()
+                .find(|mapping| {
+                    mapping.file_range.start <= file_range.start
+                        && file_range.end <= mapping.file_range.end
+                })
+                .map(|mapping| mapping.address + (file_range.start - mapping.file_range.start)),
+        }
+    }
+
+    /// Define a data variable, and optionally a symbol for it, if the data variable lies within the file.
+    /// Returns whether the data variable lies within the file.
+    fn define_data_var(&self, offset: u64, data_type: &Type, name: Option<&str>) -> bool {
+        let Some(end) = offset.checked_add(data_type.width()) else {
+            return false;
+        };
+        if end > self.dump_data.len() as u64 {
+            debug!(
+                "Not annotating structure at {:#x} in the minidump file, since it extends past the end of the file",
+                offset
+            );
+            return false;
+        }
+        if let Some(address) = self.address_of(offset..end) {
+            self.view.define_auto_data_var(address, data_type);
+            if let Some(name) = name {
+                self.view
+                    .define_auto_symbol(&Symbol::builder(SymbolType::Data, name, address).create());
+            }
+        }
+        true
+    }
+
+    /// Define an array of `count` structures, clamping the count so that the array lies within the file.
+    fn define_array(&self, offset: u64, element_type: &Type, count: u64, name: Option<&str>) {
+        let element_size = element_type.width().max(1);
+        let available_count = (self.dump_data.len() as u64).saturating_sub(offset) / element_size;
+        let count = count.min(available_count);
+        if count > 0 {
+            self.define_data_var(offset, &array_type(element_type, count), name);
+        }
+    }
+
+    /// Define a `MINIDUMP_STRING`: a byte length, followed by a UTF-16 string of that length.
+    fn define_string(&self, offset: u64, name: Option<&str>) {
+        // An RVA of zero means that the string is absent.
+        let Some(length) = self.read_u32(offset).filter(|_| offset != 0) else {
+            return;
+        };
+        let string_type = structure_type(&[
+            ("Length", u32_type()),
+            (
+                "Buffer",
+                array_type(&Type::wide_char(2), u64::from(length / 2)),
+            ),
+        ]);
+        self.define_data_var(offset, &string_type, name);
+    }
+
+    /// Define the `CONTEXT` at a location, with the type for the CPU in its `ContextFlags`.
+    fn define_context(&self, location_offset: u64, name: &str) {
+        let (Some(data_size), Some(rva)) = (
+            self.read_u32(location_offset),
+            self.read_u32(location_offset + 4),
+        ) else {
+            return;
+        };
+        let rva = u64::from(rva);
+        let Some(context_flags) = self.read_u32(rva).filter(|_| rva != 0) else {
+            return;
+        };
+        let context_type = match context_flags & CONTEXT_CPU_MASK {
+            CONTEXT_X86 => &self.types.context_x86,
+            CONTEXT_AMD64 => &self.types.context_amd64,
+            CONTEXT_ARM => &self.types.context_arm,
+            CONTEXT_ARM64 => &self.types.context_arm64,
+            _ => {
+                self.define_array(rva, &u8_type(), u64::from(data_size), Some(name));
+                return;
+            }
+        };
+        if context_type.width() > u64::from(data_size)
+            || !self.define_data_var(rva, context_type, Some(name))
+        {
+            // Contexts without extended registers (e.g. 32-bit x86 contexts from older Breakpad versions)
+            // are shorter than the full structure.
+            self.define_array(rva, &u8_type(), u64::from(data_size), Some(name));
+        }
+    }
+
+    fn annotate(&self) {
+        // The metadata segment only holds the contents of some streams, not the file's own structures.
+        let is_raw_view = matches!(self.placement, Placement::RawView);
+        if is_raw_view {
+            self.define_data_var(0, &self.types.header, Some("MinidumpHeader"));
+            let (Some(stream_count), Some(stream_directory_rva)) =
+                (self.read_u32(8), self.read_u32(12))
+            else {
+                return;
+            };
+            self.define_array(
+                u64::from(stream_directory_rva),
+                &self.types.directory,
+                u64::from(stream_count),
+                Some("MinidumpStreamDirectory"),
+            );
+        }
+
+        for stream in read_stream_directory(self.dump_data, self.endian) {
+            let rva = u64::from(stream.rva);
+            if rva == 0 {
+                // e.g. `UnusedStream` entries, which have no data.
+                continue;
+            }
+            let stream_name = stream_type_name(stream.stream_type);
+            if !is_raw_view && !stream_name.is_some_and(|name| METADATA_STREAMS.contains(&name)) {
+                continue;
+            }
+            let stream_symbol_name = match stream_name {
+                Some(stream_name) => stream_name.to_string(),
+                None => format!("Stream_{:#x}", stream.stream_type),
+            };
+            match stream_name {
+                Some("ThreadListStream") => self.annotate_thread_list(rva),
+                Some("ModuleListStream") => self.annotate_module_list(rva),
+                Some("MemoryListStream") => self.annotate_memory_list(rva),
+                Some("ExceptionStream") => {
+                    self.define_data_var(rva, &self.types.exception_stream, None);
+                    // MINIDUMP_EXCEPTION_STREAM.ThreadContext
+                    self.define_context(rva + 0xa0, "exception_context");
+                }
+                Some("SystemInfoStream") => {
+                    self.define_data_var(rva, &self.types.system_info, None);
+                    // MINIDUMP_SYSTEM_INFO.CSDVersionRva
+                    if let Some(csd_version_rva) = self.read_u32(rva + 0x18) {
+                        self.define_string(u64::from(csd_version_rva), None);
+                    }
+                }
+                Some("Memory64ListStream") => self.annotate_memory64_list(rva),
+                Some("UnloadedModuleListStream") => self.annotate_unloaded_module_list(rva),
+                Some("MiscInfoStream") => {
+                    self.define_data_var(rva, &self.types.misc_info, None);
+                }
+                Some("MemoryInfoListStream") => self.annotate_memory_info_list(rva),
+                Some("ThreadNamesStream") => self.annotate_thread_names(rva),
+                _ => {
+                    self.define_array(rva, &u8_type(), u64::from(stream.data_size), None);
+                }
+            }
+            if let Some(address) = self.address_of(rva..rva + 1) {
+                self.view.define_auto_symbol(
+                    &Symbol::builder(SymbolType::Data, &stream_symbol_name, address).create(),
+                );
+            }
+        }
+    }
+
+    fn annotate_thread_list(&self, rva: u64) {
+        let Some(thread_count) = self.read_u32(rva) else {
+            return;
+        };
+        self.define_data_var(rva, &u32_type(), None);
+        let threads_offset = rva + 4;
+        self.define_array(
+            threads_offset,
+            &self.types.thread,
+            u64::from(thread_count),
+            None,
+        );
+        for index in 0..u64::from(thread_count) {
+            let thread_offset = threads_offset + index * self.types.thread.width();
+            let Some(thread_id) = self.read_u32(thread_offset) else {
+                break;
+            };
+            // MINIDUMP_THREAD.ThreadContext
+            self.define_context(
+                thread_offset + 0x28,
+                &format!("thread_{:#x}_context", thread_id),
+            );
+        }
+    }
+
+    fn annotate_module_list(&self, rva: u64) {
+        let Some(module_count) = self.read_u32(rva) else {
+            return;
+        };
+        self.define_data_var(rva, &u32_type(), None);
+        let modules_offset = rva + 4;
+        self.define_array(
+            modules_offset,
+            &self.types.module,
+            u64::from(module_count),
+            None,
+        );
+        for index in 0..u64::from(module_count) {
+            let module_offset = modules_offset + index * self.types.module.width();
+            // MINIDUMP_MODULE.ModuleNameRva
+            let Some(module_name_rva) = self.read_u32(module_offset + 0x14) else {
+                break;
+            };
+            self.define_string(u64::from(module_name_rva), None);
+            // MINIDUMP_MODULE.CvRecord
+            if let (Some(cv_record_size), Some(cv_record_rva)) = (
+                self.read_u32(module_offset + 0x4c),
+                self.read_u32(module_offset + 0x50),
+            ) {
+                self.annotate_cv_record(u64::from(cv_record_rva), u64::from(cv_record_size));
+            }
+        }
+    }
+
+    /// Define a CodeView record, as a `CV_INFO_PDB70` if it is one, or as bytes otherwise.
+    fn annotate_cv_record(&self, rva: u64, size: u64) {
+        if rva == 0 {
+            return;
+        }
+        if self.read_u32(rva) == Some(CV_SIGNATURE_RSDS) && size > 24 {
+            let cv_info_type = structure_type(&[
+                ("CvSignature", u32_type()),
+                ("Signature", array_type(&u8_type(), 16)),
+                ("Age", u32_type()),
+                ("PdbFileName", array_type(&Type::char(), size - 24)),
+            ]);
+            self.define_data_var(rva, &cv_info_type, None);
+        } else {
+            self.define_array(rva, &u8_type(), size, None);
+        }
+    }
+
+    fn annotate_memory_list(&self, rva: u64) {
+        let Some(range_count) = self.read_u32(rva) else {
+            return;
+        };
+        self.define_data_var(rva, &u32_type(), None);
+        self.define_array(
+            rva + 4,
+            &self.types.memory_descriptor,
+            u64::from(range_count),
+            None,
+        );
+    }
+
+    fn annotate_memory64_list(&self, rva: u64) {
+        let Some(range_count) = self.read_u64(rva) else {
+            return;
+        };
+        self.define_data_var(rva, &self.types.memory64_list, None);
+        self.define_array(rva + 16, &self.types.memory_descriptor64, range_count, None);
+    }
+
+    /// The header shared by the `MINIDUMP_UNLOADED_MODULE_LIST` and `MINIDUMP_MEMORY_INFO_LIST`
+    /// structures, which gives the size of the header and of each entry, since both can grow.
+    /// Returns the offset of the first entry, the size of each entry, and the number of entries.
+    fn read_list_header(&self, rva: u64, list_header_type: &Type) -> Option<(u64, u64, u64)> {
+        let header_size = u64::from(self.read_u32(rva)?);
+        let entry_size = u64::from(self.read_u32(rva + 4)?);
+        // `NumberOfEntries` is a 32-bit integer in `MINIDUMP_UNLOADED_MODULE_LIST`,
+        // and a 64-bit integer in `MINIDUMP_MEMORY_INFO_LIST`.
+        let entry_count = match list_header_type.width() {
+            16 => self.read_u64(rva + 8)?,
+            _ => u64::from(self.read_u32(rva + 8)?),
+        };
+        self.define_data_var(rva, list_header_type, None);
+        if entry_size == 0 {
+            return None;
+        }
+        // Don't trust the count of entries any further than the size of the file.
+        let entry_count = entry_count.min(self.dump_data.len() as u64 / entry_size);
+        Some((rva + header_size, entry_size, entry_count))
+    }
+
+    fn annotate_unloaded_module_list(&self, rva: u64) {
+        let Some((entries_offset, entry_size, entry_count)) =
+            self.read_list_header(rva, &self.types.unloaded_module_list)
+        else {
+            return;
+        };
+        if entry_size == self.types.unloaded_module.width() {
+            self.define_array(
+                entries_offset,
+                &self.types.unloaded_module,
+                entry_count,
+                None,
+            );
+        }
+        for index in 0..entry_count {
+            // MINIDUMP_UNLOADED_MODULE.ModuleNameRva
+            let Some(module_name_rva) = self.read_u32(entries_offset + index * entry_size + 0x14)
+            else {
+                break;
+            };
+            self.define_string(u64::from(module_name_rva), None);
+        }
+    }
+
+    fn annotate_memory_info_list(&self, rva: u64) {
+        let Some((entries_offset, entry_size, entry_count)) =
+            self.read_list_header(rva, &self.types.memory_info_list)
+        else {
+            return;
+        };
+        if entry_size == self.types.memory_info.width() {
+            self.define_array(entries_offset, &self.types.memory_info, entry_count, None);
+        }
+    }
+
+    fn annotate_thread_names(&self, rva: u64) {
+        let Some(thread_name_count) = self.read_u32(rva) else {
+            return;
+        };
+        self.define_data_var(rva, &u32_type(), None);
+        let thread_names_offset = rva + 4;
+        self.define_array(
+            thread_names_offset,
+            &self.types.thread_name,
+            u64::from(thread_name_count),
+            None,
+        );
+        for index in 0..u64::from(thread_name_count) {
+            // MINIDUMP_THREAD_NAME.RvaOfThreadName
+            let Some(thread_name_rva) =
+                self.read_u64(thread_names_offset + index * self.types.thread_name.width() + 4)
+            else {
+                break;
+            };
+            self.define_string(thread_name_rva, None);
+        }
+    }
+}
+
+/// The byte order of a minidump file, from the byte order of the signature in its header.
+fn file_endian(dump_data: &[u8]) -> Option<Endian> {
+    match read_u32(dump_data, 0, Endian::Little)? {
+        MINIDUMP_SIGNATURE => Some(Endian::Little),
+        signature if signature.swap_bytes() == MINIDUMP_SIGNATURE => Some(Endian::Big),
+        _ => None,
+    }
+}
+
+/// Annotate the raw view of a minidump file with the structures of the minidump file format:
+/// the header, the stream directory, and the contents of the streams (threads and their contexts,
+/// modules, memory lists, the exception, and so on), as data variables with types and symbols.
+///
+/// This only reads the raw data of the file, so that it works even for malformed minidumps
+/// which the minidump crate can't parse.
+pub fn annotate_file_structures(view: &BinaryView, dump_data: &[u8]) {
+    let Some(endian) = file_endian(dump_data) else {
+        return;
+    };
+    debug!("Annotating the structures of the minidump file in the parent view");
+    let annotator = FileStructureAnnotator {
+        view,
+        dump_data,
+        endian,
+        types: FileStructureTypes::define(view),
+        placement: Placement::RawView,
+    };
+    annotator.annotate();
+}
+
+/// Add a synthetic, read-only segment to the _Minidump_ view which maps the parts of the minidump file
+/// holding metadata that isn't in the process's address space: the thread list and every thread's
+/// `CONTEXT`, the exception record and its `CONTEXT`, the module list (with the module names and
+/// CodeView records), and the system information. These are annotated with the same types and symbols
+/// as in the raw view (e.g. `thread_0x1a2c_context`).
+///
+/// The segment starts at `base_address`, which should be past the end of all of the process's memory.
+/// Returns the address range of the segment, if one was added.
+pub fn add_metadata_segment(
+    view: &BinaryView,
+    dump_data: &[u8],
+    base_address: u64,
+    is_64bit: bool,
+) -> Option<Range<u64>> {
+    let endian = file_endian(dump_data)?;
+    let recorder = FileStructureAnnotator {
+        view,
+        dump_data,
+        endian,
+        types: FileStructureTypes::define(view),
+        placement: Placement::Record(RefCell::new(Vec::new())),
+    };
+
+    // Find the ranges of the file which hold metadata, and lay them out one after another,
+    // merging the ones which overlap or touch.
+    recorder.annotate();
+    let Placement::Record(recorded_ranges) = recorder.placement else {
+        return None;
+    };
+    let mut file_ranges = recorded_ranges.into_inner();
+    file_ranges.sort_by_key(|file_range| file_range.start);
+    let mut merged_file_ranges: Vec<Range<u64>> = Vec::new();
+    for file_range in file_ranges {
+        match merged_file_ranges.last_mut() {
+            Some(last_range) if file_range.start <= last_range.end => {
+                last_range.end = last_range.end.max(file_range.end);
+            }
+            _ => merged_file_ranges.push(file_range),
+        }
+    }
+    let mut mappings = Vec::new();
+    let mut next_address = base_address;
+    for file_range in merged_file_ranges {
+        let address = next_address;
+        next_address = address
+            .checked_add(file_range.end - file_range.start)?
+            .checked_next_multiple_of(METADATA_ALIGNMENT)?;
+        mappings.push(MetadataMapping {
+            file_range,
+            address,
+        });
+    }
+    if mappings.is_empty() {
+        return None;
+    }
+    let segment_range = base_address..next_address;
+    if !is_64bit && segment_range.end > u64::from(u32::MAX) + 1 {
+        warn!("Could not fit the metadata segment into the 32-bit address space of the minidump's process");
+        return None;
+    }
+
+    for mapping in mappings.iter() {
+        let length = mapping.file_range.end - mapping.file_range.start;
+        view.add_segment(
+            Segment::builder(mapping.address..mapping.address + length)
+                .parent_backing(mapping.file_range.clone())
+                .is_auto(true)
+                .flags(SegmentFlags::new().readable(true)),
+        );
+    }
+    view.add_section(
+        Section::builder(METADATA_SECTION_NAME, segment_range.clone())
+            .semantics(Semantics::ReadOnlyData)
+            .is_auto(true),
+    );
+
+    let annotator = FileStructureAnnotator {
+        view,
+        dump_data,
+        endian,
+        types: recorder.types,
+        placement: Placement::MetadataSegment(&mappings),
+    };
+    annotator.annotate();
+    Some(segment_range)
+}
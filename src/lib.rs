@@ -1,21 +1,95 @@
-use binaryninja::binary_view::BinaryView;
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
 use binaryninja::command::{register_command, Command};
 use binaryninja::custom_binary_view::register_view_type;
+use binaryninja::debuginfo::DebugInfoParser;
 use binaryninja::logger::Logger;
 use log::{debug, LevelFilter};
 
+use minidump_layout::streams;
+
 mod command;
+mod debug_info;
+mod dump_data;
+mod file_structures;
+mod model;
+mod overview;
 mod view;
+mod view_metadata;
+
+struct PrintStreamsCommand {
+    /// The stream type to print, or `None` to print every stream.
+    stream_type: Option<u32>,
+}
+
+impl Command for PrintStreamsCommand {
+    fn action(&self, binary_view: &BinaryView) {
+        command::print_streams(binary_view, self.stream_type);
+    }
+
+    fn valid(&self, binary_view: &BinaryView) -> bool {
+        binary_view.view_type().to_string() == view::VIEW_TYPE_NAME
+    }
+}
+
+struct ShowMinidumpOverviewCommand;
+
+impl Command for ShowMinidumpOverviewCommand {
+    fn action(&self, binary_view: &BinaryView) {
+        overview::show_minidump_overview(binary_view);
+    }
+
+    fn valid(&self, binary_view: &BinaryView) -> bool {
+        binary_view.view_type().to_string() == view::VIEW_TYPE_NAME
+    }
+}
+
+struct ShowThreadCallStacksCommand;
+
+impl Command for ShowThreadCallStacksCommand {
+    fn action(&self, binary_view: &BinaryView) {
+        command::show_thread_call_stacks(binary_view);
+    }
+
+    fn valid(&self, binary_view: &BinaryView) -> bool {
+        binary_view.view_type().to_string() == view::VIEW_TYPE_NAME
+    }
+}
+
+struct ShowThreadRegistersCommand;
+
+impl Command for ShowThreadRegistersCommand {
+    fn action(&self, binary_view: &BinaryView) {
+        command::show_thread_registers(binary_view);
+    }
 
-struct PrintMemoryInformationCommand;
+    fn valid(&self, binary_view: &BinaryView) -> bool {
+        binary_view.view_type().to_string() == view::VIEW_TYPE_NAME
+    }
+}
+
+struct ApplyRegisterValuesCommand {
+    all_threads: bool,
+}
+
+impl Command for ApplyRegisterValuesCommand {
+    fn action(&self, binary_view: &BinaryView) {
+        command::apply_register_values(binary_view, self.all_threads);
+    }
 
-impl Command for PrintMemoryInformationCommand {
+    fn valid(&self, binary_view: &BinaryView) -> bool {
+        binary_view.view_type().to_string() == view::VIEW_TYPE_NAME
+    }
+}
+
+struct ApplyLineCommentsCommand;
+
+impl Command for ApplyLineCommentsCommand {
     fn action(&self, binary_view: &BinaryView) {
-        command::print_memory_information(binary_view);
+        debug_info::apply_line_comments(binary_view);
     }
 
-    fn valid(&self, _binary_view: &BinaryView) -> bool {
-        true // TODO: Of course, the command will not always be valid!
+    fn valid(&self, binary_view: &BinaryView) -> bool {
+        binary_view.view_type().to_string() == view::VIEW_TYPE_NAME
     }
 }
 
@@ -27,13 +101,67 @@ pub extern "C" fn CorePluginInit() -> bool {
         .init();
 
     debug!("Registering minidump binary view type");
-    register_view_type("Minidump", "Minidump", view::MinidumpBinaryViewType::new);
+    register_view_type(
+        view::VIEW_TYPE_NAME,
+        "Minidump",
+        view::MinidumpBinaryViewType::new,
+    );
+
+    debug!("Registering minidump debug info parser");
+    debug_info::register_settings();
+    DebugInfoParser::register(
+        debug_info::DEBUG_INFO_PARSER_NAME,
+        debug_info::MinidumpDebugInfoParser,
+    );
 
     debug!("Registering minidump plugin commands");
     register_command(
-        "Minidump\\[DEBUG] Print Minidump Memory Information",
-        "Print a human-readable description of the contents of the MinidumpMemoryInfoList stream in the loaded minidump",
-        PrintMemoryInformationCommand {},
+        "Minidump\\Print Stream\\All Streams",
+        "Print a human-readable description of every stream in the loaded minidump to the log, like minidump-dump",
+        PrintStreamsCommand { stream_type: None },
+    );
+    for (stream_type, stream_name) in streams::printable_stream_types() {
+        register_command(
+            format!("Minidump\\Print Stream\\{}", stream_name).as_str(),
+            format!(
+                "Print a human-readable description of the {} stream in the loaded minidump to the log",
+                stream_name
+            )
+            .as_str(),
+            PrintStreamsCommand {
+                stream_type: Some(stream_type),
+            },
+        );
+    }
+    register_command(
+        "Minidump\\Minidump Overview",
+        "Show an overview of the loaded minidump as a report: system, process, exception, threads, modules, memory, and streams",
+        ShowMinidumpOverviewCommand {},
+    );
+    register_command(
+        "Minidump\\Show Thread Call Stacks",
+        "Unwind the call stack of every thread in the loaded minidump, and show the symbolized call stacks as a report",
+        ShowThreadCallStacksCommand {},
+    );
+    register_command(
+        "Minidump\\Show Thread Registers",
+        "Show the registers of every thread in the loaded minidump as a report, including vector registers",
+        ShowThreadRegistersCommand {},
+    );
+    register_command(
+        "Minidump\\Apply Crash Site Register Values",
+        "Inform the data flow analysis of the register values at the crash site, from the exception context in the loaded minidump",
+        ApplyRegisterValuesCommand { all_threads: false },
+    );
+    register_command(
+        "Minidump\\Apply Register Values for All Threads",
+        "Inform the data flow analysis of the register values at the crash site, and at the instruction pointer of every other thread in the loaded minidump",
+        ApplyRegisterValuesCommand { all_threads: true },
+    );
+    register_command(
+        "Minidump\\Apply Breakpad Line Comments",
+        "Comment the start of each source line's code with its file and line number, from the Breakpad symbol files of the modules in the loaded minidump, without overwriting existing comments",
+        ApplyLineCommentsCommand {},
     );
 
     true
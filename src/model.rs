@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use log::warn;
+use minidump::{
+    Minidump, MinidumpException, MinidumpLinuxMaps, MinidumpMemoryInfoList, MinidumpMiscInfo,
+    MinidumpModuleList, MinidumpSystemInfo, MinidumpThreadList, MinidumpThreadNames,
+    MinidumpUnloadedModuleList,
+};
+use self_cell::self_cell;
+
+use minidump_layout::extended_registers::XStateConfig;
+use minidump_layout::layout::DumpLayout;
+use minidump_layout::memory::DumpMemory;
+use minidump_layout::streams::{read_stream_directory, StreamDirectoryEntry};
+use minidump_layout::unwind::{StackWalker, ThreadStack};
+
+use crate::dump_data::DumpData;
+
+/// The streams of a minidump which borrow from the minidump's data.
+pub struct BorrowedStreams<'a> {
+    pub thread_list: Option<MinidumpThreadList<'a>>,
+    pub exception: Option<MinidumpException<'a>>,
+    pub memory_info_list: Option<MinidumpMemoryInfoList<'a>>,
+    pub linux_maps: Option<MinidumpLinuxMaps<'a>>,
+    /// The process memory captured in the minidump.
+    pub memory: DumpMemory<'a>,
+}
+
+self_cell!(
+    /// A parsed minidump, along with the streams which borrow from it.
+    struct ParsedMinidump {
+        owner: Minidump<'static, DumpData>,
+
+        #[covariant]
+        dependent: BorrowedStreams,
+    }
+);
+
+/// The parsed contents of a minidump, which the _Minidump_ view creates once when it is loaded,
+/// and which every command, report, and debug info parser then shares.
+///
+/// Every stream which is used by more than one part of the plugin is parsed once, up front,
+/// including the streams which borrow from the minidump's data (see [`BorrowedStreams`]).
+pub struct MinidumpModel {
+    parsed: ParsedMinidump,
+    /// The data of the minidump file, for resolving RVAs which the minidump crate doesn't.
+    pub dump_data: DumpData,
+    /// Every entry in the stream directory, including streams which the minidump crate doesn't know about.
+    pub stream_directory: Vec<StreamDirectoryEntry>,
+    pub system_info: Option<MinidumpSystemInfo>,
+    pub misc_info: Option<MinidumpMiscInfo>,
+    pub module_list: Option<MinidumpModuleList>,
+    pub unloaded_module_list: Option<MinidumpUnloadedModuleList>,
+    pub thread_names: Option<MinidumpThreadNames>,
+    /// The layout of the extended register state in the thread contexts, from the `MinidumpMiscInfo` stream.
+    pub xstate_config: Option<XStateConfig>,
+    /// The segments, modules, threads, and exception of the process, which the view is built from.
+    pub layout: DumpLayout,
+    /// Whether the return addresses in the call stacks of the threads other than the crashing thread
+    /// have been tagged, which happens the first time the call stacks of every thread are unwound.
+    pub other_thread_stacks_tagged: AtomicBool,
+}
+
+/// The models of the minidumps loaded in each _Minidump_ view, by the session ID of the view's file.
+fn models() -> &'static Mutex<HashMap<usize, Arc<MinidumpModel>>> {
+    static MODELS: OnceLock<Mutex<HashMap<usize, Arc<MinidumpModel>>>> = OnceLock::new();
+    MODELS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+impl MinidumpModel {
+    /// Parse a minidump from the data of a minidump file.
+    pub fn parse(dump_data: DumpData) -> Option<Self> {
+        let minidump = Minidump::read(dump_data.clone()).ok()?;
+        let stream_directory = read_stream_directory(&dump_data, minidump.endian);
+        let system_info = minidump.get_stream::<MinidumpSystemInfo>().ok();
+        let misc_info = minidump.get_stream::<MinidumpMiscInfo>().ok();
+        let module_list = minidump.get_stream::<MinidumpModuleList>().ok();
+        let unloaded_module_list = minidump.get_stream::<MinidumpUnloadedModuleList>().ok();
+        let thread_names = minidump.get_stream::<MinidumpThreadNames>().ok();
+        let xstate_config = XStateConfig::read(&minidump);
+        let layout = DumpLayout::read(&minidump);
+        let parsed = ParsedMinidump::new(minidump, |minidump| BorrowedStreams {
+            thread_list: minidump.get_stream::<MinidumpThreadList>().ok(),
+            exception: minidump.get_stream::<MinidumpException>().ok(),
+            memory_info_list: minidump.get_stream::<MinidumpMemoryInfoList>().ok(),
+            linux_maps: minidump.get_stream::<MinidumpLinuxMaps>().ok(),
+            memory: DumpMemory::from_minidump(minidump),
+        });
+        Some(MinidumpModel {
+            parsed,
+            dump_data,
+            stream_directory,
+            system_info,
+            misc_info,
+            module_list,
+            unloaded_module_list,
+            thread_names,
+            xstate_config,
+            layout,
+            other_thread_stacks_tagged: AtomicBool::new(false),
+        })
+    }
+
+    /// Share the model of the minidump loaded in a _Minidump_ view with everything else that works on the view.
+    pub fn cache(view: &BinaryView, model: Arc<MinidumpModel>) {
+        let session_id = view.file().session_id();
+        if let Ok(mut models) = models().lock() {
+            models.insert(session_id, model);
+        }
+    }
+
+    /// Forget the model of the minidump loaded in a _Minidump_ view, when the view is closed.
+    pub fn evict(view: &BinaryView) {
+        let session_id = view.file().session_id();
+        if let Ok(mut models) = models().lock() {
+            models.remove(&session_id);
+        }
+    }
+
+    /// Get the model of the minidump loaded in a _Minidump_ view, which the view created when it was loaded.
+    ///
+    /// Returns `None` if the view has no model (e.g. because it isn't a _Minidump_ view, or because
+    /// loading it failed), rather than parsing the minidump again behind the view's back.
+    pub fn for_view(view: &BinaryView) -> Option<Arc<MinidumpModel>> {
+        let session_id = view.file().session_id();
+        let model = models()
+            .lock()
+            .ok()
+            .and_then(|models| models.get(&session_id).cloned());
+        if model.is_none() {
+            warn!("Could not find the parsed minidump for this view; try reopening the minidump");
+        }
+        model
+    }
+
+    pub fn minidump(&self) -> &Minidump<'static, DumpData> {
+        self.parsed.borrow_owner()
+    }
+
+    fn streams(&self) -> &BorrowedStreams<'_> {
+        self.parsed.borrow_dependent()
+    }
+
+    pub fn thread_list(&self) -> Option<&MinidumpThreadList<'_>> {
+        self.streams().thread_list.as_ref()
+    }
+
+    pub fn exception(&self) -> Option<&MinidumpException<'_>> {
+        self.streams().exception.as_ref()
+    }
+
+    pub fn memory_info_list(&self) -> Option<&MinidumpMemoryInfoList<'_>> {
+        self.streams().memory_info_list.as_ref()
+    }
+
+    pub fn linux_maps(&self) -> Option<&MinidumpLinuxMaps<'_>> {
+        self.streams().linux_maps.as_ref()
+    }
+
+    /// The process memory captured in the minidump.
+    pub fn memory(&self) -> &DumpMemory<'_> {
+        &self.streams().memory
+    }
+
+    /// Unwind the call stack of every thread, or with `only_crashing_thread`, only the call stack
+    /// of the crashing thread. See [`StackWalker::walk_threads`].
+    pub fn walk_threads(&self, is_64bit: bool, only_crashing_thread: bool) -> Vec<ThreadStack> {
+        let (Some(system_info), Some(thread_list)) = (&self.system_info, self.thread_list()) else {
+            warn!("Could not unwind thread call stacks: the minidump has no valid MinidumpSystemInfo or MinidumpThreadList stream");
+            return Vec::new();
+        };
+        StackWalker::new(self.memory(), is_64bit, self.module_list.as_ref()).walk_threads(
+            thread_list,
+            self.exception(),
+            system_info,
+            self.misc_info.as_ref(),
+            only_crashing_thread,
+        )
+    }
+}
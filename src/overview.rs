@@ -0,0 +1,273 @@
+use std::fmt::Write;
+
+use log::debug;
+use minidump::Module;
+
+use binaryninja::binary_view::{BinaryView, BinaryViewBase, BinaryViewExt};
+
+use minidump_layout::layout::{describe_exception, module_file_name};
+use minidump_layout::linux::LinuxProcessInfo;
+use minidump_layout::streams::stream_type_name;
+
+use crate::model::MinidumpModel;
+
+/// The `MEMORY_BASIC_INFORMATION.State` of committed memory.
+const MEM_COMMIT: u32 = 0x1000;
+
+/// A Markdown link which navigates to an address in the view.
+fn address_link(address: u64) -> String {
+    format!("[`{:#x}`](binaryninja://?expr={:#x})", address, address)
+}
+
+/// Escape the characters in a string which would break a Markdown table cell.
+fn escape_table_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Show an overview of the contents of the minidump as a report, with sections for the system,
+/// the process, the exception, the threads, the modules, the captured memory, and the stream directory.
+///
+/// The report is shown for the view, so that its address links navigate within the view.
+pub fn show_minidump_overview(bv: &BinaryView) {
+    debug!("Showing minidump overview");
+    let Some(model) = MinidumpModel::for_view(bv) else {
+        return;
+    };
+    let dump_memory = model.memory();
+    let minidump_system_info = model.system_info.as_ref();
+    let minidump_misc_info = model.misc_info.as_ref();
+    let minidump_module_list = model.module_list.as_ref();
+    let mut report = String::from("# Minidump Overview\n\n");
+
+    let _ = writeln!(report, "## System\n");
+    match minidump_system_info {
+        Some(minidump_system_info) => {
+            let raw = &minidump_system_info.raw;
+            let _ = writeln!(report, "- OS: {:?}", minidump_system_info.os);
+            let _ = write!(
+                report,
+                "- OS version: {}.{}.{}",
+                raw.major_version, raw.minor_version, raw.build_number
+            );
+            if let Some(csd_version) = minidump_system_info.csd_version() {
+                let _ = write!(report, " {}", csd_version);
+            }
+            let _ = writeln!(report);
+            let _ = writeln!(report, "- CPU: {:?}", minidump_system_info.cpu);
+            if let Some(cpu_info) = minidump_system_info.cpu_info() {
+                let _ = writeln!(report, "- CPU information: {}", cpu_info);
+            }
+            let _ = writeln!(report, "- Processor count: {}", raw.number_of_processors);
+        }
+        None => {
+            let _ = writeln!(report, "The minidump has no valid `SystemInfoStream`.");
+        }
+    }
+    let _ = writeln!(report);
+
+    let _ = writeln!(report, "## Process\n");
+    let _ = writeln!(
+        report,
+        "- Minidump written at: {} (Unix time)",
+        model.minidump().header.time_date_stamp
+    );
+    if let Some(minidump_misc_info) = minidump_misc_info {
+        if let Some(process_id) = minidump_misc_info.raw.process_id() {
+            let _ = writeln!(report, "- Process ID: {}", process_id);
+        }
+        if let Some(process_create_time) = minidump_misc_info.raw.process_create_time() {
+            let _ = writeln!(
+                report,
+                "- Process created at: {} (Unix time)",
+                process_create_time
+            );
+        }
+    }
+    if let Some(main_module) =
+        minidump_module_list.and_then(|module_list| module_list.main_module())
+    {
+        let _ = writeln!(report, "- Main module: `{}`", main_module.name);
+    }
+    if let Some(linux_info) =
+        LinuxProcessInfo::read(model.minidump(), &model.dump_data, bv.address_size() == 8)
+    {
+        if !linux_info.command_line.is_empty() {
+            let _ = writeln!(
+                report,
+                "- Command line: `{}`",
+                linux_info.command_line.join(" ")
+            );
+        }
+        if let Some(lsb_release) = &linux_info.lsb_release {
+            let _ = writeln!(
+                report,
+                "- Linux distribution: {}",
+                escape_table_cell(lsb_release.trim())
+            );
+        }
+    }
+    let _ = writeln!(report);
+
+    let _ = writeln!(report, "## Exception\n");
+    match model.exception() {
+        Some(minidump_exception) => {
+            let _ = writeln!(
+                report,
+                "- Exception: {}",
+                describe_exception(minidump_exception, minidump_system_info)
+            );
+            let _ = writeln!(
+                report,
+                "- Exception code: `{:#x}`",
+                minidump_exception.raw.exception_record.exception_code
+            );
+            let _ = writeln!(
+                report,
+                "- Crash site: {}",
+                address_link(minidump_exception.raw.exception_record.exception_address)
+            );
+            let _ = writeln!(
+                report,
+                "- Thread: `{:#x}`",
+                minidump_exception.raw.thread_id
+            );
+        }
+        None => {
+            let _ = writeln!(
+                report,
+                "The minidump has no `ExceptionStream`, so it may not have been written because of a crash."
+            );
+        }
+    }
+    let _ = writeln!(report);
+
+    let _ = writeln!(report, "## Threads\n");
+    if let Some(minidump_thread_list) = model.thread_list() {
+        let minidump_thread_names = model.thread_names.as_ref();
+        let _ = writeln!(
+            report,
+            "| Thread | Name | Instruction pointer | Stack pointer | Stack |\n| --- | --- | --- | --- | --- |"
+        );
+        for thread in minidump_thread_list.threads.iter() {
+            let thread_id = thread.raw.thread_id;
+            let thread_name = minidump_thread_names
+                .and_then(|thread_names| thread_names.get_name(thread_id))
+                .map(|thread_name| escape_table_cell(&thread_name))
+                .unwrap_or_default();
+            let context = minidump_system_info.and_then(|minidump_system_info| {
+                thread.context(minidump_system_info, minidump_misc_info)
+            });
+            let (instruction_pointer, stack_pointer) = match &context {
+                Some(context) => (
+                    address_link(context.get_instruction_pointer()),
+                    address_link(context.get_stack_pointer()),
+                ),
+                None => (String::new(), String::new()),
+            };
+            let stack_start = thread.raw.stack.start_of_memory_range;
+            let stack_size = thread.raw.stack.memory.data_size as u64;
+            let _ = writeln!(
+                report,
+                "| `{:#x}` | {} | {} | {} | {} - `{:#x}` |",
+                thread_id,
+                thread_name,
+                instruction_pointer,
+                stack_pointer,
+                address_link(stack_start),
+                stack_start.saturating_add(stack_size),
+            );
+        }
+    } else {
+        let _ = writeln!(report, "The minidump has no valid `ThreadListStream`.");
+    }
+    let _ = writeln!(report);
+
+    let _ = writeln!(report, "## Modules\n");
+    if let Some(minidump_module_list) = minidump_module_list {
+        let _ = writeln!(
+            report,
+            "| Base address | Size | Name | Version | Debug file | Debug identifier | Code identifier |\n| --- | --- | --- | --- | --- | --- | --- |"
+        );
+        for module_info in minidump_module_list.by_addr() {
+            let _ = writeln!(
+                report,
+                "| {} | `{:#x}` | {} | {} | {} | {} | {} |",
+                address_link(module_info.base_address()),
+                module_info.size(),
+                escape_table_cell(&module_info.name),
+                module_info.version().unwrap_or_default(),
+                module_info
+                    .debug_file()
+                    .map(|debug_file| escape_table_cell(module_file_name(&debug_file)))
+                    .unwrap_or_default(),
+                module_info
+                    .debug_identifier()
+                    .map(|debug_id| format!("`{}`", debug_id.breakpad()))
+                    .unwrap_or_default(),
+                module_info
+                    .code_identifier()
+                    .map(|code_id| format!("`{}`", code_id))
+                    .unwrap_or_default(),
+            );
+        }
+    } else {
+        let _ = writeln!(report, "The minidump has no valid `ModuleListStream`.");
+    }
+    if let Some(minidump_unloaded_module_list) = &model.unloaded_module_list {
+        let _ = writeln!(
+            report,
+            "\n### Unloaded Modules\n\n| Base address | Size | Name |\n| --- | --- | --- |"
+        );
+        for unloaded_module_info in minidump_unloaded_module_list.iter() {
+            let _ = writeln!(
+                report,
+                "| {} | `{:#x}` | {} |",
+                address_link(unloaded_module_info.base_address()),
+                unloaded_module_info.size(),
+                escape_table_cell(&unloaded_module_info.name),
+            );
+        }
+    }
+    let _ = writeln!(report);
+
+    let _ = writeln!(report, "## Memory\n");
+    let _ = writeln!(
+        report,
+        "- Captured memory: {} regions, `{:#x}` bytes",
+        dump_memory.region_count(),
+        dump_memory.total_size()
+    );
+    if let Some(minidump_memory_info_list) = model.memory_info_list() {
+        let mut region_count = 0;
+        let mut committed_size: u64 = 0;
+        for memory_info in minidump_memory_info_list.iter() {
+            region_count += 1;
+            if memory_info.raw.state == MEM_COMMIT {
+                committed_size = committed_size.saturating_add(memory_info.raw.region_size);
+            }
+        }
+        let _ = writeln!(
+            report,
+            "- Memory regions in the process's address space: {}, with `{:#x}` bytes committed",
+            region_count, committed_size
+        );
+    }
+    let _ = writeln!(report);
+
+    let _ = writeln!(
+        report,
+        "## Stream Directory\n\n| Stream type | Name | Offset | Size |\n| --- | --- | --- | --- |"
+    );
+    for stream in model.stream_directory.iter() {
+        let _ = writeln!(
+            report,
+            "| `{:#x}` | {} | `{:#x}` | `{:#x}` |",
+            stream.stream_type,
+            stream_type_name(stream.stream_type).unwrap_or("(unknown)"),
+            stream.rva,
+            stream.data_size,
+        );
+    }
+
+    bv.show_markdown_report("Minidump Overview", &report, &report);
+}
@@ -1,14 +1,14 @@
-use std::collections::HashMap;
-use std::ops::{Deref, Range};
+use std::collections::HashSet;
+use std::ops::Range;
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use binaryninja::section::Section;
+use binaryninja::section::{Section, Semantics};
 use binaryninja::segment::{Segment, SegmentFlags};
 use log::{debug, error, info, warn};
-use minidump::format::MemoryProtection;
 use minidump::{
-    Minidump, MinidumpMemory64List, MinidumpMemoryInfoList, MinidumpMemoryList, MinidumpModuleList,
-    MinidumpStream, MinidumpSystemInfo, Module,
+    MinidumpLinuxMaps, MinidumpModuleList, MinidumpSystemInfo, MinidumpUnloadedModuleList, Module,
 };
 
 use binaryninja::architecture::Architecture;
@@ -17,36 +17,72 @@ use binaryninja::custom_binary_view::{
     BinaryViewType, BinaryViewTypeBase, CustomBinaryView, CustomBinaryViewType, CustomView,
     CustomViewBuilder,
 };
-use binaryninja::data_buffer::DataBuffer;
+use binaryninja::main_thread::execute_on_main_thread;
 use binaryninja::platform::Platform;
+use binaryninja::rc::Ref;
+use binaryninja::settings::{QueryOptions, Settings};
+use binaryninja::symbol::{Symbol, SymbolType};
+use binaryninja::tags::TagType;
 use binaryninja::Endianness;
 
+use minidump_layout::elf::ElfHeader;
+use minidump_layout::layout::{
+    module_file_name, DumpLayout, MD_CPU_ARCHITECTURE_RISCV, MD_CPU_ARCHITECTURE_RISCV64,
+};
+use minidump_layout::linux::{LinuxProcessInfo, AT_ENTRY};
+use minidump_layout::macho::MachHeader;
+use minidump_layout::memory::DumpMemory;
+use minidump_layout::pe::{ExportIndex, ExportTarget, ImportName, PeHeaders};
+use minidump_layout::unwind::ThreadStack;
+
+use crate::dump_data::DumpData;
+use crate::file_structures::{add_metadata_segment, annotate_file_structures};
+use crate::model::MinidumpModel;
+use crate::view_metadata::store_view_metadata;
+
 type BinaryViewResult<R> = binaryninja::binary_view::Result<R>;
 
-/// A wrapper around a `binaryninja::databuffer::DataBuffer`, from which a `[u8]` buffer can be obtained
-/// to pass to `minidump::Minidump::read`.
-///
-/// This code is taken from [`dwarfdump`](https://github.com/Vector35/binaryninja-api/blob/9d8bc846bd213407fb1a7a19af2a96f17501ac3b/rust/examples/dwarfdump/src/lib.rs#L81)
-/// in the Rust API examples.
-#[derive(Clone)]
-pub struct DataBufferWrapper {
-    inner: Arc<DataBuffer>,
-}
+pub const VIEW_TYPE_NAME: &str = "Minidump";
 
-impl DataBufferWrapper {
-    pub fn new(buf: DataBuffer) -> Self {
-        DataBufferWrapper {
-            inner: Arc::new(buf),
-        }
-    }
-}
+/// The load setting which lets the user force the platform of a minidump,
+/// instead of using the platform from the `MinidumpSystemInfo` stream.
+const PLATFORM_OVERRIDE_SETTING: &str = "loader.minidump.platform";
 
-impl Deref for DataBufferWrapper {
-    type Target = [u8];
-    fn deref(&self) -> &Self::Target {
-        self.inner.get_data()
-    }
-}
+/// The load setting which adds a synthetic segment mapping the metadata in the minidump file
+/// (thread contexts, the exception record, the module list, and the system information) into the view.
+const METADATA_SEGMENT_SETTING: &str = "loader.minidump.metadataSegment";
+
+/// The alignment of the start of the metadata segment, after the end of the process's memory.
+const METADATA_SEGMENT_ALIGNMENT: u64 = 0x1_0000;
+
+/// The tag type for import address table slots which don't point to the export they import.
+const IAT_MISMATCH_TAG_TYPE: &str = "Minidump IAT Mismatch";
+
+/// The tag type for the instruction pointer of each thread in the minidump.
+const THREAD_TAG_TYPE: &str = "Minidump Thread";
+
+/// The tag type for the address at which the exception in the `MinidumpException` stream happened.
+const CRASH_SITE_TAG_TYPE: &str = "Crash Site";
+
+/// The name and icon of Binary Ninja's built-in tag type for bookmarks.
+const BOOKMARKS_TAG_TYPE: &str = "Bookmarks";
+const BOOKMARKS_TAG_ICON: &str = "📌";
+
+/// The size of the thread environment block (TEB) reserved for each thread on Windows,
+/// which is one page on 32-bit Windows and two pages on 64-bit Windows.
+const TEB_SIZE_32: u64 = 0x1000;
+const TEB_SIZE_64: u64 = 0x2000;
+
+/// How often to check whether the view has finished loading, before navigating to the crash site.
+const NAVIGATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long to wait for the view to finish loading, before giving up on navigating to the crash site.
+const NAVIGATION_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// The tag type for the return addresses in the unwound call stack of each thread.
+const STACK_FRAME_TAG_TYPE: &str = "Minidump Stack Frame";
+
+/// The tag type for the ranges of modules which were unloaded before the minidump was written.
+const UNLOADED_MODULE_TAG_TYPE: &str = "Minidump Unloaded Module";
 
 /// The _Minidump_ binary view type, which the Rust plugin registers with the Binary Ninja core
 /// (via `binaryninja::custombinaryview::register_view_type`) as a possible binary view
@@ -78,6 +114,43 @@ impl BinaryViewTypeBase for MinidumpBinaryViewType {
         false
     }
 
+    fn load_settings_for_data(&self, data: &BinaryView) -> Option<Ref<Settings>> {
+        let load_settings = self.default_load_settings_for_data(data)?;
+
+        // Offer every platform known to Binary Ninja (including the architecture-only platforms),
+        // with an empty value meaning that the platform should be determined automatically.
+        let mut platform_names = vec![String::from("\"\"")];
+        for platform in Platform::list_all().iter() {
+            platform_names.push(format!("\"{}\"", platform.name()));
+        }
+        load_settings.register_setting_json(
+            PLATFORM_OVERRIDE_SETTING,
+            &format!(
+                r#"{{
+                    "title": "Platform Override",
+                    "type": "string",
+                    "default": "",
+                    "enum": [{}],
+                    "description": "Force the platform (OS and architecture) of the minidump, instead of using the platform from the MinidumpSystemInfo stream. Leave empty to determine the platform automatically.",
+                    "ignore": ["SettingsProjectScope", "SettingsUserScope"]
+                }}"#,
+                platform_names.join(", "),
+            ),
+        );
+        load_settings.register_setting_json(
+            METADATA_SEGMENT_SETTING,
+            r#"{
+                "title": "Map Minidump Metadata",
+                "type": "boolean",
+                "default": false,
+                "description": "Add a synthetic read-only segment after the end of the process's memory, which maps the thread contexts, exception record, module list, and system information from the minidump file, with types and symbols applied.",
+                "ignore": ["SettingsProjectScope", "SettingsUserScope"]
+            }"#,
+        );
+
+        Some(load_settings)
+    }
+
     fn is_valid_for(&self, data: &BinaryView) -> bool {
         let mut magic_number = Vec::<u8>::new();
         data.read_into_vec(&mut magic_number, 0, 4);
@@ -99,193 +172,117 @@ impl CustomBinaryViewType for MinidumpBinaryViewType {
     }
 }
 
-#[derive(Debug)]
-struct SegmentData {
-    rva_range: Range<u64>,
-    mapped_addr_range: Range<u64>,
-}
-
-impl SegmentData {
-    fn from_addresses_and_size(rva: u64, mapped_addr: u64, size: u64) -> Self {
-        SegmentData {
-            rva_range: Range {
-                start: rva,
-                end: rva + size,
-            },
-            mapped_addr_range: Range {
-                start: mapped_addr,
-                end: mapped_addr + size,
-            },
-        }
-    }
-}
-
-#[derive(Debug)]
-struct SegmentMemoryProtection {
-    readable: bool,
-    writable: bool,
-    executable: bool,
-}
-
 /// An instance of the actual _Minidump_ custom binary view.
 /// This contains the main logic to load the memory segments inside a minidump file into the binary view.
 pub struct MinidumpBinaryView {
     /// The handle to the "real" BinaryView object, in the Binary Ninja core.
     inner: binaryninja::rc::Ref<BinaryView>,
+    /// The entry point of the minidump's main module, or 0 if it could not be found.
+    entry_point: u64,
 }
 
 impl MinidumpBinaryView {
     fn new(view: &BinaryView) -> Self {
         MinidumpBinaryView {
             inner: view.to_owned(),
+            entry_point: 0,
         }
     }
 
-    fn init(&self) -> BinaryViewResult<()> {
+    fn init(&mut self) -> BinaryViewResult<()> {
         let parent_view = self.parent_view().ok_or(())?;
-        let read_buffer = parent_view.read_buffer(0, parent_view.len() as usize)?;
-        let read_buffer = DataBufferWrapper::new(read_buffer);
+        let dump_data = DumpData::read(&parent_view).ok_or(())?;
+
+        // Annotate the minidump file format itself in the raw parent view, before parsing the minidump,
+        // so that malformed minidumps which can't be loaded can still be inspected.
+        annotate_file_structures(&parent_view, &dump_data);
+
+        // Some streams (e.g. LinuxDsoDebug) refer to data elsewhere in the file by RVA,
+        // which the minidump crate doesn't resolve for us, so keep the data of the file around too.
+        if let Some(model) = MinidumpModel::parse(dump_data.clone()) {
+            // Every command, report, and the debug info parser share this parse of the minidump
+            let model = Arc::new(model);
+            MinidumpModel::cache(self.as_ref(), model.clone());
+            let minidump_obj = model.minidump();
 
-        if let Ok(minidump_obj) = Minidump::read(read_buffer) {
             // Architecture, platform information
-            if let Ok(minidump_system_info) = minidump_obj.get_stream::<MinidumpSystemInfo>() {
-                if let Some(platform) = MinidumpBinaryView::translate_minidump_platform(
-                    minidump_system_info.cpu,
-                    minidump_obj.endian,
-                    minidump_system_info.os,
-                ) {
-                    self.set_default_platform(&platform);
-                } else {
-                    error!(
-                        "Could not parse valid system information from minidump: could not map system information in MinidumpSystemInfo stream (arch {:?}, endian {:?}, os {:?}) to a known architecture",
-                        minidump_system_info.cpu,
+            let dump_memory = model.memory();
+            let platform = self
+                .platform_override()
+                .or_else(|| {
+                    MinidumpBinaryView::platform_from_system_info(
+                        model.system_info.as_ref(),
                         minidump_obj.endian,
-                        minidump_system_info.os,
-                    );
-                    return Err(());
-                }
-            } else {
-                error!("Could not parse system information from minidump: could not find a valid MinidumpSystemInfo stream");
+                    )
+                })
+                .or_else(|| {
+                    MinidumpBinaryView::platform_from_main_module(
+                        model.module_list.as_ref(),
+                        dump_memory,
+                    )
+                });
+            let Some(platform) = platform else {
+                error!("Could not determine the platform of the minidump; set a platform manually in the load options for the Minidump view");
                 return Err(());
-            }
+            };
+            info!("Using platform {}", platform.name());
+            self.set_default_platform(&platform);
+            let is_64bit = platform.arch().address_size() == 8;
 
-            // Memory segments
-            let mut segment_data = Vec::<SegmentData>::new();
-
-            // Memory segments in a full memory dump (MinidumpMemory64List)
-            // Grab the shared base RVA for all entries in the MinidumpMemory64List,
-            // since the minidump crate doesn't expose this to us
-            if let Ok(raw_stream) = minidump_obj.get_raw_stream(MinidumpMemory64List::STREAM_TYPE) {
-                if let Ok(base_rva_array) = raw_stream[8..16].try_into() {
-                    let base_rva = u64::from_le_bytes(base_rva_array);
-                    debug!("Found BaseRVA value {:#x}", base_rva);
-
-                    if let Ok(minidump_memory_list) =
-                        minidump_obj.get_stream::<MinidumpMemory64List>()
-                    {
-                        let mut current_rva = base_rva;
-                        for memory_segment in minidump_memory_list.iter() {
-                            debug!(
-                            "Found memory segment at RVA {:#x} with virtual address {:#x} and size {:#x}",
-                            current_rva,
-                            memory_segment.base_address,
-                            memory_segment.size,
-                        );
-                            segment_data.push(SegmentData::from_addresses_and_size(
-                                current_rva.clone(),
-                                memory_segment.base_address,
-                                memory_segment.size,
-                            ));
-                            current_rva = current_rva + memory_segment.size;
-                        }
-                    }
-                } else {
-                    error!("Could not parse BaseRVA value shared by all entries in the MinidumpMemory64List stream")
+            // Linux-specific process information, from Breakpad / Crashpad minidumps
+            let linux_info = LinuxProcessInfo::read(minidump_obj, &dump_data, is_64bit);
+            if let Some(linux_info) = &linux_info {
+                info!(
+                    "Found Linux process information in minidump: command line {:?}, {} environment variables, {} auxiliary vector entries, {} shared objects in link map",
+                    linux_info.command_line,
+                    linux_info.environment.len(),
+                    linux_info.auxv.len(),
+                    linux_info.link_map.len(),
+                );
+                if let Some(lsb_release) = &linux_info.lsb_release {
+                    info!(
+                        "Found Linux distribution information in minidump: {}",
+                        lsb_release.trim()
+                    );
                 }
-            } else {
-                warn!("Could not read memory from minidump: could not find a valid MinidumpMemory64List stream. This minidump may not be a full memory dump. Trying to find partial dump memory from a MinidumpMemoryList now...");
-                // Memory segments in a regular memory dump (MinidumpMemoryList),
-                // i.e. one that does not include the full process memory data.
-                if let Ok(minidump_memory_list) = minidump_obj.get_stream::<MinidumpMemoryList>() {
-                    for memory_segment in minidump_memory_list.by_addr() {
-                        debug!(
-                            "Found memory segment at RVA {:#x} with virtual address {:#x} and size {:#x}",
-                            memory_segment.desc.memory.rva,
-                            memory_segment.base_address,
-                            memory_segment.size
-                        );
-                        segment_data.push(SegmentData::from_addresses_and_size(
-                            memory_segment.desc.memory.rva as u64,
-                            memory_segment.base_address,
-                            memory_segment.size,
-                        ));
-                    }
-                } else {
-                    error!("Could not read any memory from minidump: could not find a valid MinidumpMemory64List stream or a valid MinidumpMemoryList stream.");
+                if let Some(proc_status) = &linux_info.proc_status {
+                    debug!("Found process status in minidump:\n{}", proc_status);
                 }
-            }
-
-            // Memory protection information
-            let mut segment_protection_data = HashMap::new();
-
-            if let Ok(minidump_memory_info_list) =
-                minidump_obj.get_stream::<MinidumpMemoryInfoList>()
-            {
-                for memory_info in minidump_memory_info_list.iter() {
-                    if let Some(memory_range) = memory_info.memory_range() {
-                        debug!(
-                            "Found memory protection info for memory segment ranging from virtual address {:#x} to {:#x}: {:#?}",
-                            memory_range.start,
-                            memory_range.end,
-                            memory_info.protection
-                        );
-                        segment_protection_data.insert(
-                            // The range returned to us by MinidumpMemoryInfoList is an
-                            // end-inclusive range_map::Range; we need to add 1 to
-                            // the end index to make it into an end-exclusive std::ops::Range.
-                            Range {
-                                start: memory_range.start,
-                                end: memory_range.end + 1,
-                            },
-                            memory_info.protection,
-                        );
-                    }
+                if let Some(cpu_info) = &linux_info.cpu_info {
+                    debug!("Found CPU information in minidump:\n{}", cpu_info);
                 }
             }
 
-            for segment in segment_data.iter() {
-                if let Some(segment_protection) =
-                    segment_protection_data.get(&segment.mapped_addr_range)
-                {
-                    let segment_memory_protection =
-                        MinidumpBinaryView::translate_memory_protection(*segment_protection);
-
+            // Memory segments, and their protections
+            let layout = &model.layout;
+            for segment in layout.segments.iter() {
+                if let Some(segment_protection) = segment.protection {
                     info!(
                         "Adding memory segment at virtual address {:#x} to {:#x}, from data range {:#x} to {:#x}, with protections readable {}, writable {}, executable {}",
-                         segment.mapped_addr_range.start,
-                         segment.mapped_addr_range.end,
-                         segment.rva_range.start,
-                         segment.rva_range.end,
-                         segment_memory_protection.readable,
-                         segment_memory_protection.writable,
-                         segment_memory_protection.executable,
+                         segment.address_range.start,
+                         segment.address_range.end,
+                         segment.backing_range.start,
+                         segment.backing_range.end,
+                         segment_protection.readable,
+                         segment_protection.writable,
+                         segment_protection.executable,
                     );
 
                     let segment_flags = SegmentFlags::new()
-                        .readable(segment_memory_protection.readable)
-                        .writable(segment_memory_protection.writable)
-                        .executable(segment_memory_protection.executable);
+                        .readable(segment_protection.readable)
+                        .writable(segment_protection.writable)
+                        .executable(segment_protection.executable);
 
                     self.add_segment(
-                        Segment::builder(segment.mapped_addr_range.clone())
-                            .parent_backing(segment.rva_range.clone())
+                        Segment::builder(segment.address_range.clone())
+                            .parent_backing(segment.backing_range.clone())
                             .is_auto(true)
                             .flags(segment_flags),
                     );
                 } else {
                     warn!(
-                        "Could not find memory protection information for memory segment from {:#x} to {:#x}; segment will be added as readable, writable, and executable (RWX)", segment.mapped_addr_range.start,
-                        segment.mapped_addr_range.end,
+                        "Could not find memory protection information for memory segment from {:#x} to {:#x}; segment will be added as readable, writable, and executable (RWX)", segment.address_range.start,
+                        segment.address_range.end,
                     );
 
                     let segment_flags = SegmentFlags::new()
@@ -294,8 +291,8 @@ impl MinidumpBinaryView {
                         .executable(true);
 
                     self.add_segment(
-                        Segment::builder(segment.mapped_addr_range.clone())
-                            .parent_backing(segment.rva_range.clone())
+                        Segment::builder(segment.address_range.clone())
+                            .parent_backing(segment.backing_range.clone())
                             // In order to allow the user to actually edit the segment
                             // and manually adjust the permissions here to the correct ones,
                             // we need to set `is_auto` to false.
@@ -309,101 +306,907 @@ impl MinidumpBinaryView {
             // This stretches the concept a bit, but we can add each module as a
             // separate "section" of the binary.
             // Sections can be named, and can span multiple segments.
-            if let Ok(minidump_module_list) = minidump_obj.get_stream::<MinidumpModuleList>() {
-                for module_info in minidump_module_list.by_addr() {
+            for module in layout.modules.iter() {
+                info!(
+                    "Found module with name {} at virtual address {:#x} with size {:#x}",
+                    module.name,
+                    module.address_range.start,
+                    module.address_range.end - module.address_range.start,
+                );
+                self.add_section(
+                    Section::builder(module.name.clone(), module.address_range.clone())
+                        .is_auto(true),
+                );
+
+                // The real sections of the module, from its mapped PE headers
+                if let Some(pe_headers) = PeHeaders::read(dump_memory, module.address_range.start) {
+                    self.add_pe_sections(&module.name, &pe_headers);
+                }
+            }
+
+            // Modules which were unloaded before the minidump was written.
+            // Their code is often still mapped, and jumps into it are a common cause of crashes.
+            self.add_unloaded_module_sections(
+                model.unloaded_module_list.as_ref(),
+                model.module_list.as_ref(),
+                dump_memory,
+            );
+
+            // Symbols for the exports and imports of every module
+            if let Some(minidump_module_list) = &model.module_list {
+                let export_index = self.add_export_symbols(minidump_module_list, dump_memory);
+                self.add_import_symbols(minidump_module_list, dump_memory, &export_index);
+            }
+
+            // Entry points
+            if let Some(minidump_module_list) = &model.module_list {
+                self.add_module_entry_points(
+                    minidump_module_list,
+                    dump_memory,
+                    linux_info.as_ref(),
+                );
+            }
+
+            // Threads
+            self.add_thread_sections_and_entry_points(layout, dump_memory, is_64bit);
+
+            // Return addresses in the call stack of the crashing thread. Unwinding every thread of
+            // a large process slows down loading, so the call stacks of the other threads are
+            // tagged when the Show Thread Call Stacks command unwinds them.
+            let crashing_thread_stacks = model.walk_threads(is_64bit, true);
+            add_stack_frame_tags(&*self, &crashing_thread_stacks, dump_memory);
+
+            // The crash site, which the view navigates to once the initial analysis is done,
+            // while the view's entry point stays the main module's entry point
+            self.add_crash_site(layout, dump_memory, &platform);
+
+            // Shared objects which the dynamic linker knew about (from the LinuxDsoDebug stream),
+            // but which are missing from the MinidumpModuleList stream. This happens when
+            // Breakpad / Crashpad fail to read the ELF headers of a mapped shared object.
+            if let Some(linux_info) = &linux_info {
+                self.add_link_map_sections(
+                    model.module_list.as_ref(),
+                    model.linux_maps(),
+                    linux_info,
+                );
+            }
+
+            // The parsed contents of the minidump, saved into the database for scripts and other plugins
+            store_view_metadata(self.as_ref(), &model);
+
+            // Metadata which isn't in the process's address space, placed after all of the process's memory
+            if self.metadata_segment_enabled() {
+                let memory_end = layout
+                    .segments
+                    .iter()
+                    .map(|segment| segment.address_range.end)
+                    .chain(layout.modules.iter().map(|module| module.address_range.end))
+                    .max()
+                    .unwrap_or(0);
+                let metadata_segment = memory_end
+                    .checked_next_multiple_of(METADATA_SEGMENT_ALIGNMENT)
+                    .and_then(|base_address| {
+                        add_metadata_segment(self.as_ref(), &dump_data, base_address, is_64bit)
+                    });
+                match metadata_segment {
+                    Some(metadata_segment) => info!(
+                        "Mapped minidump metadata at {:#x} to {:#x}",
+                        metadata_segment.start, metadata_segment.end
+                    ),
+                    None => warn!("Could not map the minidump metadata into the view"),
+                }
+            }
+        } else {
+            error!("Could not parse data as minidump");
+            return Err(());
+        }
+        Ok(())
+    }
+
+    /// Add each section in the section table of a module's mapped PE image as a section of the view,
+    /// named like `kernel32.dll!.text`.
+    ///
+    /// The semantics of each section are set from its characteristics, since Binary Ninja's
+    /// linear sweep and data variable analysis depend on them.
+    fn add_pe_sections(&self, module_name: &str, pe_headers: &PeHeaders) {
+        let module_file_name = module_file_name(module_name);
+        for pe_section in pe_headers.sections.iter() {
+            let section_name = format!("{}!{}", module_file_name, pe_section.name);
+            let section_start = pe_headers.base_address + pe_section.virtual_address as u64;
+            let section_address_range = Range {
+                start: section_start,
+                end: section_start + pe_section.mapped_size() as u64,
+            };
+            let semantics = if pe_section.is_code() {
+                Semantics::ReadOnlyCode
+            } else if pe_section.is_writable() {
+                Semantics::ReadWriteData
+            } else if pe_section.is_data() {
+                Semantics::ReadOnlyData
+            } else {
+                Semantics::DefaultSection
+            };
+
+            debug!(
+                "Adding PE section {} at virtual address {:#x} to {:#x} with semantics {:?}",
+                section_name, section_address_range.start, section_address_range.end, semantics,
+            );
+            self.add_section(
+                Section::builder(section_name, section_address_range)
+                    .semantics(semantics)
+                    .is_auto(true),
+            );
+        }
+    }
+
+    /// Walk the export directory of every mapped PE module, and create a symbol named like
+    /// `kernel32.dll!CreateFileW` for each export (or `kernel32.dll!Ordinal_123` for exports without names).
+    ///
+    /// Exports which are forwarded to another module (e.g. `kernel32.dll!HeapAlloc`, which is implemented
+    /// by `ntdll.dll!RtlAllocateHeap`) are followed to their targets, and only get a symbol there if
+    /// the target doesn't already have one of its own.
+    fn add_export_symbols(
+        &self,
+        minidump_module_list: &MinidumpModuleList,
+        dump_memory: &DumpMemory,
+    ) -> ExportIndex {
+        let mut export_index = ExportIndex::default();
+        let mut module_exports = Vec::new();
+        let mut code_ranges = Vec::new();
+
+        for module_info in minidump_module_list.by_addr() {
+            let Some(pe_headers) = PeHeaders::read(dump_memory, module_info.base_address()) else {
+                continue;
+            };
+            let exports = pe_headers.exports(dump_memory);
+            debug!(
+                "Found {} exports in module {}",
+                exports.len(),
+                module_info.name
+            );
+            export_index.insert(&module_info.name, &exports);
+            code_ranges.extend(
+                pe_headers
+                    .sections
+                    .iter()
+                    .filter(|pe_section| pe_section.is_code())
+                    .map(|pe_section| {
+                        let section_start =
+                            pe_headers.base_address + pe_section.virtual_address as u64;
+                        section_start..section_start + pe_section.mapped_size() as u64
+                    }),
+            );
+            module_exports.push((module_info.name.clone(), exports));
+        }
+
+        let direct_export_addresses: HashSet<u64> = module_exports
+            .iter()
+            .flat_map(|(_, exports)| exports.iter())
+            .filter_map(|export| match export.target {
+                ExportTarget::Address(address) => Some(address),
+                ExportTarget::Forwarder(_) => None,
+            })
+            .collect();
+        let platform = self.default_platform();
+
+        for (module_name, exports) in module_exports.iter() {
+            let module_file_name = module_file_name(module_name);
+            for export in exports.iter() {
+                let Some(address) = export_index.resolve(&export.target) else {
+                    debug!(
+                        "Could not resolve export {}!{} with target {:?}",
+                        module_file_name,
+                        export.display_name(),
+                        export.target,
+                    );
+                    continue;
+                };
+                let is_forwarded = matches!(export.target, ExportTarget::Forwarder(_));
+                if is_forwarded && direct_export_addresses.contains(&address) {
+                    continue;
+                }
+
+                let is_code = code_ranges
+                    .iter()
+                    .any(|code_range| code_range.contains(&address));
+                let symbol_type = if is_code {
+                    SymbolType::Function
+                } else {
+                    SymbolType::Data
+                };
+                for export_name in export.symbol_names() {
+                    let symbol_name = format!("{}!{}", module_file_name, export_name);
+                    self.define_auto_symbol(
+                        &Symbol::builder(symbol_type, &symbol_name, address).create(),
+                    );
+                }
+
+                if is_code && dump_memory.contains(address) {
+                    if let Some(platform) = &platform {
+                        self.add_auto_function(platform, address);
+                    }
+                }
+            }
+        }
+
+        export_index
+    }
+
+    /// Walk the import directory and delay-load import directory of every mapped PE module,
+    /// and create an import address symbol for every slot in its import address table (IAT),
+    /// named after the export which the slot imports (e.g. `KERNEL32.dll!CreateFileW`).
+    ///
+    /// Since the minidump contains the IAT after the loader has bound it, each slot can be checked
+    /// against the real address of the export it imports. Slots which point somewhere else
+    /// (e.g. because the import has been hooked) are tagged and logged.
+    fn add_import_symbols(
+        &self,
+        minidump_module_list: &MinidumpModuleList,
+        dump_memory: &DumpMemory,
+        export_index: &ExportIndex,
+    ) {
+        let mismatch_tag_type = self.get_or_create_tag_type(IAT_MISMATCH_TAG_TYPE, "🪝");
+
+        for module_info in minidump_module_list.by_addr() {
+            let Some(pe_headers) = PeHeaders::read(dump_memory, module_info.base_address()) else {
+                continue;
+            };
+            let module_range = module_info.base_address()
+                ..module_info
+                    .base_address()
+                    .saturating_add(module_info.size());
+            let imports = pe_headers.imports(dump_memory);
+            debug!(
+                "Found {} imports in module {}",
+                imports.len(),
+                module_info.name
+            );
+
+            for import in imports.iter() {
+                let import_name = match &import.name {
+                    Some(ImportName::Name(name)) => format!("{}!{}", import.dll_name, name),
+                    Some(ImportName::Ordinal(ordinal)) => {
+                        format!("{}!Ordinal_{}", import.dll_name, ordinal)
+                    }
+                    // Without an import name table, name the slot after whatever it points to
+                    None => match import
+                        .slot_value
+                        .and_then(|slot_value| export_index.name_at(slot_value))
+                    {
+                        Some(export_name) => export_name.to_string(),
+                        None => continue,
+                    },
+                };
+                self.define_auto_symbol(
+                    &Symbol::builder(SymbolType::ImportAddress, &import_name, import.slot_address)
+                        .create(),
+                );
+
+                if let Some(mismatch) = export_index.import_mismatch(import, &module_range) {
+                    let actual_target = export_index
+                        .name_at(mismatch.slot_value)
+                        .map(String::from)
+                        .unwrap_or_else(|| format!("{:#x}", mismatch.slot_value));
+                    let message = format!(
+                        "IAT slot for {} points to {}, instead of the export's real address {:#x}",
+                        import_name, actual_target, mismatch.expected_address,
+                    );
+                    warn!("{} (in module {})", message, module_info.name);
+                    self.add_tag(import.slot_address, &mismatch_tag_type, &message, false);
+                }
+            }
+        }
+    }
+
+    fn get_or_create_tag_type(&self, name: &str, icon: &str) -> Ref<TagType> {
+        self.tag_type_by_name(name)
+            .unwrap_or_else(|| self.create_tag_type(name, icon))
+    }
+
+    /// Find the entry points of every module from the module's executable header
+    /// (PE, ELF, or Mach-O) in the minidump's memory, and add them as entry functions.
+    ///
+    /// The main module's entry point becomes the entry point of the view.
+    /// Every other module's entry point (e.g. `DllMain`), and every module's initializers
+    /// (TLS callbacks, ELF init arrays, Mach-O `__mod_init_func` functions), are added as
+    /// additional entry functions.
+    fn add_module_entry_points(
+        &mut self,
+        minidump_module_list: &MinidumpModuleList,
+        dump_memory: &DumpMemory,
+        linux_info: Option<&LinuxProcessInfo>,
+    ) {
+        let main_module_base_address = minidump_module_list
+            .main_module()
+            .map(|main_module| main_module.base_address());
+        let mut additional_entry_points = Vec::new();
+
+        for module_info in minidump_module_list.by_addr() {
+            let base_address = module_info.base_address();
+            let (module_entry_point, module_initializers) = if let Some(pe_headers) =
+                PeHeaders::read(dump_memory, base_address)
+            {
+                (
+                    pe_headers.entry_point(),
+                    pe_headers.tls_callbacks(dump_memory),
+                )
+            } else if let Some(elf_header) = ElfHeader::read(dump_memory, base_address) {
+                (
+                    elf_header.entry_point(dump_memory),
+                    elf_header.initializers(dump_memory),
+                )
+            } else if let Some(mach_header) = MachHeader::read(dump_memory, base_address) {
+                (
+                    mach_header.entry_point(),
+                    mach_header.initializers(dump_memory),
+                )
+            } else {
+                debug!(
+                        "Could not find a PE, ELF, or Mach-O header for module {} at virtual address {:#x}",
+                        module_info.name, base_address,
+                    );
+                (None, Vec::new())
+            };
+
+            if Some(base_address) == main_module_base_address {
+                // If the main executable's headers weren't captured in a Linux minidump,
+                // its entry point can still be found in the auxiliary vector.
+                let main_entry_point = module_entry_point
+                    .or_else(|| linux_info.and_then(|linux_info| linux_info.auxv_value(AT_ENTRY)));
+                if let Some(main_entry_point) = main_entry_point {
                     info!(
-                        "Found module with name {} at virtual address {:#x} with size {:#x}",
-                        module_info.name,
-                        module_info.base_address(),
-                        module_info.size(),
+                        "Found entry point {:#x} for main module {}",
+                        main_entry_point, module_info.name,
+                    );
+                    self.entry_point = main_entry_point;
+                } else {
+                    warn!(
+                        "Could not find the entry point of main module {}",
+                        module_info.name
+                    );
+                }
+            } else if let Some(module_entry_point) = module_entry_point {
+                debug!(
+                    "Found entry point {:#x} for module {}",
+                    module_entry_point, module_info.name,
+                );
+                additional_entry_points.push(module_entry_point);
+            }
+
+            for initializer in module_initializers {
+                debug!(
+                    "Found initializer {:#x} for module {}",
+                    initializer, module_info.name,
+                );
+                additional_entry_points.push(initializer);
+            }
+        }
+
+        if self.entry_point != 0 {
+            self.add_entry_point(self.entry_point);
+        }
+        for entry_point in additional_entry_points {
+            // There's no point in starting analysis at code which isn't in the minidump
+            if dump_memory.contains(entry_point) {
+                self.add_entry_point(entry_point);
+            }
+        }
+    }
+
+    /// Add the stack memory and thread environment block (TEB) of every thread in the
+    /// `MinidumpThreadList` stream as sections, named like `thread 0x1a2c stack` and `thread 0x1a2c TEB`.
+    ///
+    /// The instruction pointer of each thread, from the thread's `CONTEXT`, is added as an entry
+    /// point and tagged with the thread's ID (and name, if the minidump has a `ThreadNamesStream`),
+    /// so that where every thread was at the time of the dump can be navigated to from the _Tags_ window.
+    fn add_thread_sections_and_entry_points(
+        &self,
+        layout: &DumpLayout,
+        dump_memory: &DumpMemory,
+        is_64bit: bool,
+    ) {
+        let thread_tag_type = self.get_or_create_tag_type(THREAD_TAG_TYPE, "🧵");
+
+        for thread in layout.threads.iter() {
+            let thread_id = thread.thread_id;
+            let thread_description = match &thread.name {
+                Some(thread_name) => format!("thread {:#x} ({})", thread_id, thread_name),
+                None => format!("thread {:#x}", thread_id),
+            };
+
+            if let Some(stack_range) = &thread.stack_range {
+                info!(
+                    "Found stack for {} at virtual address {:#x} with size {:#x}",
+                    thread_description,
+                    stack_range.start,
+                    stack_range.end - stack_range.start,
+                );
+                self.add_section(
+                    Section::builder(
+                        format!("thread {:#x} stack", thread_id),
+                        stack_range.clone(),
+                    )
+                    .semantics(Semantics::ReadWriteData)
+                    .is_auto(true),
+                );
+            }
+
+            if let Some(teb) = thread.teb {
+                debug!(
+                    "Found TEB for {} at virtual address {:#x}",
+                    thread_description, teb
+                );
+                let teb_size = if is_64bit { TEB_SIZE_64 } else { TEB_SIZE_32 };
+                self.add_section(
+                    Section::builder(
+                        format!("thread {:#x} TEB", thread_id),
+                        Range {
+                            start: teb,
+                            end: teb.saturating_add(teb_size),
+                        },
+                    )
+                    .semantics(Semantics::ReadWriteData)
+                    .is_auto(true),
+                );
+            }
+
+            let Some(instruction_pointer) = thread.instruction_pointer else {
+                continue;
+            };
+            if !dump_memory.contains(instruction_pointer) {
+                info!(
+                    "Instruction pointer {:#x} of {} is not present in the minidump",
+                    instruction_pointer, thread_description,
+                );
+                continue;
+            }
+            debug!(
+                "Adding instruction pointer {:#x} of {} as an entry point",
+                instruction_pointer, thread_description,
+            );
+            self.add_entry_point(instruction_pointer);
+            self.add_tag(
+                instruction_pointer,
+                &thread_tag_type,
+                &format!("Instruction pointer of {}", thread_description),
+                false,
+            );
+        }
+    }
+
+    /// Annotate the address at which the exception in the `MinidumpException` stream happened
+    /// with a _Crash Site_ tag, a bookmark, and a comment decoding the exception
+    /// (e.g. `EXCEPTION_ACCESS_VIOLATION_WRITE` and the address being written to, or `SIGSEGV`).
+    ///
+    /// A function is created at the crash site even if analysis wouldn't otherwise reach it,
+    /// and the view navigates to the crash site once the initial analysis is done (see
+    /// [`Self::navigate_to_crash_site`]). The view's entry point is left as the main module's entry
+    /// point, since scripts and other plugins expect it to be a real entry point.
+    fn add_crash_site(&self, layout: &DumpLayout, dump_memory: &DumpMemory, platform: &Platform) {
+        let Some(exception) = &layout.exception else {
+            info!("Could not find a MinidumpException stream in minidump; this minidump may not have been written because of a crash");
+            return;
+        };
+        let crash_site = exception.address;
+        let message = format!(
+            "Crash site: {} in thread {:#x}",
+            exception.description, exception.thread_id
+        );
+        info!("{} at address {:#x}", message, crash_site);
+
+        if !dump_memory.contains(crash_site) {
+            warn!(
+                "Crash site {:#x} is not present in the minidump; it cannot be annotated",
+                crash_site
+            );
+            return;
+        }
+
+        let crash_site_tag_type = self.get_or_create_tag_type(CRASH_SITE_TAG_TYPE, "💥");
+        self.add_tag(crash_site, &crash_site_tag_type, &message, false);
+        let bookmarks_tag_type =
+            self.get_or_create_tag_type(BOOKMARKS_TAG_TYPE, BOOKMARKS_TAG_ICON);
+        self.add_tag(crash_site, &bookmarks_tag_type, "Crash site", true);
+        self.set_comment_at(crash_site, &message);
+
+        self.add_auto_function(platform, crash_site);
+        self.navigate_to_crash_site(crash_site);
+    }
+
+    /// Navigate to the crash site once the initial analysis of the view is done, so that the view
+    /// opens at the crash instead of at the main module's entry point.
+    ///
+    /// The UI navigates to the entry point when it first shows the view, so navigating while the
+    /// view is still loading would be undone. Instead, a background thread waits until the view has
+    /// finished loading and its initial analysis is done, and then navigates on the main thread.
+    fn navigate_to_crash_site(&self, crash_site: u64) {
+        let view = self.inner.clone();
+        thread::spawn(move || {
+            // The view is only registered with its file once `init` has returned. If it never is
+            // (e.g. because loading failed, or the file was closed), give up.
+            let started_waiting = Instant::now();
+            while view.file().view_of_type(VIEW_TYPE_NAME).is_none() {
+                if started_waiting.elapsed() > NAVIGATION_TIMEOUT {
+                    debug!(
+                        "Gave up waiting for the view to load before navigating to the crash site"
+                    );
+                    return;
+                }
+                thread::sleep(NAVIGATION_POLL_INTERVAL);
+            }
+            view.update_analysis_and_wait();
+            execute_on_main_thread(move || {
+                let file = view.file();
+                let current_view = file.current_view();
+                if file.navigate_to(current_view, crash_site).is_err() {
+                    debug!(
+                        "Could not navigate to crash site {:#x}; the view may not be open in the UI",
+                        crash_site
+                    );
+                }
+            });
+        });
+    }
+
+    /// Add the range of every module in the `MinidumpUnloadedModuleList` stream as a section,
+    /// named like `<unloaded> foo.dll`.
+    ///
+    /// Each unloaded module is also tagged at the start of whatever part of its range was captured
+    /// in the minidump, noting how much of it is still present, so that code which is still running
+    /// from an unloaded module can be recognised.
+    fn add_unloaded_module_sections(
+        &self,
+        minidump_unloaded_module_list: Option<&MinidumpUnloadedModuleList>,
+        minidump_module_list: Option<&MinidumpModuleList>,
+        dump_memory: &DumpMemory,
+    ) {
+        let Some(minidump_unloaded_module_list) = minidump_unloaded_module_list else {
+            debug!("Could not find a valid MinidumpUnloadedModuleList stream in minidump");
+            return;
+        };
+        let unloaded_module_tag_type = self.get_or_create_tag_type(UNLOADED_MODULE_TAG_TYPE, "👻");
+
+        for unloaded_module_info in minidump_unloaded_module_list.iter() {
+            let module_address_range = Range {
+                start: unloaded_module_info.base_address(),
+                end: unloaded_module_info
+                    .base_address()
+                    .saturating_add(unloaded_module_info.size()),
+            };
+            // A module which was unloaded and then loaded again at the same address
+            // is described by the MinidumpModuleList stream instead.
+            let is_reloaded = minidump_module_list.is_some_and(|module_list| {
+                module_list.by_addr().any(|module_info| {
+                    module_info.base_address() < module_address_range.end
+                        && module_address_range.start
+                            < module_info
+                                .base_address()
+                                .saturating_add(module_info.size())
+                })
+            });
+            if is_reloaded {
+                debug!(
+                    "Skipping unloaded module {} at virtual address {:#x}, which overlaps a loaded module",
+                    unloaded_module_info.name, module_address_range.start,
+                );
+                continue;
+            }
+
+            let section_name = format!(
+                "<unloaded> {}",
+                module_file_name(&unloaded_module_info.name)
+            );
+            info!(
+                "Found unloaded module {} at virtual address {:#x} with size {:#x}",
+                unloaded_module_info.name,
+                module_address_range.start,
+                unloaded_module_info.size(),
+            );
+            self.add_section(
+                Section::builder(section_name.clone(), module_address_range.clone()).is_auto(true),
+            );
+
+            match dump_memory.captured_in(&module_address_range) {
+                Some((first_captured_address, captured_len)) => {
+                    let message = format!(
+                        "{} ({:#x} to {:#x}): {:#x} of {:#x} bytes are still present in the minidump",
+                        section_name,
+                        module_address_range.start,
+                        module_address_range.end,
+                        captured_len,
+                        unloaded_module_info.size(),
+                    );
+                    info!("{}", message);
+                    self.add_tag(
+                        first_captured_address,
+                        &unloaded_module_tag_type,
+                        &message,
+                        false,
+                    );
+                }
+                None => info!(
+                    "{} ({:#x} to {:#x}) is not present in the minidump",
+                    section_name, module_address_range.start, module_address_range.end,
+                ),
+            }
+        }
+    }
+
+    /// Add every shared object which is in the dynamic linker's link map but missing from the
+    /// `MinidumpModuleList` stream as a section, named after the shared object's path. The link map
+    /// is the one found through `DT_DEBUG` and `r_debug`, from the `LinuxDsoDebug` stream.
+    ///
+    /// The section spans the memory mappings (from the `LinuxMaps` stream) from the shared object's
+    /// headers to its dynamic section, since the link map doesn't record the size of the object.
+    fn add_link_map_sections(
+        &self,
+        minidump_module_list: Option<&MinidumpModuleList>,
+        minidump_linux_maps: Option<&MinidumpLinuxMaps>,
+        linux_info: &LinuxProcessInfo,
+    ) {
+        let linux_map_ranges: Vec<Range<u64>> = minidump_linux_maps
+            .map(|linux_maps| {
+                linux_maps
+                    .iter()
+                    .filter_map(|map_info| map_info.memory_range())
+                    .map(|memory_range| Range {
+                        start: memory_range.start,
+                        end: memory_range.end.saturating_add(1),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mapping_containing = |address: u64| {
+            linux_map_ranges
+                .iter()
+                .find(|map_range| map_range.contains(&address))
+        };
+
+        for link_map_entry in linux_info.link_map.iter() {
+            // The main executable has an empty name in the link map,
+            // and is always present in the MinidumpModuleList stream anyway.
+            if link_map_entry.name.is_empty() {
+                continue;
+            }
+            let is_in_module_list = minidump_module_list.is_some_and(|module_list| {
+                module_list
+                    .module_at_address(link_map_entry.dynamic)
+                    .is_some()
+            });
+            if is_in_module_list {
+                continue;
+            }
+
+            // The link map only tells us where the shared object's headers (at the load bias)
+            // and its dynamic section are, so span the mappings from the first to the second.
+            if let (Some(first_mapping), Some(dynamic_mapping)) = (
+                mapping_containing(link_map_entry.load_bias),
+                mapping_containing(link_map_entry.dynamic),
+            ) {
+                if first_mapping.start < dynamic_mapping.end {
+                    info!(
+                        "Found shared object {} missing from module list at virtual address {:#x} to {:#x}",
+                        link_map_entry.name, first_mapping.start, dynamic_mapping.end,
                     );
-                    let module_address_range = Range {
-                        start: module_info.base_address(),
-                        end: module_info.base_address() + module_info.size(),
-                    };
                     self.add_section(
-                        Section::builder(module_info.name.clone(), module_address_range)
-                            .is_auto(true),
+                        Section::builder(
+                            link_map_entry.name.clone(),
+                            Range {
+                                start: first_mapping.start,
+                                end: dynamic_mapping.end,
+                            },
+                        )
+                        .is_auto(true),
                     );
                 }
             } else {
-                warn!("Could not find valid module information in minidump: could not find a valid MinidumpModuleList stream");
+                debug!(
+                    "Could not find memory mappings for shared object {} with load bias {:#x}",
+                    link_map_entry.name, link_map_entry.load_bias,
+                );
             }
+        }
+    }
+
+    /// Look up the platform which the user selected in the load options for the view, if any.
+    fn platform_override(&self) -> Option<binaryninja::rc::Ref<Platform>> {
+        let load_settings = self.load_settings(VIEW_TYPE_NAME).ok()?;
+        if !load_settings.contains(PLATFORM_OVERRIDE_SETTING) {
+            return None;
+        }
+        let platform_name = load_settings
+            .get_string_with_opts(
+                PLATFORM_OVERRIDE_SETTING,
+                &mut QueryOptions::new_with_view(self.as_ref()),
+            )
+            .to_string();
+        if platform_name.is_empty() {
+            return None;
+        }
+
+        let platform = Platform::by_name(platform_name.as_str());
+        if platform.is_some() {
+            info!("Using platform {} selected in load options", platform_name);
         } else {
-            error!("Could not parse data as minidump");
-            return Err(());
+            warn!(
+                "Could not find platform {} selected in load options; ignoring it",
+                platform_name
+            );
         }
-        Ok(())
+        platform
+    }
+
+    /// Whether the user enabled the metadata segment in the load options for the view.
+    fn metadata_segment_enabled(&self) -> bool {
+        let Ok(load_settings) = self.load_settings(VIEW_TYPE_NAME) else {
+            return false;
+        };
+        load_settings.contains(METADATA_SEGMENT_SETTING)
+            && load_settings.get_bool_with_opts(
+                METADATA_SEGMENT_SETTING,
+                &mut QueryOptions::new_with_view(self.as_ref()),
+            )
+    }
+
+    fn platform_from_system_info(
+        minidump_system_info: Option<&MinidumpSystemInfo>,
+        endian: minidump::Endian,
+    ) -> Option<binaryninja::rc::Ref<Platform>> {
+        if let Some(minidump_system_info) = minidump_system_info {
+            let platform = MinidumpBinaryView::translate_minidump_platform(
+                minidump_system_info.cpu,
+                endian,
+                minidump_system_info.os,
+            );
+            if platform.is_none() {
+                warn!(
+                    "Could not parse valid system information from minidump: could not map system information in MinidumpSystemInfo stream (arch {:?}, endian {:?}, os {:?}) to a known architecture",
+                    minidump_system_info.cpu,
+                    endian,
+                    minidump_system_info.os,
+                );
+            }
+            platform
+        } else {
+            warn!("Could not parse system information from minidump: could not find a valid MinidumpSystemInfo stream");
+            None
+        }
+    }
+
+    /// Infer the platform from the machine type in the executable header of the main module,
+    /// for minidumps where the `MinidumpSystemInfo` stream is missing or damaged.
+    fn platform_from_main_module(
+        minidump_module_list: Option<&MinidumpModuleList>,
+        dump_memory: &DumpMemory,
+    ) -> Option<binaryninja::rc::Ref<Platform>> {
+        let minidump_module_list = minidump_module_list?;
+        let main_module = minidump_module_list.main_module()?;
+        let base_address = main_module.base_address();
+
+        let (cpu, endian, os) = if let Some(pe_headers) = PeHeaders::read(dump_memory, base_address)
+        {
+            (
+                pe_headers.minidump_cpu(),
+                minidump::Endian::Little,
+                minidump::system_info::Os::Windows,
+            )
+        } else if let Some(elf_header) = ElfHeader::read(dump_memory, base_address) {
+            (
+                elf_header.minidump_cpu(),
+                elf_header.endian,
+                minidump::system_info::Os::Linux,
+            )
+        } else if let Some(mach_header) = MachHeader::read(dump_memory, base_address) {
+            (
+                mach_header.minidump_cpu(),
+                minidump::Endian::Little,
+                minidump::system_info::Os::MacOs,
+            )
+        } else {
+            warn!(
+                "Could not find a PE, ELF, or Mach-O header for main module {} at virtual address {:#x}",
+                main_module.name, base_address,
+            );
+            return None;
+        };
+
+        let Some(cpu) = cpu else {
+            warn!(
+                "Could not map the machine type in the header of main module {} to a known architecture",
+                main_module.name,
+            );
+            return None;
+        };
+        info!(
+            "Inferred system information (arch {:?}, endian {:?}, os {:?}) from the header of main module {}",
+            cpu, endian, os, main_module.name,
+        );
+        MinidumpBinaryView::translate_minidump_platform(cpu, endian, os)
     }
 
+    /// Map the operating system and CPU architecture reported in a minidump's
+    /// `MinidumpSystemInfo` stream to a Binary Ninja platform.
+    ///
+    /// Binary Ninja doesn't have a platform for every OS / CPU pair that can appear in a
+    /// minidump (e.g. there are no Solaris platforms). When there is no exact match, this
+    /// falls back to the closest related OS's platform (Linux for Android, macOS for iOS),
+    /// and then to the architecture-only platform, so that the minidump can still be loaded.
     fn translate_minidump_platform(
         minidump_cpu_arch: minidump::system_info::Cpu,
         minidump_endian: minidump::Endian,
         minidump_os: minidump::system_info::Os,
     ) -> Option<binaryninja::rc::Ref<Platform>> {
-        match minidump_os {
-            minidump::system_info::Os::Windows => match minidump_cpu_arch {
-                minidump::system_info::Cpu::Arm64 => Platform::by_name("windows-aarch64"),
-                minidump::system_info::Cpu::Arm => Platform::by_name("windows-armv7"),
-                minidump::system_info::Cpu::X86 => Platform::by_name("windows-x86"),
-                minidump::system_info::Cpu::X86_64 => Platform::by_name("windows-x86_64"),
-                _ => None,
+        let (platform_arch_name, arch_name) = MinidumpBinaryView::translate_minidump_architecture(
+            minidump_cpu_arch,
+            minidump_endian,
+        )?;
+
+        let platform_os_names: &[&str] = match minidump_os {
+            minidump::system_info::Os::Windows => &["windows"],
+            minidump::system_info::Os::MacOs => &["mac"],
+            minidump::system_info::Os::Ios => &["ios", "mac"],
+            minidump::system_info::Os::Linux => &["linux"],
+            minidump::system_info::Os::Android => &["android", "linux"],
+            minidump::system_info::Os::Solaris => &["solaris"],
+            minidump::system_info::Os::NaCl => &[],
+            minidump::system_info::Os::Ps3 => &[],
+            _ => &[],
+        };
+
+        for platform_os_name in platform_os_names {
+            let platform_name = format!("{}-{}", platform_os_name, platform_arch_name);
+            if let Some(platform) = Platform::by_name(platform_name.as_str()) {
+                return Some(platform);
+            }
+            debug!("Could not find platform {}", platform_name);
+        }
+
+        warn!(
+            "Could not find a platform for OS {:?} and architecture {:?} in minidump; falling back to architecture-only platform {}",
+            minidump_os, minidump_cpu_arch, arch_name,
+        );
+        Platform::by_name(arch_name)
+    }
+
+    /// Map the CPU architecture reported in a minidump's `MinidumpSystemInfo` stream to
+    /// the architecture part of a Binary Ninja platform name (e.g. the `ppc32_le` in `linux-ppc32_le`),
+    /// and to the name of the corresponding Binary Ninja architecture (e.g. `ppc_le`).
+    fn translate_minidump_architecture(
+        minidump_cpu_arch: minidump::system_info::Cpu,
+        minidump_endian: minidump::Endian,
+    ) -> Option<(&'static str, &'static str)> {
+        match minidump_cpu_arch {
+            minidump::system_info::Cpu::X86 => Some(("x86", "x86")),
+            minidump::system_info::Cpu::X86_64 => Some(("x86_64", "x86_64")),
+            minidump::system_info::Cpu::Arm => Some(("armv7", "armv7")),
+            minidump::system_info::Cpu::Arm64 => Some(("aarch64", "aarch64")),
+            minidump::system_info::Cpu::Ppc => match minidump_endian {
+                minidump::Endian::Little => Some(("ppc32_le", "ppc_le")),
+                minidump::Endian::Big => Some(("ppc32", "ppc")),
             },
-            minidump::system_info::Os::MacOs => match minidump_cpu_arch {
-                minidump::system_info::Cpu::Arm64 => Platform::by_name("mac-aarch64"),
-                minidump::system_info::Cpu::Arm => Platform::by_name("mac-armv7"),
-                minidump::system_info::Cpu::X86 => Platform::by_name("mac-x86"),
-                minidump::system_info::Cpu::X86_64 => Platform::by_name("mac-x86_64"),
-                _ => None,
+            minidump::system_info::Cpu::Ppc64 => match minidump_endian {
+                minidump::Endian::Little => Some(("ppc64_le", "ppc64_le")),
+                minidump::Endian::Big => Some(("ppc64", "ppc64")),
             },
-            minidump::system_info::Os::Linux => match minidump_cpu_arch {
-                minidump::system_info::Cpu::Arm64 => Platform::by_name("linux-aarch64"),
-                minidump::system_info::Cpu::Arm => Platform::by_name("linux-armv7"),
-                minidump::system_info::Cpu::X86 => Platform::by_name("linux-x86"),
-                minidump::system_info::Cpu::X86_64 => Platform::by_name("linux-x86_64"),
-                minidump::system_info::Cpu::Ppc => match minidump_endian {
-                    minidump::Endian::Little => Platform::by_name("linux-ppc32_le"),
-                    minidump::Endian::Big => Platform::by_name("linux-ppc32"),
-                },
-                minidump::system_info::Cpu::Ppc64 => match minidump_endian {
-                    minidump::Endian::Little => Platform::by_name("linux-ppc64_le"),
-                    minidump::Endian::Big => Platform::by_name("linux-ppc64"),
-                },
-                _ => None,
+            minidump::system_info::Cpu::Mips => match minidump_endian {
+                minidump::Endian::Little => Some(("mipsel", "mipsel32")),
+                minidump::Endian::Big => Some(("mips", "mips32")),
             },
-            minidump::system_info::Os::NaCl => None,
-            minidump::system_info::Os::Android => None,
-            minidump::system_info::Os::Ios => None,
-            minidump::system_info::Os::Ps3 => None,
-            minidump::system_info::Os::Solaris => None,
+            minidump::system_info::Cpu::Mips64 => match minidump_endian {
+                minidump::Endian::Little => Some(("mipsel64", "mipsel64")),
+                minidump::Endian::Big => Some(("mips64", "mips64")),
+            },
+            minidump::system_info::Cpu::Unknown(MD_CPU_ARCHITECTURE_RISCV) => {
+                Some(("rv32gc", "rv32gc"))
+            }
+            minidump::system_info::Cpu::Unknown(MD_CPU_ARCHITECTURE_RISCV64) => {
+                Some(("rv64gc", "rv64gc"))
+            }
+            // Binary Ninja has no SPARC architecture
+            minidump::system_info::Cpu::Sparc => None,
             _ => None,
         }
     }
-
-    fn translate_memory_protection(
-        minidump_memory_protection: MemoryProtection,
-    ) -> SegmentMemoryProtection {
-        let (readable, writable, executable) = match minidump_memory_protection {
-            MemoryProtection::PAGE_NOACCESS => (false, false, false),
-            MemoryProtection::PAGE_READONLY => (true, false, false),
-            MemoryProtection::PAGE_READWRITE => (true, true, false),
-            MemoryProtection::PAGE_WRITECOPY => (true, true, false),
-            MemoryProtection::PAGE_EXECUTE => (false, false, true),
-            MemoryProtection::PAGE_EXECUTE_READ => (true, false, true),
-            MemoryProtection::PAGE_EXECUTE_READWRITE => (true, true, true),
-            MemoryProtection::PAGE_EXECUTE_WRITECOPY => (true, true, true),
-            MemoryProtection::ACCESS_MASK => (false, false, false),
-            MemoryProtection::PAGE_GUARD => (false, false, false),
-            MemoryProtection::PAGE_NOCACHE => (false, false, false),
-            MemoryProtection::PAGE_WRITECOMBINE => (false, false, false),
-            _ => (false, false, false),
-        };
-        SegmentMemoryProtection {
-            readable,
-            writable,
-            executable,
-        }
-    }
 }
 
 impl AsRef<BinaryView> for MinidumpBinaryView {
@@ -412,6 +1215,12 @@ impl AsRef<BinaryView> for MinidumpBinaryView {
     }
 }
 
+impl Drop for MinidumpBinaryView {
+    fn drop(&mut self) {
+        MinidumpModel::evict(&self.inner);
+    }
+}
+
 impl BinaryViewBase for MinidumpBinaryView {
     fn address_size(&self) -> usize {
         if let Some(plat) = self.default_platform() {
@@ -432,11 +1241,7 @@ impl BinaryViewBase for MinidumpBinaryView {
     }
 
     fn entry_point(&self) -> u64 {
-        // TODO: We should fill this out with a real entry point.
-        // This can be done by getting the main module of the minidump
-        // with MinidumpModuleList::main_module,
-        // then parsing the PE metadata of the main module to find its entry point(s).
-        0
+        self.entry_point
     }
 }
 
@@ -451,3 +1256,37 @@ unsafe impl CustomBinaryView for MinidumpBinaryView {
         MinidumpBinaryView::init(self)
     }
 }
+
+/// Tag each return address in the call stacks of some threads with the thread
+/// and the number of its frame (e.g. `thread 0x1a2c frame #3`).
+pub fn add_stack_frame_tags<'a>(
+    view: &impl BinaryViewExt,
+    thread_stacks: impl IntoIterator<Item = &'a ThreadStack>,
+    dump_memory: &DumpMemory,
+) {
+    let stack_frame_tag_type = view
+        .tag_type_by_name(STACK_FRAME_TAG_TYPE)
+        .unwrap_or_else(|| view.create_tag_type(STACK_FRAME_TAG_TYPE, "🥞"));
+    for thread_stack in thread_stacks {
+        debug!(
+            "Unwound {} frames for thread {:#x}",
+            thread_stack.frames.len(),
+            thread_stack.thread_id,
+        );
+        // The innermost frame is already tagged with the thread's instruction pointer
+        for (frame_number, frame) in thread_stack.frames.iter().enumerate().skip(1) {
+            if !dump_memory.contains(frame.instruction) {
+                continue;
+            }
+            view.add_tag(
+                frame.instruction,
+                &stack_frame_tag_type,
+                &format!(
+                    "thread {:#x} frame #{} (found by {})",
+                    thread_stack.thread_id, frame_number, frame.trust
+                ),
+                false,
+            );
+        }
+    }
+}
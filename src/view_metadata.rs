@@ -0,0 +1,291 @@
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use binaryninja::metadata::{Metadata, MetadataType};
+use binaryninja::rc::Ref;
+use log::debug;
+use minidump::format::{MINIDUMP_LOCATION_DESCRIPTOR, MINIDUMP_MEMORY_INFO};
+use minidump::{MinidumpContext, Module};
+
+use minidump_layout::extended_registers::{context_bytes, read_extended_registers};
+use minidump_layout::layout::describe_exception;
+
+use crate::model::MinidumpModel;
+
+/// The prefix of the keys under which the parsed minidump is stored in the view's metadata.
+pub const METADATA_KEY_PREFIX: &str = "minidump";
+
+/// The version of the layout of the metadata, stored under `minidump.schema_version`.
+///
+/// The layout of each key is documented in the _Scripting_ section of the README. Fields may be
+/// added without changing the version, but it is bumped whenever a field is renamed, removed,
+/// or changes its type, so that scripts can tell which layout they are reading.
+pub const METADATA_SCHEMA_VERSION: u64 = 2;
+
+/// Build a key-value metadata object from its entries.
+fn object(entries: Vec<(&str, Ref<Metadata>)>) -> Ref<Metadata> {
+    let object = Metadata::new_of_type(MetadataType::KeyValueDataType);
+    for (key, value) in entries {
+        let _ = object.insert(key, &value);
+    }
+    object
+}
+
+/// Build an array metadata value from its elements.
+fn array(elements: impl IntoIterator<Item = Ref<Metadata>>) -> Ref<Metadata> {
+    let array = Metadata::new_of_type(MetadataType::ArrayDataType);
+    for element in elements {
+        let _ = array.push(&element);
+    }
+    array
+}
+
+fn unsigned(value: impl Into<u64>) -> Ref<Metadata> {
+    Ref::<Metadata>::from(value.into())
+}
+
+fn string(value: impl AsRef<str>) -> Ref<Metadata> {
+    Ref::<Metadata>::from(value.as_ref())
+}
+
+/// The general purpose registers of a `CONTEXT`, by register name.
+fn context_registers(context: &MinidumpContext) -> Ref<Metadata> {
+    object(
+        context
+            .general_purpose_registers()
+            .iter()
+            .filter_map(|register_name| {
+                let value = context.get_register(register_name)?;
+                Some((*register_name, unsigned(value)))
+            })
+            .collect(),
+    )
+}
+
+/// The vector, floating-point, and mask registers of a `CONTEXT` which aren't zero, by register name,
+/// as hexadecimal strings, since they are wider than a metadata integer. Zero registers are left out
+/// to keep the metadata of processes with many threads small.
+fn context_extended_registers(
+    model: &MinidumpModel,
+    context: &MinidumpContext,
+    location: &MINIDUMP_LOCATION_DESCRIPTOR,
+) -> Ref<Metadata> {
+    let extended_registers = read_extended_registers(
+        context,
+        context_bytes(&model.dump_data, location),
+        model.xstate_config.as_ref(),
+    );
+    object(
+        extended_registers
+            .iter()
+            .filter(|register| register.value.iter().any(|byte| *byte != 0))
+            .map(|register| (register.name.as_str(), string(register.value_hex())))
+            .collect(),
+    )
+}
+
+/// The entries describing a `CONTEXT`: its instruction pointer and stack pointer, which scripts
+/// shouldn't need to know the architecture's register names to find, and all of its registers.
+fn context_entries(
+    model: &MinidumpModel,
+    context: &MinidumpContext,
+    location: &MINIDUMP_LOCATION_DESCRIPTOR,
+) -> [(&'static str, Ref<Metadata>); 4] {
+    [
+        (
+            "instruction_pointer",
+            unsigned(context.get_instruction_pointer()),
+        ),
+        ("stack_pointer", unsigned(context.get_stack_pointer())),
+        ("registers", context_registers(context)),
+        (
+            "extended_registers",
+            context_extended_registers(model, context, location),
+        ),
+    ]
+}
+
+fn system_info_metadata(model: &MinidumpModel) -> Option<Ref<Metadata>> {
+    let system_info = model.system_info.as_ref()?;
+    let raw = &system_info.raw;
+    let mut entries = vec![
+        ("os", string(format!("{:?}", system_info.os))),
+        ("cpu", string(format!("{:?}", system_info.cpu))),
+        (
+            "os_version",
+            string(format!(
+                "{}.{}.{}",
+                raw.major_version, raw.minor_version, raw.build_number
+            )),
+        ),
+        ("processor_count", unsigned(raw.number_of_processors)),
+    ];
+    if let Some(csd_version) = system_info.csd_version() {
+        entries.push(("csd_version", string(csd_version)));
+    }
+    if let Some(cpu_info) = system_info.cpu_info() {
+        entries.push(("cpu_info", string(cpu_info)));
+    }
+    Some(object(entries))
+}
+
+fn threads_metadata(model: &MinidumpModel) -> Option<Ref<Metadata>> {
+    let thread_list = model.thread_list()?;
+    let crashing_thread_id = model.exception().map(|exception| exception.raw.thread_id);
+    Some(array(thread_list.threads.iter().map(|thread| {
+        let thread_id = thread.raw.thread_id;
+        let mut entries = vec![
+            ("id", unsigned(thread_id)),
+            ("suspend_count", unsigned(thread.raw.suspend_count)),
+            ("priority", unsigned(thread.raw.priority)),
+            ("teb", unsigned(thread.raw.teb)),
+            (
+                "stack_start",
+                unsigned(thread.raw.stack.start_of_memory_range),
+            ),
+            ("stack_size", unsigned(thread.raw.stack.memory.data_size)),
+            (
+                "is_crashing_thread",
+                Ref::<Metadata>::from(Some(thread_id) == crashing_thread_id),
+            ),
+        ];
+        if let Some(thread_name) = model
+            .thread_names
+            .as_ref()
+            .and_then(|thread_names| thread_names.get_name(thread_id))
+        {
+            entries.push(("name", string(thread_name)));
+        }
+        if let Some(context) = model
+            .system_info
+            .as_ref()
+            .and_then(|system_info| thread.context(system_info, model.misc_info.as_ref()))
+        {
+            entries.extend(context_entries(model, &context, &thread.raw.thread_context));
+        }
+        object(entries)
+    })))
+}
+
+fn exception_metadata(model: &MinidumpModel) -> Option<Ref<Metadata>> {
+    let exception = model.exception()?;
+    let exception_record = &exception.raw.exception_record;
+    let mut entries = vec![
+        ("thread_id", unsigned(exception.raw.thread_id)),
+        ("code", unsigned(exception_record.exception_code)),
+        ("flags", unsigned(exception_record.exception_flags)),
+        ("address", unsigned(exception_record.exception_address)),
+        (
+            "description",
+            string(describe_exception(exception, model.system_info.as_ref())),
+        ),
+    ];
+    if let Some(context) = model
+        .system_info
+        .as_ref()
+        .and_then(|system_info| exception.context(system_info, model.misc_info.as_ref()))
+    {
+        entries.extend(context_entries(
+            model,
+            &context,
+            &exception.raw.thread_context,
+        ));
+    }
+    Some(object(entries))
+}
+
+fn modules_metadata(model: &MinidumpModel) -> Option<Ref<Metadata>> {
+    let module_list = model.module_list.as_ref()?;
+    Some(array(module_list.by_addr().map(|module_info| {
+        let mut entries = vec![
+            ("name", string(&module_info.name)),
+            ("base_address", unsigned(module_info.base_address())),
+            ("size", unsigned(module_info.size())),
+            ("checksum", unsigned(module_info.raw.checksum)),
+            ("timestamp", unsigned(module_info.raw.time_date_stamp)),
+        ];
+        if let Some(version) = module_info.version() {
+            entries.push(("version", string(version)));
+        }
+        if let Some(debug_file) = module_info.debug_file() {
+            entries.push(("debug_file", string(debug_file)));
+        }
+        if let Some(debug_id) = module_info.debug_identifier() {
+            entries.push(("debug_id", string(debug_id.breakpad().to_string())));
+        }
+        if let Some(code_id) = module_info.code_identifier() {
+            entries.push(("code_id", string(code_id.to_string())));
+        }
+        object(entries)
+    })))
+}
+
+fn unloaded_modules_metadata(model: &MinidumpModel) -> Option<Ref<Metadata>> {
+    let unloaded_module_list = model.unloaded_module_list.as_ref()?;
+    Some(array(unloaded_module_list.iter().map(
+        |unloaded_module_info| {
+            object(vec![
+                ("name", string(&unloaded_module_info.name)),
+                (
+                    "base_address",
+                    unsigned(unloaded_module_info.base_address()),
+                ),
+                ("size", unsigned(unloaded_module_info.size())),
+            ])
+        },
+    )))
+}
+
+/// The memory regions, as an object of parallel arrays with one element per region, rather than an
+/// array of objects: full memory dumps can have hundreds of thousands of regions, and a metadata
+/// object for each of them makes the database much larger and slower to save.
+fn memory_info_metadata(model: &MinidumpModel) -> Option<Ref<Metadata>> {
+    let memory_info_list = model.memory_info_list()?;
+    let column = |field: fn(&MINIDUMP_MEMORY_INFO) -> u64| -> Ref<Metadata> {
+        Ref::<Metadata>::from(
+            memory_info_list
+                .iter()
+                .map(|memory_info| field(&memory_info.raw))
+                .collect::<Vec<u64>>(),
+        )
+    };
+    Some(object(vec![
+        ("base_address", column(|raw| raw.base_address)),
+        ("allocation_base", column(|raw| raw.allocation_base)),
+        (
+            "allocation_protection",
+            column(|raw| raw.allocation_protection as u64),
+        ),
+        ("region_size", column(|raw| raw.region_size)),
+        ("state", column(|raw| raw.state as u64)),
+        ("protection", column(|raw| raw.protection as u64)),
+        ("type", column(|raw| raw._type as u64)),
+    ]))
+}
+
+/// Store the parsed contents of the minidump in the view's metadata, so that they are saved
+/// into the database along with the analysis, and can be queried by scripts and other plugins.
+///
+/// Each part is stored under its own key (e.g. `minidump.threads`), and is left out
+/// if the minidump doesn't have the stream it comes from.
+pub fn store_view_metadata(view: &BinaryView, model: &MinidumpModel) {
+    view.store_metadata(
+        &format!("{}.schema_version", METADATA_KEY_PREFIX),
+        unsigned(METADATA_SCHEMA_VERSION),
+        false,
+    );
+    let parts = [
+        ("system_info", system_info_metadata(model)),
+        ("threads", threads_metadata(model)),
+        ("exception", exception_metadata(model)),
+        ("modules", modules_metadata(model)),
+        ("unloaded_modules", unloaded_modules_metadata(model)),
+        ("memory_info", memory_info_metadata(model)),
+    ];
+    for (name, value) in parts {
+        let Some(value) = value else {
+            continue;
+        };
+        let key = format!("{}.{}", METADATA_KEY_PREFIX, name);
+        debug!("Storing {} in the view's metadata", key);
+        view.store_metadata(&key, value, false);
+    }
+}